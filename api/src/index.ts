@@ -337,30 +337,40 @@ export class LendingMarket {
     liquidityPubkey: PublicKey,
     collateralPubkey: PublicKey,
     owner: PublicKey,
+    index = 0,
   ): Promise<{ tx: Transaction; pubkey: PublicKey }> {
     const [obligationAuthority] = await PublicKey.findProgramAddress(
-      [
-        owner.toBuffer(),
-        this.pubkey.toBuffer(),
-        liquidityPubkey.toBuffer(),
-        collateralPubkey.toBuffer(),
-      ],
+      [owner.toBuffer(), this.pubkey.toBuffer()],
       this.programId,
     )
 
+    // The first sub-account keeps the seed used before sub-accounts were introduced
     const obligationPubkey = await PublicKey.createWithSeed(
       obligationAuthority,
-      'obligation',
+      index === 0 ? 'obligation' : `obligation${index}`,
+      this.programId,
+    )
+
+    const [pairAuthority] = await PublicKey.findProgramAddress(
+      [
+        this.pubkey.toBuffer(),
+        liquidityPubkey.toBuffer(),
+        collateralPubkey.toBuffer(),
+        Buffer.from('pair'),
+      ],
       this.programId,
     )
+    const pairPubkey = await PublicKey.createWithSeed(pairAuthority, 'pair', this.programId)
 
     const tx = new Transaction().add(
       Instruction.createObligation({
         programId: this.programId,
+        index,
         market: this.pubkey,
         obligation: obligationPubkey,
         liquidity: liquidityPubkey,
         collateral: collateralPubkey,
+        pair: pairPubkey,
         obligationAuthority,
         owner,
       }),
@@ -373,11 +383,13 @@ export class LendingMarket {
     liquidityPubkey: PublicKey,
     collateralPubkey: PublicKey,
     payer = this.payer,
+    index = 0,
   ): Promise<PublicKey> {
     const { tx, pubkey } = await this.createObligationTx(
       liquidityPubkey,
       collateralPubkey,
       payer.publicKey,
+      index,
     )
 
     const signature = await sendAndConfirmTransaction(this.connection, tx, [payer])
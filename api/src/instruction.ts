@@ -99,29 +99,34 @@ export const liquidityWithdraw = ({
 }
 
 export type CreateObligationParams = BaseInstructionParams & {
+  index?: number
   market: PublicKey
   obligation: PublicKey
   liquidity: PublicKey
   collateral: PublicKey
+  pair: PublicKey
   obligationAuthority: PublicKey
   owner: PublicKey
 }
 export const createObligation = ({
   programId,
+  index = 0,
   market,
   obligation,
   liquidity,
   collateral,
+  pair,
   obligationAuthority,
   owner,
 }: CreateObligationParams) => {
-  const data = encodeData(MarketInsructionLayouts.CreateObligation)
+  const data = encodeData(MarketInsructionLayouts.CreateObligation, { index })
 
   return new TransactionInstruction({
     keys: [
       { pubkey: obligation, isSigner: false, isWritable: true },
       { pubkey: liquidity, isSigner: false, isWritable: false },
       { pubkey: collateral, isSigner: false, isWritable: false },
+      { pubkey: pair, isSigner: false, isWritable: false },
       { pubkey: market, isSigner: false, isWritable: false },
       { pubkey: obligationAuthority, isSigner: false, isWritable: false },
       { pubkey: owner, isSigner: true, isWritable: false },
@@ -5,39 +5,122 @@ export const InstructionLayout = BufferLayout.u8('instruction')
 
 export const MarketLayout = BufferLayout.struct([
   BufferLayout.u8('version'),
+  BufferLayout.blob(7, 'padding'),
   BaseLayout.publicKey('owner'),
   BaseLayout.uint64('liquidity_tokens'),
   BaseLayout.uint64('collateral_tokens'),
+  BaseLayout.publicKey('guardian'),
+  BaseLayout.uint64('paused'),
+  BaseLayout.uint64('paused_at'),
 ])
 
 export const LiquidityLayout = BufferLayout.struct([
   BufferLayout.u8('version'),
   BufferLayout.u8('status'),
+  BufferLayout.u8('category'),
+  BufferLayout.blob(5, 'padding'),
   BaseLayout.publicKey('market'),
   BaseLayout.publicKey('token_mint'),
   BaseLayout.publicKey('token_account'),
   BaseLayout.publicKey('pool_mint'),
   BaseLayout.uint64('amount_borrowed'),
+  BaseLayout.publicKey('insurance_account'),
+  BaseLayout.uint64('reserve_factor'),
+  BaseLayout.uint64('insurance_withdraw_amount'),
+  BaseLayout.uint64('insurance_withdraw_unlock_at'),
+  BaseLayout.uint64('max_utilization'),
+  BaseLayout.uint64('queue_amount_requested'),
+  BaseLayout.uint64('queue_amount_claimed'),
+  BaseLayout.uint64('outflow_limit'),
+  BaseLayout.uint64('outflow_window'),
+  BaseLayout.uint64('outflow_window_start'),
+  BaseLayout.uint64('outflow_amount'),
+  BaseLayout.uint64('optimal_utilization'),
+  BaseLayout.uint64('borrow_rate_min'),
+  BaseLayout.uint64('borrow_rate_optimal'),
+  BaseLayout.uint64('borrow_rate_max'),
+  BufferLayout.blob(24, 'cumulative_borrow_rate'),
+  BaseLayout.uint64('interest_slot'),
+  BaseLayout.uint64('amount_reserve'),
 ])
 
 export const CollateralLayout = BufferLayout.struct([
   BufferLayout.u8('version'),
   BufferLayout.u8('status'),
+  BufferLayout.u8('isolated'),
+  BufferLayout.u8('category'),
+  BufferLayout.blob(4, 'padding'),
   BaseLayout.publicKey('market'),
   BaseLayout.publicKey('token_mint'),
   BaseLayout.publicKey('token_account'),
   BaseLayout.uint64('ratio_initial'),
   BaseLayout.uint64('ratio_healthy'),
+  BaseLayout.uint64('debt_ceiling'),
+  BaseLayout.uint64('isolated_debt'),
+  BufferLayout.seq(BaseLayout.publicKey(), 4, 'isolated_liquidities'),
+  BaseLayout.uint64('liquidation_bonus_start'),
+  BaseLayout.uint64('liquidation_bonus_max'),
+  BaseLayout.uint64('liquidation_bonus_slots'),
+  BaseLayout.uint64('liquidation_close_factor'),
 ])
 
 export const ObligationLayout = BufferLayout.struct([
   BufferLayout.u8('version'),
+  BufferLayout.u8('isolated'),
+  BufferLayout.u8('category'),
+  BufferLayout.blob(3, 'padding'),
+  BufferLayout.u16('index'),
   BaseLayout.publicKey('market'),
   BaseLayout.publicKey('owner'),
   BaseLayout.publicKey('liquidity'),
   BaseLayout.publicKey('collateral'),
   BaseLayout.uint64('amount_liquidity_borrowed'),
   BaseLayout.uint64('amount_collateral_deposited'),
+  BaseLayout.uint64('unhealthy_since'),
+  BaseLayout.publicKey('position_mint'),
+  BaseLayout.publicKey('operator'),
+  BaseLayout.uint64('unhealthy_ratio'),
+  BufferLayout.blob(24, 'cumulative_borrow_rate'),
+])
+
+export const PairLayout = BufferLayout.struct([
+  BufferLayout.u8('version'),
+  BufferLayout.u8('borrow_enabled'),
+  BufferLayout.blob(6, 'padding'),
+  BaseLayout.publicKey('market'),
+  BaseLayout.publicKey('liquidity'),
+  BaseLayout.publicKey('collateral'),
+  BaseLayout.uint64('ratio_initial'),
+  BaseLayout.uint64('ratio_healthy'),
+])
+
+export const CategoryLayout = BufferLayout.struct([
+  BufferLayout.u8('version'),
+  BufferLayout.u8('id'),
+  BufferLayout.blob(6, 'padding'),
+  BaseLayout.publicKey('market'),
+  BaseLayout.uint64('ratio_initial'),
+  BaseLayout.uint64('ratio_healthy'),
+  BufferLayout.blob(32, 'name'),
+])
+
+export const WithdrawalTicketLayout = BufferLayout.struct([
+  BufferLayout.u8('version'),
+  BufferLayout.blob(7, 'padding'),
+  BaseLayout.publicKey('liquidity'),
+  BaseLayout.publicKey('owner'),
+  BaseLayout.uint64('amount'),
+  BaseLayout.uint64('pool_tokens_amount'),
+  BaseLayout.uint64('queue_offset'),
+])
+
+export const DelegationLayout = BufferLayout.struct([
+  BufferLayout.u8('version'),
+  BufferLayout.blob(7, 'padding'),
+  BaseLayout.publicKey('obligation'),
+  BaseLayout.publicKey('owner'),
+  BaseLayout.publicKey('delegate'),
+  BaseLayout.uint64('allowance'),
 ])
 
 export const MarketInsructionLayouts = {
@@ -51,7 +134,7 @@ export const MarketInsructionLayouts = {
   },
   CreateObligation: {
     index: 7,
-    layout: BufferLayout.struct([InstructionLayout]),
+    layout: BufferLayout.struct([InstructionLayout, BufferLayout.u16('index')]),
   },
   ObligationCollateralDeposit: {
     index: 8,
@@ -1,6 +1,14 @@
 import { u64 } from '@solana/spl-token'
 import { PublicKey } from '@solana/web3.js'
-import { CollateralLayout, LiquidityLayout, MarketLayout, ObligationLayout } from './layout'
+import {
+  CollateralLayout,
+  DelegationLayout,
+  LiquidityLayout,
+  MarketLayout,
+  ObligationLayout,
+  PairLayout,
+  WithdrawalTicketLayout,
+} from './layout'
 
 export class Market {
   constructor(
@@ -8,16 +16,23 @@ export class Market {
     public owner: PublicKey,
     public liquidityTokens: u64,
     public collateralTokens: u64,
+    public guardian: PublicKey,
+    public paused: u64,
+    public pausedAt: u64,
   ) {}
 
   static from(buffer: Buffer): Market {
-    const { version, owner, liquidity_tokens, collateral_tokens } = MarketLayout.decode(buffer)
+    const { version, owner, liquidity_tokens, collateral_tokens, guardian, paused, paused_at } =
+      MarketLayout.decode(buffer)
 
     return {
       version,
       owner: new PublicKey(owner),
       liquidityTokens: u64.fromBuffer(liquidity_tokens),
       collateralTokens: u64.fromBuffer(collateral_tokens),
+      guardian: new PublicKey(guardian),
+      paused: u64.fromBuffer(paused),
+      pausedAt: u64.fromBuffer(paused_at),
     }
   }
 }
@@ -37,11 +52,34 @@ export class Liquidity {
     public tokenAccount: PublicKey,
     public poolMint: PublicKey,
     public amountBorrowed: u64,
+    public insuranceAccount: PublicKey,
+    public reserveFactor: u64,
+    public maxUtilization: u64,
+    public optimalUtilization: u64,
+    public borrowRateMin: u64,
+    public borrowRateOptimal: u64,
+    public borrowRateMax: u64,
+    public amountReserve: u64,
   ) {}
 
   static from(buffer: Buffer): Liquidity {
-    const { version, status, market, token_mint, token_account, pool_mint, amount_borrowed } =
-      LiquidityLayout.decode(buffer)
+    const {
+      version,
+      status,
+      market,
+      token_mint,
+      token_account,
+      pool_mint,
+      amount_borrowed,
+      insurance_account,
+      reserve_factor,
+      max_utilization,
+      optimal_utilization,
+      borrow_rate_min,
+      borrow_rate_optimal,
+      borrow_rate_max,
+      amount_reserve,
+    } = LiquidityLayout.decode(buffer)
 
     return {
       version,
@@ -51,6 +89,14 @@ export class Liquidity {
       tokenAccount: new PublicKey(token_account),
       poolMint: new PublicKey(pool_mint),
       amountBorrowed: u64.fromBuffer(amount_borrowed),
+      insuranceAccount: new PublicKey(insurance_account),
+      reserveFactor: u64.fromBuffer(reserve_factor),
+      maxUtilization: u64.fromBuffer(max_utilization),
+      optimalUtilization: u64.fromBuffer(optimal_utilization),
+      borrowRateMin: u64.fromBuffer(borrow_rate_min),
+      borrowRateOptimal: u64.fromBuffer(borrow_rate_optimal),
+      borrowRateMax: u64.fromBuffer(borrow_rate_max),
+      amountReserve: u64.fromBuffer(amount_reserve),
     }
   }
 }
@@ -70,11 +116,26 @@ export class Collateral {
     public tokenAccount: PublicKey,
     public ratioInitial: u64,
     public ratioHealthy: u64,
+    public liquidationBonusStart: u64,
+    public liquidationBonusMax: u64,
+    public liquidationBonusSlots: u64,
+    public liquidationCloseFactor: u64,
   ) {}
 
   static from(buffer: Buffer): Collateral {
-    const { version, status, market, token_mint, token_account, ratio_initial, ratio_healthy } =
-      CollateralLayout.decode(buffer)
+    const {
+      version,
+      status,
+      market,
+      token_mint,
+      token_account,
+      ratio_initial,
+      ratio_healthy,
+      liquidation_bonus_start,
+      liquidation_bonus_max,
+      liquidation_bonus_slots,
+      liquidation_close_factor,
+    } = CollateralLayout.decode(buffer)
 
     return {
       version,
@@ -84,6 +145,10 @@ export class Collateral {
       tokenAccount: new PublicKey(token_account),
       ratioInitial: u64.fromBuffer(ratio_initial),
       ratioHealthy: u64.fromBuffer(ratio_healthy),
+      liquidationBonusStart: u64.fromBuffer(liquidation_bonus_start),
+      liquidationBonusMax: u64.fromBuffer(liquidation_bonus_max),
+      liquidationBonusSlots: u64.fromBuffer(liquidation_bonus_slots),
+      liquidationCloseFactor: u64.fromBuffer(liquidation_close_factor),
     }
   }
 }
@@ -91,33 +156,122 @@ export class Collateral {
 export class Obligation {
   constructor(
     public version: number,
+    public index: number,
     public market: PublicKey,
     public owner: PublicKey,
     public liquidity: PublicKey,
     public collateral: PublicKey,
     public amountLiquidityBorrowed: u64,
     public amountCollateralDeposited: u64,
+    public unhealthySince: u64,
+    public positionMint: PublicKey,
+    public operator: PublicKey,
+    public unhealthyRatio: u64,
   ) {}
 
   static from(buffer: Buffer): Obligation {
     const {
       version,
+      index,
       market,
       owner,
       liquidity,
       collateral,
       amount_liquidity_borrowed,
       amount_collateral_deposited,
+      unhealthy_since,
+      position_mint,
+      operator,
+      unhealthy_ratio,
     } = ObligationLayout.decode(buffer)
 
     return {
       version,
+      index,
       market: new PublicKey(market),
       owner: new PublicKey(owner),
       liquidity: new PublicKey(liquidity),
       collateral: new PublicKey(collateral),
       amountLiquidityBorrowed: u64.fromBuffer(amount_liquidity_borrowed),
       amountCollateralDeposited: u64.fromBuffer(amount_collateral_deposited),
+      unhealthySince: u64.fromBuffer(unhealthy_since),
+      positionMint: new PublicKey(position_mint),
+      operator: new PublicKey(operator),
+      unhealthyRatio: u64.fromBuffer(unhealthy_ratio),
+    }
+  }
+}
+
+export class Pair {
+  constructor(
+    public version: number,
+    public borrowEnabled: boolean,
+    public market: PublicKey,
+    public liquidity: PublicKey,
+    public collateral: PublicKey,
+    public ratioInitial: u64,
+    public ratioHealthy: u64,
+  ) {}
+
+  static from(buffer: Buffer): Pair {
+    const { version, borrow_enabled, market, liquidity, collateral, ratio_initial, ratio_healthy } =
+      PairLayout.decode(buffer)
+
+    return {
+      version,
+      borrowEnabled: borrow_enabled === 1,
+      market: new PublicKey(market),
+      liquidity: new PublicKey(liquidity),
+      collateral: new PublicKey(collateral),
+      ratioInitial: u64.fromBuffer(ratio_initial),
+      ratioHealthy: u64.fromBuffer(ratio_healthy),
+    }
+  }
+}
+
+export class WithdrawalTicket {
+  constructor(
+    public version: number,
+    public liquidity: PublicKey,
+    public owner: PublicKey,
+    public amount: u64,
+    public poolTokensAmount: u64,
+    public queueOffset: u64,
+  ) {}
+
+  static from(buffer: Buffer): WithdrawalTicket {
+    const { version, liquidity, owner, amount, pool_tokens_amount, queue_offset } =
+      WithdrawalTicketLayout.decode(buffer)
+
+    return {
+      version,
+      liquidity: new PublicKey(liquidity),
+      owner: new PublicKey(owner),
+      amount: u64.fromBuffer(amount),
+      poolTokensAmount: u64.fromBuffer(pool_tokens_amount),
+      queueOffset: u64.fromBuffer(queue_offset),
+    }
+  }
+}
+
+export class Delegation {
+  constructor(
+    public version: number,
+    public obligation: PublicKey,
+    public owner: PublicKey,
+    public delegate: PublicKey,
+    public allowance: u64,
+  ) {}
+
+  static from(buffer: Buffer): Delegation {
+    const { version, obligation, owner, delegate, allowance } = DelegationLayout.decode(buffer)
+
+    return {
+      version,
+      obligation: new PublicKey(obligation),
+      owner: new PublicKey(owner),
+      delegate: new PublicKey(delegate),
+      allowance: u64.fromBuffer(allowance),
     }
   }
 }
@@ -1,11 +1,16 @@
 use clap::{
-    arg_enum, crate_description, crate_name, crate_version, value_t, App, AppSettings, Arg,
-    SubCommand,
+    arg_enum, crate_description, crate_name, crate_version, value_t, values_t, App, AppSettings,
+    Arg, SubCommand,
 };
 use everlend_lending::{
     find_program_address, instruction,
-    state::{ui_ratio_to_ratio, Collateral, CollateralStatus, Liquidity, LiquidityStatus, Market},
+    state::{
+        ui_ratio_to_ratio, Collateral, CollateralStatus, Liquidity, LiquidityStatus, Market,
+        Obligation, PAUSE_ALL, PAUSE_BORROW, PAUSE_DEPOSIT, PAUSE_LIQUIDATE, PAUSE_WITHDRAW,
+    },
+    token::{get_token_account_len, is_supported_token_program, unpack_token_account},
 };
+use solana_account_decoder::UiAccountEncoding;
 use solana_clap_utils::{
     fee_payer::fee_payer_arg,
     input_parsers::{keypair_of, pubkey_of, value_of},
@@ -14,7 +19,11 @@ use solana_clap_utils::{
     },
     keypair::signer_from_path,
 };
-use solana_client::rpc_client::RpcClient;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
 use solana_program::{
     native_token::lamports_to_sol, program_pack::Pack, pubkey::Pubkey, system_instruction,
 };
@@ -64,6 +73,29 @@ impl From<ArgTokenStatus> for CollateralStatus {
     }
 }
 
+arg_enum! {
+    #[derive(Debug)]
+    pub enum ArgOperation {
+        Deposit,
+        Withdraw,
+        Borrow,
+        Liquidate,
+        All,
+    }
+}
+
+impl From<ArgOperation> for u64 {
+    fn from(other: ArgOperation) -> u64 {
+        match other {
+            ArgOperation::Deposit => PAUSE_DEPOSIT,
+            ArgOperation::Withdraw => PAUSE_WITHDRAW,
+            ArgOperation::Borrow => PAUSE_BORROW,
+            ArgOperation::Liquidate => PAUSE_LIQUIDATE,
+            ArgOperation::All => PAUSE_ALL,
+        }
+    }
+}
+
 macro_rules! unique_signers {
     ($vec:ident) => {
         $vec.sort_by_key(|l| l.pubkey());
@@ -152,6 +184,28 @@ fn command_market_info(config: &Config, market_pubkey: &Pubkey) -> CommandResult
         let liquidity = Liquidity::unpack(&liquidity_account.data)?;
 
         println!("{:#?}", liquidity);
+
+        if liquidity.has_insurance() {
+            let insurance_account = config
+                .rpc_client
+                .get_account(&liquidity.insurance_account)?;
+            let insurance_amount = unpack_token_account(&insurance_account.data)?.amount;
+
+            if liquidity.amount_borrowed > 0 {
+                println!(
+                    "Insurance coverage: {} ({:.2}% of borrowed)",
+                    insurance_amount,
+                    insurance_amount as f64 / liquidity.amount_borrowed as f64 * 100.0
+                );
+            } else {
+                println!(
+                    "Insurance coverage: {} (nothing borrowed)",
+                    insurance_amount
+                );
+            }
+        } else {
+            println!("Insurance coverage: none");
+        }
     }
 
     println!("Collateral tokens:");
@@ -170,6 +224,58 @@ fn command_market_info(config: &Config, market_pubkey: &Pubkey) -> CommandResult
     Ok(None)
 }
 
+/// Offset of the obligation market following the version, flags, padding and index
+const OBLIGATION_MARKET_OFFSET: usize = 8;
+/// Offset of the obligation owner following the market
+const OBLIGATION_OWNER_OFFSET: usize = OBLIGATION_MARKET_OFFSET + 32;
+
+fn pubkey_filter(offset: usize, pubkey: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp {
+        offset,
+        bytes: MemcmpEncodedBytes::Binary(pubkey.to_string()),
+        encoding: None,
+    })
+}
+
+fn command_obligations(
+    config: &Config,
+    market_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> CommandResult {
+    let accounts = config.rpc_client.get_program_accounts_with_config(
+        &everlend_lending::id(),
+        RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(Obligation::LEN as u64),
+                pubkey_filter(OBLIGATION_MARKET_OFFSET, market_pubkey),
+                pubkey_filter(OBLIGATION_OWNER_OFFSET, owner_pubkey),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: None,
+        },
+    )?;
+
+    let mut obligations = Vec::with_capacity(accounts.len());
+    for (obligation_pubkey, account) in accounts {
+        obligations.push((obligation_pubkey, Obligation::unpack(&account.data)?));
+    }
+    obligations.sort_by_key(|(_, obligation)| obligation.index);
+
+    println!("Owner: {}", owner_pubkey);
+    println!("Sub-accounts:");
+    for (obligation_pubkey, obligation) in obligations {
+        println!("{} {}", obligation.index, obligation_pubkey);
+        if config.verbose {
+            println!("{:#?}", obligation);
+        }
+    }
+
+    Ok(None)
+}
+
 fn command_create_liquidity_token(
     config: &Config,
     market_pubkey: &Pubkey,
@@ -178,6 +284,14 @@ fn command_create_liquidity_token(
     let market_account = config.rpc_client.get_account(&market_pubkey)?;
     let market = Market::unpack(&market_account.data)?;
 
+    // Token program is SPL Token or Token-2022 depending on the mint
+    let token_mint_account = config.rpc_client.get_account(&token_mint)?;
+    let token_program_id = token_mint_account.owner;
+    if !is_supported_token_program(&token_program_id) {
+        return Err(format!("Token program {} is not supported", token_program_id).into());
+    }
+    let token_account_len = get_token_account_len(&token_mint_account.data)?;
+
     // Generate new accounts
     let token_account = Keypair::new();
     let pool_mint = Keypair::new();
@@ -196,7 +310,7 @@ fn command_create_liquidity_token(
 
     let token_account_balance = config
         .rpc_client
-        .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
+        .get_minimum_balance_for_rent_exemption(token_account_len)?;
     let pool_mint_balance = config
         .rpc_client
         .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
@@ -209,15 +323,15 @@ fn command_create_liquidity_token(
                 &config.fee_payer.pubkey(),
                 &token_account.pubkey(),
                 token_account_balance,
-                spl_token::state::Account::LEN as u64,
-                &spl_token::id(),
+                token_account_len as u64,
+                &token_program_id,
             ),
             system_instruction::create_account(
                 &config.fee_payer.pubkey(),
                 &pool_mint.pubkey(),
                 pool_mint_balance,
                 spl_token::state::Mint::LEN as u64,
-                &spl_token::id(),
+                &token_program_id,
             ),
             instruction::create_liquidity_token(
                 &everlend_lending::id(),
@@ -227,6 +341,7 @@ fn command_create_liquidity_token(
                 &pool_mint.pubkey(),
                 &market_pubkey,
                 &config.owner.pubkey(),
+                &token_program_id,
             )?,
         ],
         Some(&config.fee_payer.pubkey()),
@@ -261,6 +376,14 @@ fn command_create_collateral_token(
     let market_account = config.rpc_client.get_account(&market_pubkey)?;
     let market = Market::unpack(&market_account.data)?;
 
+    // Token program is SPL Token or Token-2022 depending on the mint
+    let token_mint_account = config.rpc_client.get_account(&token_mint)?;
+    let token_program_id = token_mint_account.owner;
+    if !is_supported_token_program(&token_program_id) {
+        return Err(format!("Token program {} is not supported", token_program_id).into());
+    }
+    let token_account_len = get_token_account_len(&token_mint_account.data)?;
+
     // Generate new accounts
     let token_account = Keypair::new();
     let ratio_initial = ui_ratio_to_ratio(ui_ratio_initial);
@@ -283,7 +406,7 @@ fn command_create_collateral_token(
 
     let token_account_balance = config
         .rpc_client
-        .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
+        .get_minimum_balance_for_rent_exemption(token_account_len)?;
 
     let total_rent_free_balances = token_account_balance;
 
@@ -293,8 +416,8 @@ fn command_create_collateral_token(
                 &config.fee_payer.pubkey(),
                 &token_account.pubkey(),
                 token_account_balance,
-                spl_token::state::Account::LEN as u64,
-                &spl_token::id(),
+                token_account_len as u64,
+                &token_program_id,
             ),
             instruction::create_collateral_token(
                 &everlend_lending::id(),
@@ -305,6 +428,7 @@ fn command_create_collateral_token(
                 &token_account.pubkey(),
                 &market_pubkey,
                 &config.owner.pubkey(),
+                &token_program_id,
             )?,
         ],
         Some(&config.fee_payer.pubkey()),
@@ -433,6 +557,89 @@ fn command_update_collateral_token(
     Ok(Some(tx))
 }
 
+fn command_set_guardian(
+    config: &Config,
+    market_pubkey: &Pubkey,
+    guardian_pubkey: &Pubkey,
+) -> CommandResult {
+    println!("Market: {}", market_pubkey);
+    println!("New guardian: {}", guardian_pubkey);
+
+    let mut tx = Transaction::new_with_payer(
+        &[instruction::update_market_guardian(
+            &everlend_lending::id(),
+            market_pubkey,
+            &config.owner.pubkey(),
+            guardian_pubkey,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&tx.message()))?;
+
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+
+    unique_signers!(signers);
+    tx.sign(&signers, recent_blockhash);
+
+    Ok(Some(tx))
+}
+
+fn command_pause_market(config: &Config, market_pubkey: &Pubkey, operations: u64) -> CommandResult {
+    println!("Market: {}", market_pubkey);
+    println!("Pause operations: {:#b}", operations);
+
+    let mut tx = Transaction::new_with_payer(
+        &[instruction::pause_market(
+            &everlend_lending::id(),
+            operations,
+            market_pubkey,
+            &config.owner.pubkey(),
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&tx.message()))?;
+
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+
+    unique_signers!(signers);
+    tx.sign(&signers, recent_blockhash);
+
+    Ok(Some(tx))
+}
+
+fn command_unpause_market(
+    config: &Config,
+    market_pubkey: &Pubkey,
+    operations: u64,
+) -> CommandResult {
+    println!("Market: {}", market_pubkey);
+    println!("Unpause operations: {:#b}", operations);
+
+    let mut tx = Transaction::new_with_payer(
+        &[instruction::unpause_market(
+            &everlend_lending::id(),
+            operations,
+            market_pubkey,
+            &config.owner.pubkey(),
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&tx.message()))?;
+
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+
+    unique_signers!(signers);
+    tx.sign(&signers, recent_blockhash);
+
+    Ok(Some(tx))
+}
+
 fn main() {
     let matches = App::new(crate_name!())
         .about(crate_description!())
@@ -513,6 +720,27 @@ fn main() {
                         .help("Market pubkey"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("obligations")
+                .about("Print out obligation sub-accounts of the owner in the market")
+                .arg(
+                    Arg::with_name("market_pubkey")
+                        .long("market")
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Market pubkey"),
+                )
+                .arg(
+                    Arg::with_name("owner_pubkey")
+                        .long("obligation-owner")
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .help("Obligation owner pubkey [default: owner]"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("create-liquidity")
                 .about("Add a liquidity token")
@@ -671,6 +899,74 @@ fn main() {
                         .help("Ratio healthy"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("set-guardian")
+                .about("Set the market guardian allowed to pause operations")
+                .arg(
+                    Arg::with_name("market_pubkey")
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .index(1)
+                        .help("Market pubkey"),
+                )
+                .arg(
+                    Arg::with_name("guardian_pubkey")
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .index(2)
+                        .help("Guardian pubkey, the default pubkey removes the guardian"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pause-market")
+                .about("Pause operations market-wide, signed by the guardian or the owner")
+                .arg(
+                    Arg::with_name("market_pubkey")
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .index(1)
+                        .help("Market pubkey"),
+                )
+                .arg(
+                    Arg::with_name("operations")
+                        .value_name("OPERATION")
+                        .takes_value(true)
+                        .multiple(true)
+                        .required(true)
+                        .possible_values(&ArgOperation::variants())
+                        .index(2)
+                        .help("Operations to pause."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("unpause-market")
+                .about("Unpause operations market-wide, signed by the owner")
+                .arg(
+                    Arg::with_name("market_pubkey")
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .index(1)
+                        .help("Market pubkey"),
+                )
+                .arg(
+                    Arg::with_name("operations")
+                        .value_name("OPERATION")
+                        .takes_value(true)
+                        .multiple(true)
+                        .required(true)
+                        .possible_values(&ArgOperation::variants())
+                        .index(2)
+                        .help("Operations to unpause."),
+                ),
+        )
         .get_matches();
 
     let mut wallet_manager = None;
@@ -727,6 +1023,12 @@ fn main() {
             let market_pubkey = pubkey_of(arg_matches, "market_pubkey").unwrap();
             command_market_info(&config, &market_pubkey)
         }
+        ("obligations", Some(arg_matches)) => {
+            let market_pubkey = pubkey_of(arg_matches, "market_pubkey").unwrap();
+            let owner_pubkey =
+                pubkey_of(arg_matches, "owner_pubkey").unwrap_or_else(|| config.owner.pubkey());
+            command_obligations(&config, &market_pubkey, &owner_pubkey)
+        }
         ("create-liquidity", Some(arg_matches)) => {
             let market_pubkey = pubkey_of(arg_matches, "market_pubkey").unwrap();
             let token_mint = pubkey_of(arg_matches, "token_mint").unwrap();
@@ -775,6 +1077,27 @@ fn main() {
                 ratio_healthy,
             )
         }
+        ("set-guardian", Some(arg_matches)) => {
+            let market_pubkey = pubkey_of(arg_matches, "market_pubkey").unwrap();
+            let guardian_pubkey = pubkey_of(arg_matches, "guardian_pubkey").unwrap();
+            command_set_guardian(&config, &market_pubkey, &guardian_pubkey)
+        }
+        ("pause-market", Some(arg_matches)) => {
+            let market_pubkey = pubkey_of(arg_matches, "market_pubkey").unwrap();
+            let operations = values_t!(arg_matches, "operations", ArgOperation)
+                .unwrap()
+                .into_iter()
+                .fold(0, |operations, operation| operations | u64::from(operation));
+            command_pause_market(&config, &market_pubkey, operations)
+        }
+        ("unpause-market", Some(arg_matches)) => {
+            let market_pubkey = pubkey_of(arg_matches, "market_pubkey").unwrap();
+            let operations = values_t!(arg_matches, "operations", ArgOperation)
+                .unwrap()
+                .into_iter()
+                .fold(0, |operations, operation| operations | u64::from(operation));
+            command_unpause_market(&config, &market_pubkey, operations)
+        }
         _ => unreachable!(),
     }
     .and_then(|tx| {
@@ -20,6 +20,69 @@ pub enum LendingError {
     /// Collateral health check failed, returned if user borrows too much or withdraws too much collateral.
     #[error("Collateral health check failed")]
     CollateralHealthCheckFailed,
+    /// The mint has Token-2022 extensions which aren't supported by the program.
+    #[error("Unsupported mint extension")]
+    UnsupportedMintExtension,
+    /// The exchange rate moved beyond the limit set by the user.
+    #[error("Exchange rate slippage exceeded")]
+    SlippageExceeded,
+    /// The obligation still has collateral to cover the debt.
+    #[error("Obligation is not insolvent")]
+    ObligationNotInsolvent,
+    /// The insurance withdrawal wasn't requested.
+    #[error("Insurance withdrawal not requested")]
+    InsuranceWithdrawNotRequested,
+    /// The insurance withdrawal is still under the timelock.
+    #[error("Insurance withdrawal locked")]
+    InsuranceWithdrawLocked,
+    /// The liquidity isn't approved for the isolated collateral.
+    #[error("Liquidity not approved for the isolated collateral")]
+    IsolatedLiquidityNotApproved,
+    /// The isolated collateral debt ceiling is exceeded.
+    #[error("Debt ceiling exceeded")]
+    DebtCeilingExceeded,
+    /// Borrowing is disabled for the collateral and liquidity pair.
+    #[error("Pair borrow disabled")]
+    PairBorrowDisabled,
+    /// The category doesn't exist, or the obligation assets don't share it.
+    #[error("Invalid category")]
+    InvalidCategory,
+    /// The liquidity utilization exceeds the maximum.
+    #[error("Utilization exceeded")]
+    UtilizationExceeded,
+    /// The liquidity didn't fill the withdrawal ticket yet.
+    #[error("Withdrawal ticket not filled")]
+    WithdrawalTicketNotFilled,
+    /// The operation is paused market-wide.
+    #[error("Operation paused")]
+    OperationPaused,
+    /// The liquidity outflow exceeds the limit of the window.
+    #[error("Outflow limit exceeded")]
+    OutflowLimitExceeded,
+    /// The obligation is within the healthy collateralization ratio.
+    #[error("Obligation is healthy")]
+    ObligationHealthy,
+    /// The unhealthy obligation wasn't recorded by the health refresh yet.
+    #[error("Liquidation not started")]
+    LiquidationNotStarted,
+    /// The borrow amount exceeds the allowance of the delegate.
+    #[error("Delegation allowance exceeded")]
+    DelegationAllowanceExceeded,
+    /// The obligation ownership is represented by the position mint.
+    #[error("Obligation tokenized")]
+    ObligationTokenized,
+    /// The obligation ownership is not represented by a position mint.
+    #[error("Obligation not tokenized")]
+    ObligationNotTokenized,
+    /// The swapped amount is more than the debt to repay.
+    #[error("Swap amount exceeds debt")]
+    SwapAmountExceedsDebt,
+    /// The account was written with a layout of another program version.
+    #[error("Unsupported state version")]
+    UnsupportedStateVersion,
+    /// The obligation has no debt to write off.
+    #[error("Obligation has no debt")]
+    ObligationHasNoDebt,
 }
 
 impl PrintProgramError for LendingError {
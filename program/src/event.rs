@@ -0,0 +1,439 @@
+//! Events logged by the program for off-chain indexers
+
+use crate::state::{CollateralStatus, LiquidityStatus};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{msg, pubkey::Pubkey};
+
+/// Prefix of the log line carrying a base64 encoded event
+pub const EVENT_LOG_PREFIX: &str = "Event: ";
+
+/// Prefix added by the runtime to the program log lines
+const PROGRAM_LOG_PREFIX: &str = "Program log: ";
+
+/// Updated config of the market account
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub enum ConfigUpdate {
+    /// Liquidity token config
+    Liquidity {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// New status
+        status: LiquidityStatus,
+    },
+    /// Collateral token config
+    Collateral {
+        /// Collateral account
+        collateral: Pubkey,
+        /// New status
+        status: CollateralStatus,
+        /// New initial collateralization ratio
+        ratio_initial: u64,
+        /// New healthy collateralization ratio
+        ratio_healthy: u64,
+    },
+    /// Liquidity insurance config
+    LiquidityInsurance {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Insurance account
+        insurance_account: Pubkey,
+        /// New share of the income transferred to the insurance account
+        reserve_factor: u64,
+    },
+    /// Market guardian
+    MarketGuardian {
+        /// Market account
+        market: Pubkey,
+        /// New guardian, default if removed
+        guardian: Pubkey,
+    },
+    /// Liquidity outflow rate limit
+    LiquidityOutflowLimit {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// New maximum amount of liquidity leaving per window
+        outflow_limit: u64,
+        /// New length of the window in slots
+        outflow_window: u64,
+    },
+    /// Liquidity maximum utilization
+    LiquidityUtilization {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// New maximum share of the total liquidity borrowed
+        max_utilization: u64,
+    },
+    /// Collateral isolation mode
+    CollateralIsolation {
+        /// Collateral account
+        collateral: Pubkey,
+        /// New isolation mode
+        isolated: bool,
+        /// New debt ceiling
+        debt_ceiling: u64,
+        /// Liquidity approved for the isolated obligations
+        liquidities: Vec<Pubkey>,
+    },
+    /// Collateral Dutch-auction liquidation
+    CollateralLiquidation {
+        /// Collateral account
+        collateral: Pubkey,
+        /// New liquidation bonus once the obligation became unhealthy
+        bonus_start: u64,
+        /// New maximum liquidation bonus
+        bonus_max: u64,
+        /// New number of slots the liquidation bonus rises over
+        bonus_slots: u64,
+        /// New maximum fraction of the debt repaid by a single liquidation
+        close_factor: u64,
+    },
+    /// Pair risk parameters
+    Pair {
+        /// Pair account
+        pair: Pubkey,
+        /// Borrowing is enabled
+        borrow_enabled: bool,
+        /// New initial collateralization ratio
+        ratio_initial: u64,
+        /// New healthy collateralization ratio
+        ratio_healthy: u64,
+    },
+    /// Category of the correlated assets
+    Category {
+        /// Category account
+        category: Pubkey,
+        /// Category id
+        id: u8,
+        /// New initial collateralization ratio
+        ratio_initial: u64,
+        /// New healthy collateralization ratio
+        ratio_healthy: u64,
+    },
+    /// Liquidity token category
+    LiquidityCategory {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// New category id
+        category: u8,
+    },
+    /// Collateral token category
+    CollateralCategory {
+        /// Collateral account
+        collateral: Pubkey,
+        /// New category id
+        category: u8,
+    },
+    /// Liquidity utilization-driven borrow rate
+    LiquidityBorrowRate {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// New utilization the borrow rate bends at
+        optimal_utilization: u64,
+        /// New annual borrow rate at zero utilization
+        borrow_rate_min: u64,
+        /// New annual borrow rate at the optimal utilization
+        borrow_rate_optimal: u64,
+        /// New annual borrow rate at the full utilization
+        borrow_rate_max: u64,
+    },
+}
+
+/// Event of the state change
+///
+/// Exchange rate is the amount of liquidity per pool token multiplied by `RATIO_POWER`,
+/// health is the obligation health ratio after the change.
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub enum LendingEvent {
+    /// Liquidity deposited
+    Deposit {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity received
+        amount: u64,
+        /// Amount of pool tokens minted
+        pool_tokens_amount: u64,
+        /// Exchange rate the deposit was made at
+        exchange_rate: u64,
+    },
+    /// Liquidity withdrawn
+    Withdraw {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity transferred
+        amount: u64,
+        /// Amount of pool tokens burned
+        pool_tokens_amount: u64,
+        /// Exchange rate the withdrawal was made at
+        exchange_rate: u64,
+    },
+    /// Liquidity withdrawal queued
+    WithdrawRequested {
+        /// Withdrawal ticket account
+        withdrawal_ticket: Pubkey,
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity to pay out
+        amount: u64,
+        /// Amount of pool tokens burned
+        pool_tokens_amount: u64,
+        /// Amount of liquidity requested by the tickets queued before
+        queue_offset: u64,
+        /// Exchange rate the withdrawal was requested at
+        exchange_rate: u64,
+    },
+    /// Queued liquidity withdrawal paid out
+    WithdrawClaimed {
+        /// Withdrawal ticket account
+        withdrawal_ticket: Pubkey,
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity transferred
+        amount: u64,
+    },
+    /// Liquidity borrowed
+    Borrow {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity borrowed
+        amount: u64,
+        /// Obligation health
+        health: u64,
+    },
+    /// Liquidity repaid
+    Repay {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity repaid
+        amount: u64,
+        /// Obligation health
+        health: u64,
+    },
+    /// Collateral deposited
+    CollateralDeposit {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Collateral account
+        collateral: Pubkey,
+        /// Amount of collateral deposited
+        amount: u64,
+        /// Obligation health
+        health: u64,
+    },
+    /// Collateral withdrawn
+    CollateralWithdraw {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Collateral account
+        collateral: Pubkey,
+        /// Amount of collateral withdrawn
+        amount: u64,
+        /// Obligation health
+        health: u64,
+    },
+    /// Obligation created
+    ObligationCreated {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Obligation owner
+        owner: Pubkey,
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Collateral account
+        collateral: Pubkey,
+        /// Sub-account index among the owner obligations of the pair
+        index: u16,
+    },
+    /// Debt of the obligation without collateral written off
+    BadDebtWrittenOff {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity written off
+        amount: u64,
+        /// Amount of liquidity covered by the insurance
+        insurance_amount: u64,
+        /// Exchange rate before the write off
+        exchange_rate_before: u64,
+        /// Exchange rate after the write off
+        exchange_rate: u64,
+    },
+    /// Insurance account topped up
+    InsuranceTopUp {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity received
+        amount: u64,
+    },
+    /// Insurance withdrawal requested
+    InsuranceWithdrawRequested {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity requested
+        amount: u64,
+        /// Unix timestamp after which the withdrawal is unlocked
+        unlock_at: i64,
+    },
+    /// Insurance withdrawn
+    InsuranceWithdraw {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity transferred
+        amount: u64,
+    },
+    /// Obligation opted into the category or out of it
+    ObligationCategoryUpdated {
+        /// Obligation account
+        obligation: Pubkey,
+        /// New category id
+        category: u8,
+    },
+    /// Outflow of the current window reset by the market owner
+    OutflowReset {
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of liquidity left within the window before the reset
+        outflow_amount: u64,
+    },
+    /// Market operations paused or unpaused
+    PauseUpdated {
+        /// Market account
+        market: Pubkey,
+        /// Bitmask of the paused operations
+        paused: u64,
+    },
+    /// Obligation health refreshed by the crank
+    HealthRefreshed {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Obligation health
+        health: u64,
+        /// Slot the obligation was recorded unhealthy at, zero while healthy
+        unhealthy_since: u64,
+    },
+    /// Unhealthy obligation liquidated
+    Liquidation {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Collateral account
+        collateral: Pubkey,
+        /// Amount of liquidity repaid
+        repay_amount: u64,
+        /// Amount of collateral seized by the liquidator
+        collateral_amount: u64,
+        /// Liquidation bonus charged
+        liquidation_bonus: u64,
+        /// Obligation health
+        health: u64,
+    },
+    /// Obligation debt repaid with the swapped collateral
+    RepayWithCollateral {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Liquidity account
+        liquidity: Pubkey,
+        /// Amount of collateral swapped
+        collateral_amount: u64,
+        /// Amount of liquidity repaid
+        repay_amount: u64,
+        /// Obligation health
+        health: u64,
+    },
+    /// Obligation collateral replaced with the swapped one
+    CollateralSwapped {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Old collateral account
+        collateral: Pubkey,
+        /// New collateral account
+        new_collateral: Pubkey,
+        /// Amount of old collateral swapped
+        amount: u64,
+        /// Amount of new collateral deposited
+        new_amount: u64,
+        /// Obligation health
+        health: u64,
+    },
+    /// Obligation debt moved to the new liquidity
+    DebtRefinanced {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Old liquidity account
+        liquidity: Pubkey,
+        /// New liquidity account
+        new_liquidity: Pubkey,
+        /// Amount of old liquidity repaid
+        repay_amount: u64,
+        /// Amount of new liquidity borrowed
+        borrow_amount: u64,
+        /// Obligation health
+        health: u64,
+    },
+    /// Delegation allowance updated or spent by the delegate
+    DelegationUpdated {
+        /// Delegation account
+        delegation: Pubkey,
+        /// Obligation account
+        obligation: Pubkey,
+        /// Delegate
+        delegate: Pubkey,
+        /// Amount of liquidity the delegate can borrow
+        allowance: u64,
+    },
+    /// Obligation ownership changed
+    ObligationTransferred {
+        /// Obligation account
+        obligation: Pubkey,
+        /// New obligation owner
+        owner: Pubkey,
+    },
+    /// Obligation ownership represented by the position mint
+    ObligationTokenized {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Position mint
+        position_mint: Pubkey,
+    },
+    /// Obligation operator appointed or cleared
+    OperatorUpdated {
+        /// Obligation account
+        obligation: Pubkey,
+        /// Operator, default when cleared
+        operator: Pubkey,
+    },
+    /// Market config updated
+    ConfigUpdated {
+        /// Updated config
+        config: ConfigUpdate,
+    },
+}
+
+impl LendingEvent {
+    /// Log the event as a base64 encoded borsh
+    pub fn emit(&self) {
+        if let Ok(data) = self.try_to_vec() {
+            msg!("{}{}", EVENT_LOG_PREFIX, base64::encode(data));
+        }
+    }
+
+    /// Decode the event from the program log line, `None` if the line isn't an event
+    pub fn decode(log: &str) -> Option<Self> {
+        let log = log.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(log);
+        let data = base64::decode(log.strip_prefix(EVENT_LOG_PREFIX)?).ok()?;
+
+        Self::try_from_slice(&data).ok()
+    }
+}
+
+/// Decode all events from the transaction log messages
+pub fn decode_events<S: AsRef<str>>(logs: &[S]) -> Vec<LendingEvent> {
+    logs.iter()
+        .filter_map(|log| LendingEvent::decode(log.as_ref()))
+        .collect()
+}
@@ -1,8 +1,12 @@
 //! Instruction types
 
 use crate::{
-    find_obligation_authority, find_program_address,
-    state::{CollateralStatus, LiquidityStatus},
+    find_delegation_authority, find_native_account_address, find_obligation_authority,
+    find_pair_authority, find_program_address,
+    state::{
+        CollateralStatus, LiquidityStatus, PairConfig, PAUSE_BORROW, PAUSE_DEPOSIT,
+        PAUSE_LIQUIDATE, PAUSE_WITHDRAW,
+    },
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
@@ -35,7 +39,7 @@ pub enum LendingInstruction {
     /// [R] Market authority
     /// [R] Rent sysvar
     /// [R] Sytem program
-    /// [R] Token program id
+    /// [R] Token program id (SPL Token or Token-2022)
     CreateLiquidityToken,
 
     /// Update liquidity token
@@ -60,7 +64,7 @@ pub enum LendingInstruction {
     /// [R] Market authority
     /// [R] Rent sysvar
     /// [R] Sytem program
-    /// [R] Token program id
+    /// [R] Token program id (SPL Token or Token-2022)
     CreateCollateralToken {
         /// Fractional initial collateralization ratio (multiplied by 10e9)
         ratio_initial: u64,
@@ -86,10 +90,11 @@ pub enum LendingInstruction {
     /// Deposit liquidity
     ///
     /// Accounts:
-    /// [R] Liquidity account
+    /// [W] Liquidity account
     /// [W] Source provider account (for token mint)
     /// [W] Destination provider account (for pool mint)
     /// [W] Token account
+    /// [R] Token mint account
     /// [W] Pool mint account
     /// [R] Market account
     /// [R] Market authority
@@ -103,17 +108,18 @@ pub enum LendingInstruction {
     /// Withdraw liquidity
     ///
     /// Accounts:
-    /// [R] Liquidity account
+    /// [W] Liquidity account
     /// [W] Source provider account (for pool mint)
     /// [W] Destination provider account (for token mint)
     /// [W] Token account
+    /// [R] Token mint account
     /// [W] Pool mint account
     /// [R] Market account
     /// [R] Market authority
     /// [RS] User transfer authority
     /// [R] Token program id
     LiquidityWithdraw {
-        /// Amount of liquidity to withdraw
+        /// Amount of pool tokens to burn, `u64::MAX` to withdraw the whole balance
         amount: u64,
     },
 
@@ -123,13 +129,17 @@ pub enum LendingInstruction {
     /// [W] Obligation account to create - uninitialized
     /// [R] Liquidity account
     /// [R] Collateral account
+    /// [R] Pair account
     /// [R] Market account
     /// [R] Obligation authority (owner/market/liquidity/collateral combination)
     /// [RS] Obligation owner
     /// [R] Rent sysvar
     /// [R] Sytem program
     /// [R] Token program id
-    CreateObligation,
+    CreateObligation {
+        /// Sub-account index among the owner obligations of the pair
+        index: u16,
+    },
 
     /// Deposit collateral token to obligation
     ///
@@ -138,6 +148,7 @@ pub enum LendingInstruction {
     /// [R] Collateral account
     /// [W] Source account (for collateral token mint)
     /// [W] Collateral token account
+    /// [R] Collateral token mint account
     /// [R] Market account
     /// [RS] User transfer authority
     /// [R] Token program id
@@ -150,15 +161,21 @@ pub enum LendingInstruction {
     ///
     /// Accounts:
     /// [W] Obligation account
+    /// [W] Liquidity account
     /// [R] Collateral account
+    /// [R] Pair account
     /// [W] Destination account (for collateral token mint)
     /// [W] Collateral token account
+    /// [R] Collateral token mint account
     /// [R] Market account
     /// [RS] Obligation owner
     /// [R] Market authority
     /// [R] Token program id
+    /// [R] Liquidity token account
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - if the obligation opted into a category
     ObligationCollateralWithdraw {
-        /// Amount of collateral to withdraw
+        /// Amount of collateral to withdraw, `u64::MAX` to withdraw up to the limit
         amount: u64,
     },
 
@@ -167,15 +184,19 @@ pub enum LendingInstruction {
     /// Accounts:
     /// [W] Obligation account
     /// [R] Liquidity account
-    /// [R] Collateral account
+    /// [W] Collateral account
+    /// [R] Pair account
     /// [W] Destination account (for liquidity token mint)
     /// [W] Liquidity token account
+    /// [R] Liquidity token mint account
     /// [R] Market account
     /// [RS] Obligation owner
     /// [R] Market authority
     /// [R] Token program id
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - if the obligation opted into a category
     ObligationLiquidityBorrow {
-        /// Amount of liquidity to borrow
+        /// Amount of liquidity to borrow, `u64::MAX` to borrow up to the limit
         amount: u64,
     },
 
@@ -184,32 +205,2507 @@ pub enum LendingInstruction {
     /// Accounts:
     /// [W] Obligation account
     /// [R] Liquidity account
+    /// [W] Collateral account
     /// [W] Source account (for liquidity token mint)
     /// [W] Liquidity token account
+    /// [R] Liquidity token mint account
     /// [R] Market account
     /// [RS] User transfer authority
     /// [R] Token program id
+    /// [R] Market authority - if the liquidity has insurance
+    /// [W] Insurance account - if the liquidity has insurance
     ObligationLiquidityRepay {
-        /// Amount of liquidity to repay
+        /// Amount of liquidity to repay, `u64::MAX` to repay the whole debt
         amount: u64,
     },
-}
 
-/// Create `InitMarket` instruction
-pub fn init_market(
-    program_id: &Pubkey,
-    market: &Pubkey,
-    owner: &Pubkey,
-) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::InitMarket;
-    let data = init_data.try_to_vec()?;
+    /// Deposit native SOL liquidity
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [WS] Owner (pays lamports for deposit)
+    /// [W] Temporary native account (owner/market combination) - uninitialized
+    /// [W] Destination provider account (for pool mint)
+    /// [W] Token account
+    /// [R] Native mint account
+    /// [W] Pool mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    /// [R] Token program id
+    LiquidityDepositNative {
+        /// Amount of lamports to deposit
+        amount: u64,
+    },
 
-    let accounts = vec![
-        AccountMeta::new(*market, false),
-        AccountMeta::new_readonly(*owner, true),
-        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    /// Withdraw native SOL liquidity
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [W] Source provider account (for pool mint)
+    /// [WS] Owner (receives lamports)
+    /// [W] Temporary native account (owner/market combination) - uninitialized
+    /// [W] Token account
+    /// [R] Native mint account
+    /// [W] Pool mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    /// [R] Token program id
+    LiquidityWithdrawNative {
+        /// Amount of pool tokens to burn, `u64::MAX` to withdraw the whole balance
+        amount: u64,
+    },
+
+    /// Deposit native SOL collateral to obligation
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [R] Collateral account
+    /// [WS] Owner (pays lamports for deposit)
+    /// [W] Temporary native account (owner/market combination) - uninitialized
+    /// [W] Collateral token account
+    /// [R] Native mint account
+    /// [R] Market account
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    /// [R] Token program id
+    ObligationCollateralDepositNative {
+        /// Amount of lamports to deposit
+        amount: u64,
+    },
+
+    /// Withdraw native SOL collateral from obligation
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [R] Collateral account
+    /// [R] Pair account
+    /// [WS] Obligation owner (receives lamports)
+    /// [W] Temporary native account (owner/market combination) - uninitialized
+    /// [W] Collateral token account
+    /// [R] Native mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    /// [R] Token program id
+    /// [R] Liquidity token account
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - if the obligation opted into a category
+    ObligationCollateralWithdrawNative {
+        /// Amount of collateral to withdraw, `u64::MAX` to withdraw up to the limit
+        amount: u64,
+    },
+
+    /// Borrow native SOL liquidity from obligation
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [W] Collateral account
+    /// [R] Pair account
+    /// [WS] Obligation owner (receives lamports)
+    /// [W] Temporary native account (owner/market combination) - uninitialized
+    /// [W] Liquidity token account
+    /// [R] Native mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    /// [R] Token program id
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - if the obligation opted into a category
+    ObligationLiquidityBorrowNative {
+        /// Amount of liquidity to borrow, `u64::MAX` to borrow up to the limit
+        amount: u64,
+    },
+
+    /// Repay native SOL liquidity to obligation
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [W] Collateral account
+    /// [WS] Owner (pays lamports for repay)
+    /// [W] Temporary native account (owner/market combination) - uninitialized
+    /// [W] Liquidity token account
+    /// [R] Native mint account
+    /// [R] Market account
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    /// [R] Token program id
+    /// [R] Market authority - if the liquidity has insurance
+    /// [W] Insurance account - if the liquidity has insurance
+    ObligationLiquidityRepayNative {
+        /// Amount of lamports to repay, `u64::MAX` to repay the whole debt
+        amount: u64,
+    },
+    /// Deposit liquidity with the minimum amount of pool tokens to receive
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [W] Source provider account (for token mint)
+    /// [W] Destination provider account (for pool mint)
+    /// [W] Token account
+    /// [R] Token mint account
+    /// [W] Pool mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] User transfer authority
+    /// [R] Token program id
+    LiquidityDepositChecked {
+        /// Amount of liquidity to deposit
+        amount: u64,
+        /// Minimum amount of pool tokens to receive
+        min_pool_tokens_out: u64,
+    },
+
+    /// Withdraw liquidity with the minimum amount of liquidity to receive
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [W] Source provider account (for pool mint)
+    /// [W] Destination provider account (for token mint)
+    /// [W] Token account
+    /// [R] Token mint account
+    /// [W] Pool mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] User transfer authority
+    /// [R] Token program id
+    LiquidityWithdrawChecked {
+        /// Amount of pool tokens to burn, `u64::MAX` to withdraw the whole balance
+        amount: u64,
+        /// Minimum amount of liquidity to receive
+        min_liquidity_out: u64,
+    },
+
+    /// Deposit liquidity for the exact amount of pool tokens.
+    /// Not supported for mints with a transfer fee.
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [W] Source provider account (for token mint)
+    /// [W] Destination provider account (for pool mint)
+    /// [W] Token account
+    /// [R] Token mint account
+    /// [W] Pool mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] User transfer authority
+    /// [R] Token program id
+    LiquidityDepositExactOut {
+        /// Amount of pool tokens to receive
+        pool_tokens_out: u64,
+        /// Maximum amount of liquidity to deposit
+        max_amount_in: u64,
+    },
+
+    /// Withdraw the exact amount of liquidity.
+    /// Not supported for mints with a transfer fee.
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [W] Source provider account (for pool mint)
+    /// [W] Destination provider account (for token mint)
+    /// [W] Token account
+    /// [R] Token mint account
+    /// [W] Pool mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] User transfer authority
+    /// [R] Token program id
+    LiquidityWithdrawExactOut {
+        /// Amount of liquidity to receive
+        liquidity_out: u64,
+        /// Maximum amount of pool tokens to burn
+        max_pool_tokens_in: u64,
+    },
+
+    /// Write off the debt of the obligation without collateral,
+    /// covered by the insurance account first
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [W] Collateral account
+    /// [W] Token account
+    /// [R] Token mint account
+    /// [R] Pool mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [R] Token program id
+    /// [W] Insurance account - if created for the liquidity
+    WriteOffBadDebt,
+
+    /// Create insurance account of the liquidity
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [W] Insurance account - uninitialized
+    /// [R] Token mint account
+    /// [R] Market account
+    /// [RS] Market owner
+    /// [R] Market authority
+    /// [R] Rent sysvar
+    /// [R] Token program id
+    CreateLiquidityInsurance {
+        /// Share of the income transferred to the insurance account
+        reserve_factor: u64,
+    },
+
+    /// Update insurance config of the liquidity
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [R] Market account
+    /// [RS] Market owner
+    UpdateLiquidityInsurance {
+        /// Share of the income transferred to the insurance account
+        reserve_factor: u64,
+    },
+
+    /// Top up insurance account of the liquidity
+    ///
+    /// Accounts:
+    /// [R] Liquidity account
+    /// [W] Source account (for token mint)
+    /// [W] Insurance account
+    /// [R] Token mint account
+    /// [R] Market account
+    /// [RS] Market owner
+    /// [R] Token program id
+    InsuranceTopUp {
+        /// Amount to top up
+        amount: u64,
+    },
+
+    /// Request withdrawal from the insurance account, unlocked after the timelock
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [R] Insurance account
+    /// [R] Market account
+    /// [RS] Market owner
+    /// [R] Clock sysvar
+    InsuranceWithdrawRequest {
+        /// Amount to withdraw, capped at the insurance account balance, zero cancels the request
+        amount: u64,
+    },
+
+    /// Withdraw the requested amount from the insurance account
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [W] Insurance account
+    /// [W] Destination account (for token mint)
+    /// [R] Token mint account
+    /// [R] Market account
+    /// [RS] Market owner
+    /// [R] Market authority
+    /// [R] Clock sysvar
+    /// [R] Token program id
+    InsuranceWithdraw,
+
+    /// Update isolation mode of the collateral, new obligations become isolated while it's enabled
+    /// and lifting it lifts the restrictions of the existing isolated obligations
+    ///
+    /// Accounts:
+    /// [W] Collateral account
+    /// [R] Market account
+    /// [RS] Market owner
+    /// [R] Liquidity accounts approved for the isolated obligations, up to `MAX_ISOLATED_LIQUIDITIES`
+    UpdateCollateralIsolation {
+        /// Isolation mode
+        isolated: bool,
+        /// Maximum amount of liquidity borrowed by the isolated obligations
+        debt_ceiling: u64,
+    },
+
+    /// Create pair with the risk parameters of the collateral backing the liquidity
+    ///
+    /// Accounts:
+    /// [W] Pair account to create - uninitialized
+    /// [R] Liquidity account
+    /// [R] Collateral account
+    /// [R] Market account
+    /// [WS] Market owner
+    /// [R] Pair authority
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    CreatePair {
+        /// Borrowing of the liquidity against the collateral is enabled
+        borrow_enabled: bool,
+        /// Fractional initial collateralization ratio (multiplied by 10e9)
+        ratio_initial: u64,
+        /// Fractional limit for the healthy collateralization ratio (multiplied by 10e9)
+        ratio_healthy: u64,
+    },
+
+    /// Update pair
+    ///
+    /// Accounts:
+    /// [W] Pair account
+    /// [R] Market account
+    /// [RS] Market owner
+    UpdatePair {
+        /// Borrowing of the liquidity against the collateral is enabled
+        borrow_enabled: bool,
+        /// Fractional initial collateralization ratio (multiplied by 10e9)
+        ratio_initial: u64,
+        /// Fractional limit for the healthy collateralization ratio (multiplied by 10e9)
+        ratio_healthy: u64,
+    },
+    /// Create category of the correlated assets
+    ///
+    /// Accounts:
+    /// [W] Category account to create - uninitialized
+    /// [R] Market account
+    /// [WS] Market owner
+    /// [R] Market authority
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    CreateCategory {
+        /// Category id, non zero
+        id: u8,
+        /// Fractional initial collateralization ratio (multiplied by 10e9)
+        ratio_initial: u64,
+        /// Fractional limit for the healthy collateralization ratio (multiplied by 10e9)
+        ratio_healthy: u64,
+        /// Human readable name, zero padded
+        name: [u8; 32],
+    },
+
+    /// Update category of the correlated assets
+    ///
+    /// Accounts:
+    /// [W] Category account
+    /// [R] Market account
+    /// [RS] Market owner
+    UpdateCategory {
+        /// Fractional initial collateralization ratio (multiplied by 10e9)
+        ratio_initial: u64,
+        /// Fractional limit for the healthy collateralization ratio (multiplied by 10e9)
+        ratio_healthy: u64,
+    },
+
+    /// Update category of the liquidity token
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [R] Market account
+    /// [RS] Market owner
+    /// [R] Category account - unless removing the liquidity with `NO_CATEGORY`
+    UpdateLiquidityCategory {
+        /// Category id, `NO_CATEGORY` to remove the liquidity from the category
+        category: u8,
+    },
+
+    /// Update category of the collateral token
+    ///
+    /// Accounts:
+    /// [W] Collateral account
+    /// [R] Market account
+    /// [RS] Market owner
+    /// [R] Category account - unless removing the collateral with `NO_CATEGORY`
+    UpdateCollateralCategory {
+        /// Category id, `NO_CATEGORY` to remove the collateral from the category
+        category: u8,
+    },
+
+    /// Opt obligation into the category shared by its liquidity and collateral
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [R] Liquidity account
+    /// [R] Collateral account
+    /// [R] Pair account
+    /// [R] Market account
+    /// [RS] Obligation owner
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - unless opting out with `NO_CATEGORY`
+    UpdateObligationCategory {
+        /// Category id, `NO_CATEGORY` to opt out
+        category: u8,
+    },
+    /// Update maximum utilization of the liquidity token,
+    /// borrowing and withdrawing can't exceed it
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [R] Market account
+    /// [RS] Market owner
+    UpdateLiquidityUtilization {
+        /// Maximum share of the total liquidity borrowed (multiplied by 10e9)
+        max_utilization: u64,
+    },
+
+    /// Queue the liquidity withdrawal, pool tokens are burned at the current exchange rate
+    /// and the ticket is paid out once deposits and repayments fill the queue up to it
+    ///
+    /// Accounts:
+    /// [W] Withdrawal ticket account - uninitialized
+    /// [W] Liquidity account
+    /// [W] Source provider account (for pool mint)
+    /// [R] Token account
+    /// [W] Pool mint account
+    /// [R] Market account
+    /// [RS] Provider, ticket owner
+    /// [R] Rent sysvar
+    /// [R] Token program id
+    LiquidityWithdrawRequest {
+        /// Amount of pool tokens to burn, `u64::MAX` to queue the whole balance
+        amount: u64,
+    },
+
+    /// Pay out the filled withdrawal ticket and close it
+    ///
+    /// Accounts:
+    /// [W] Withdrawal ticket account
+    /// [W] Liquidity account
+    /// [W] Destination provider account (for token mint)
+    /// [W] Token account
+    /// [R] Token mint account
+    /// [R] Market account
+    /// [R] Market authority
+    /// [WS] Ticket owner, receives the rent of the ticket
+    /// [R] Token program id
+    LiquidityWithdrawClaim,
+
+    /// Update market guardian allowed to pause the operations
+    ///
+    /// Accounts:
+    /// [W] Market account
+    /// [RS] Market owner
+    /// [R] Guardian, default pubkey to remove
+    UpdateMarketGuardian,
+
+    /// Pause the operations market-wide
+    ///
+    /// Accounts:
+    /// [W] Market account
+    /// [RS] Market guardian or owner
+    /// [R] Clock sysvar
+    PauseMarket {
+        /// Bitmask of the operations to pause, see `PAUSE_*`
+        operations: u64,
+    },
+
+    /// Unpause the operations market-wide
+    ///
+    /// Accounts:
+    /// [W] Market account
+    /// [RS] Market owner
+    UnpauseMarket {
+        /// Bitmask of the operations to unpause, see `PAUSE_*`
+        operations: u64,
+    },
+
+    /// Update outflow rate limit of the liquidity token,
+    /// withdrawals and borrows can't exceed the limit within the window
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [R] Market account
+    /// [RS] Market owner
+    UpdateLiquidityOutflowLimit {
+        /// Maximum amount of liquidity leaving per window, zero if unlimited
+        outflow_limit: u64,
+        /// Length of the window in slots
+        outflow_window: u64,
+    },
+
+    /// Reset outflow of the current window, overriding the rate limit
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [R] Market account
+    /// [RS] Market owner
+    ResetLiquidityOutflow,
+
+    /// Update Dutch-auction liquidation of the collateral token
+    ///
+    /// Accounts:
+    /// [W] Collateral account
+    /// [R] Market account
+    /// [RS] Market owner
+    UpdateCollateralLiquidation {
+        /// Liquidation bonus once the obligation became unhealthy (multiplied by 10e9)
+        bonus_start: u64,
+        /// Maximum liquidation bonus (multiplied by 10e9)
+        bonus_max: u64,
+        /// Number of slots the liquidation bonus rises from the start to the maximum
+        bonus_slots: u64,
+        /// Maximum fraction of the debt repaid by a single liquidation (multiplied by 10e9)
+        close_factor: u64,
+    },
+
+    /// Record the slot the obligation became unhealthy at, permissionless crank
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [R] Collateral account
+    /// [R] Pair account
+    /// [R] Market account
+    /// [R] Clock sysvar
+    /// [R] Liquidity token account
+    /// [R] Category account - if the obligation opted into the category
+    RefreshObligationHealth,
+
+    /// Repay liquidity of the unhealthy obligation for its collateral
+    /// with the current liquidation bonus
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [W] Collateral account
+    /// [R] Pair account
+    /// [W] Source liquidator account (for liquidity token mint)
+    /// [W] Destination liquidator account (for collateral token mint)
+    /// [W] Liquidity token account
+    /// [R] Liquidity token mint
+    /// [W] Collateral token account
+    /// [R] Collateral token mint
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] User transfer authority
+    /// [R] Clock sysvar
+    /// [R] Token program id
+    /// [R] Category account - if the obligation opted into the category
+    /// [W] Insurance account - if the liquidity has insurance
+    LiquidateObligation {
+        /// Amount of liquidity to repay, capped at the close factor share of the debt,
+        /// `u64::MAX` for the whole share
+        amount: u64,
+    },
+
+    /// Repay liquidity of the obligation with its collateral swapped by the swap program,
+    /// see `swap::SwapInstruction` for the interface. Operator swaps into the liquidity token
+    /// account as the swap destination
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [W] Collateral account
+    /// [R] Pair account
+    /// [W] Swap source account (for collateral token mint)
+    /// [W] Swap destination account (for liquidity token mint)
+    /// [W] Collateral token account
+    /// [R] Collateral token mint
+    /// [W] Liquidity token account
+    /// [R] Liquidity token mint
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] Obligation owner or operator
+    /// [R] Token program id
+    /// [R] Swap program id
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - if the obligation opted into the category
+    /// [W] Insurance account - if the liquidity has insurance
+    /// .. Swap program accounts
+    RepayWithCollateral {
+        /// Amount of collateral to swap
+        collateral_amount: u64,
+        /// Minimum amount of liquidity received from the swap
+        minimum_amount_out: u64,
+    },
+
+    /// Replace the collateral of the obligation with the new one swapped by the swap program,
+    /// see `swap::SwapInstruction` for the interface. Operator swaps into the new collateral
+    /// token account as the swap destination
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [W] Collateral account
+    /// [W] New collateral account
+    /// [R] New pair account
+    /// [W] Swap source account (for collateral token mint)
+    /// [W] Swap destination account (for new collateral token mint)
+    /// [W] Collateral token account
+    /// [R] Collateral token mint
+    /// [W] New collateral token account
+    /// [R] New collateral token mint
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] Obligation owner or operator
+    /// [R] Token program id
+    /// [R] Swap program id
+    /// [R] Liquidity token account
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - if the obligation opted into the category
+    /// .. Swap program accounts
+    SwapObligationCollateral {
+        /// Minimum amount of new collateral received from the swap
+        minimum_amount_out: u64,
+    },
+
+    /// Move the debt of the obligation to the new liquidity, borrowed liquidity is swapped
+    /// by the swap program to repay the whole debt, see `swap::SwapInstruction` for the interface.
+    /// Operator swaps into the liquidity token account as the swap destination
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Liquidity account
+    /// [W] New liquidity account
+    /// [W] Collateral account
+    /// [R] New pair account
+    /// [W] Swap source account (for new liquidity token mint)
+    /// [W] Swap destination account (for liquidity token mint)
+    /// [W] Liquidity token account
+    /// [R] Liquidity token mint
+    /// [W] New liquidity token account
+    /// [R] New liquidity token mint
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] Obligation owner or operator
+    /// [R] Token program id
+    /// [R] Swap program id
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - if the obligation opted into the category
+    /// [W] Insurance account - if the liquidity has insurance
+    /// .. Swap program accounts
+    RefinanceObligationDebt {
+        /// Amount of new liquidity to borrow
+        amount: u64,
+    },
+
+    /// Create delegation of the obligation borrowing power
+    ///
+    /// Accounts:
+    /// [W] Delegation account to create - uninitialized
+    /// [R] Obligation account
+    /// [R] Delegate
+    /// [WS] Obligation owner
+    /// [R] Delegation authority
+    /// [R] Rent sysvar
+    /// [R] Sytem program
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    CreateDelegation {
+        /// Amount of liquidity the delegate can borrow
+        allowance: u64,
+    },
+
+    /// Update delegation allowance, zero revokes the delegation
+    ///
+    /// Accounts:
+    /// [W] Delegation account
+    /// [R] Obligation account
+    /// [RS] Obligation owner
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    UpdateDelegation {
+        /// Amount of liquidity the delegate can borrow
+        allowance: u64,
+    },
+
+    /// Borrow liquidity token from obligation by the delegate
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [R] Liquidity account
+    /// [W] Collateral account
+    /// [R] Pair account
+    /// [W] Destination account (for liquidity token mint)
+    /// [W] Liquidity token account
+    /// [R] Liquidity token mint account
+    /// [R] Market account
+    /// [RS] Delegate
+    /// [R] Market authority
+    /// [R] Token program id
+    /// [W] Delegation account
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    /// [R] Category account - if the obligation opted into a category
+    ObligationLiquidityBorrowDelegated {
+        /// Amount of liquidity to borrow, `u64::MAX` to borrow the whole allowance within the
+        /// borrowing limit of the obligation
+        amount: u64,
+    },
+
+    /// Transfer obligation ownership
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [RS] Obligation owner
+    /// [R] New obligation owner
+    TransferObligation,
+
+    /// Represent obligation ownership by a one-of-one position mint, the holder of the position
+    /// token acts as the obligation owner
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [W] Position mint account - uninitialized
+    /// [W] Position token account (for position mint) - uninitialized
+    /// [R] Market account
+    /// [R] Market authority
+    /// [RS] Obligation owner
+    /// [R] Rent sysvar
+    /// [R] Token program id
+    TokenizeObligation,
+
+    /// Record the position token holder as the owner of the tokenized obligation
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [R] Position token account (for position mint)
+    /// [RS] Position token account owner
+    ClaimObligation,
+
+    /// Appoint operator of the obligation, the operator rebalances it through the swap program
+    /// into the market token accounts at par by the obligation valuation
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [RS] Obligation owner
+    /// [R] Operator
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    SetObligationOperator,
+
+    /// Clear obligation operator
+    ///
+    /// Accounts:
+    /// [W] Obligation account
+    /// [RS] Obligation owner or operator
+    /// [R] Position token account (for position mint) - if the obligation is tokenized
+    ClearObligationOperator,
+
+    /// Update utilization-driven borrow rate of the liquidity token,
+    /// the interest is accrued at the previous rate first
+    ///
+    /// Accounts:
+    /// [W] Liquidity account
+    /// [R] Liquidity token account
+    /// [R] Market account
+    /// [RS] Market owner
+    UpdateLiquidityBorrowRate {
+        /// Utilization the borrow rate bends at (multiplied by 10e9)
+        optimal_utilization: u64,
+        /// Annual borrow rate at zero utilization (multiplied by 10e9)
+        borrow_rate_min: u64,
+        /// Annual borrow rate at the optimal utilization (multiplied by 10e9)
+        borrow_rate_optimal: u64,
+        /// Annual borrow rate at the full utilization (multiplied by 10e9)
+        borrow_rate_max: u64,
+    },
+}
+
+impl LendingInstruction {
+    /// Operation the guardian can pause and the index of the market account
+    pub fn pause_operation(&self) -> Option<(u64, usize)> {
+        match self {
+            Self::LiquidityDeposit { .. }
+            | Self::LiquidityDepositChecked { .. }
+            | Self::LiquidityDepositExactOut { .. } => Some((PAUSE_DEPOSIT, 6)),
+            Self::LiquidityDepositNative { .. } => Some((PAUSE_DEPOSIT, 7)),
+            Self::ObligationCollateralDeposit { .. } => Some((PAUSE_DEPOSIT, 5)),
+            Self::ObligationCollateralDepositNative { .. } => Some((PAUSE_DEPOSIT, 6)),
+
+            Self::LiquidityWithdraw { .. }
+            | Self::LiquidityWithdrawChecked { .. }
+            | Self::LiquidityWithdrawExactOut { .. } => Some((PAUSE_WITHDRAW, 6)),
+            Self::LiquidityWithdrawNative { .. } => Some((PAUSE_WITHDRAW, 7)),
+            Self::LiquidityWithdrawRequest { .. } | Self::LiquidityWithdrawClaim => {
+                Some((PAUSE_WITHDRAW, 5))
+            }
+            Self::ObligationCollateralWithdraw { .. } => Some((PAUSE_WITHDRAW, 7)),
+            Self::ObligationCollateralWithdrawNative { .. } => Some((PAUSE_WITHDRAW, 8)),
+            Self::RepayWithCollateral { .. } => Some((PAUSE_WITHDRAW, 10)),
+            Self::SwapObligationCollateral { .. } => Some((PAUSE_WITHDRAW, 11)),
+
+            Self::ObligationLiquidityBorrow { .. } => Some((PAUSE_BORROW, 7)),
+            Self::ObligationLiquidityBorrowNative { .. } => Some((PAUSE_BORROW, 8)),
+            Self::ObligationLiquidityBorrowDelegated { .. } => Some((PAUSE_BORROW, 7)),
+            Self::RefinanceObligationDebt { .. } => Some((PAUSE_BORROW, 11)),
+
+            Self::WriteOffBadDebt => Some((PAUSE_LIQUIDATE, 6)),
+            Self::LiquidateObligation { .. } => Some((PAUSE_LIQUIDATE, 10)),
+
+            _ => None,
+        }
+    }
+}
+
+/// Create `InitMarket` instruction
+pub fn init_market(
+    program_id: &Pubkey,
+    market: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::InitMarket;
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `CreateLiquidityToken` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_liquidity_token(
+    program_id: &Pubkey,
+    liquidity: &Pubkey,
+    token_mint: &Pubkey,
+    token_account: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::CreateLiquidityToken;
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateLiquidityToken` instruction
+pub fn update_liquidity_token(
+    program_id: &Pubkey,
+    status: LiquidityStatus,
+    liquidity: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateLiquidityToken { status };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `CreateCollateralToken` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_collateral_token(
+    program_id: &Pubkey,
+    ratio_initial: u64,
+    ratio_healthy: u64,
+    collateral: &Pubkey,
+    token_mint: &Pubkey,
+    token_account: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::CreateCollateralToken {
+        ratio_initial,
+        ratio_healthy,
+    };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateCollateralToken` instruction
+pub fn update_collateral_token(
+    program_id: &Pubkey,
+    status: CollateralStatus,
+    ratio_initial: u64,
+    ratio_healthy: u64,
+    collateral: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateCollateralToken {
+        status,
+        ratio_initial,
+        ratio_healthy,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityDeposit` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_deposit(
+    program_id: &Pubkey,
+    amount: u64,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    token_account: &Pubkey,
+    token_mint: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityDeposit { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityWithdraw` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_withdraw(
+    program_id: &Pubkey,
+    amount: u64,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    token_account: &Pubkey,
+    token_mint: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityWithdraw { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `CreateObligation` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_obligation(
+    program_id: &Pubkey,
+    index: u16,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    market: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::CreateObligation { index };
+    let data = init_data.try_to_vec()?;
+    let (obligation_authority, _) = find_obligation_authority(program_id, owner, market);
+
+    let accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new_readonly(*liquidity, false),
+        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(obligation_authority, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ObligationCollateralDeposit` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn obligation_collateral_deposit(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    collateral: &Pubkey,
+    source: &Pubkey,
+    collateral_token_account: &Pubkey,
+    collateral_token_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ObligationCollateralDeposit { amount };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*collateral_token_account, false),
+        AccountMeta::new_readonly(*collateral_token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ObligationCollateralWithdraw` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn obligation_collateral_withdraw(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    destination: &Pubkey,
+    collateral_token_account: &Pubkey,
+    collateral_token_mint: &Pubkey,
+    market: &Pubkey,
+    obligation_owner: &Pubkey,
+    token_program_id: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ObligationCollateralWithdraw { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*collateral_token_account, false),
+        AccountMeta::new_readonly(*collateral_token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*obligation_owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*liquidity_token_account, false),
+    ];
+
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ObligationLiquidityBorrow` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn obligation_liquidity_borrow(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    destination: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    liquidity_token_mint: &Pubkey,
+    market: &Pubkey,
+    obligation_owner: &Pubkey,
+    token_program_id: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ObligationLiquidityBorrow { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(*liquidity_token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*obligation_owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ObligationLiquidityRepay` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn obligation_liquidity_repay(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    source: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    liquidity_token_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+    insurance_account: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ObligationLiquidityRepay { amount };
+    let data = init_data.try_to_vec()?;
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(*liquidity_token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    if let Some(insurance_account) = insurance_account {
+        let (market_authority, _) = find_program_address(program_id, market);
+        accounts.push(AccountMeta::new_readonly(market_authority, false));
+        accounts.push(AccountMeta::new(*insurance_account, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityDepositNative` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_deposit_native(
+    program_id: &Pubkey,
+    amount: u64,
+    liquidity: &Pubkey,
+    owner: &Pubkey,
+    destination: &Pubkey,
+    token_account: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityDepositNative { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+    let (native_account, _) = find_native_account_address(program_id, owner, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(native_account, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityWithdrawNative` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_withdraw_native(
+    program_id: &Pubkey,
+    amount: u64,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    owner: &Pubkey,
+    token_account: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityWithdrawNative { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+    let (native_account, _) = find_native_account_address(program_id, owner, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(native_account, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ObligationCollateralDepositNative` instruction
+pub fn obligation_collateral_deposit_native(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    collateral: &Pubkey,
+    owner: &Pubkey,
+    collateral_token_account: &Pubkey,
+    market: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ObligationCollateralDepositNative { amount };
+    let data = init_data.try_to_vec()?;
+    let (native_account, _) = find_native_account_address(program_id, owner, market);
+
+    let accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(native_account, false),
+        AccountMeta::new(*collateral_token_account, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ObligationCollateralWithdrawNative` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn obligation_collateral_withdraw_native(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    obligation_owner: &Pubkey,
+    collateral_token_account: &Pubkey,
+    market: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ObligationCollateralWithdrawNative { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+    let (native_account, _) = find_native_account_address(program_id, obligation_owner, market);
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new(*obligation_owner, true),
+        AccountMeta::new(native_account, false),
+        AccountMeta::new(*collateral_token_account, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*liquidity_token_account, false),
+    ];
+
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ObligationLiquidityBorrowNative` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn obligation_liquidity_borrow_native(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    obligation_owner: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    market: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ObligationLiquidityBorrowNative { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+    let (native_account, _) = find_native_account_address(program_id, obligation_owner, market);
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new(*obligation_owner, true),
+        AccountMeta::new(native_account, false),
+        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ObligationLiquidityRepayNative` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn obligation_liquidity_repay_native(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    owner: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    market: &Pubkey,
+    insurance_account: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ObligationLiquidityRepayNative { amount };
+    let data = init_data.try_to_vec()?;
+    let (native_account, _) = find_native_account_address(program_id, owner, market);
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(native_account, false),
+        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(insurance_account) = insurance_account {
+        let (market_authority, _) = find_program_address(program_id, market);
+        accounts.push(AccountMeta::new_readonly(market_authority, false));
+        accounts.push(AccountMeta::new(*insurance_account, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityDepositChecked` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_deposit_checked(
+    program_id: &Pubkey,
+    amount: u64,
+    min_pool_tokens_out: u64,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    token_account: &Pubkey,
+    token_mint: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityDepositChecked {
+        amount,
+        min_pool_tokens_out,
+    };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityWithdrawChecked` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_withdraw_checked(
+    program_id: &Pubkey,
+    amount: u64,
+    min_liquidity_out: u64,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    token_account: &Pubkey,
+    token_mint: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityWithdrawChecked {
+        amount,
+        min_liquidity_out,
+    };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityDepositExactOut` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_deposit_exact_out(
+    program_id: &Pubkey,
+    pool_tokens_out: u64,
+    max_amount_in: u64,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    token_account: &Pubkey,
+    token_mint: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityDepositExactOut {
+        pool_tokens_out,
+        max_amount_in,
+    };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityWithdrawExactOut` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_withdraw_exact_out(
+    program_id: &Pubkey,
+    liquidity_out: u64,
+    max_pool_tokens_in: u64,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    token_account: &Pubkey,
+    token_mint: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityWithdrawExactOut {
+        liquidity_out,
+        max_pool_tokens_in,
+    };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `WriteOffBadDebt` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn write_off_bad_debt(
+    program_id: &Pubkey,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    token_account: &Pubkey,
+    token_mint: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    insurance_account: Option<&Pubkey>,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::WriteOffBadDebt;
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new_readonly(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    if let Some(insurance_account) = insurance_account {
+        accounts.push(AccountMeta::new(*insurance_account, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `CreateLiquidityInsurance` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_liquidity_insurance(
+    program_id: &Pubkey,
+    reserve_factor: u64,
+    liquidity: &Pubkey,
+    insurance_account: &Pubkey,
+    token_mint: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::CreateLiquidityInsurance { reserve_factor };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*insurance_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateLiquidityInsurance` instruction
+pub fn update_liquidity_insurance(
+    program_id: &Pubkey,
+    reserve_factor: u64,
+    liquidity: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateLiquidityInsurance { reserve_factor };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `InsuranceTopUp` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn insurance_top_up(
+    program_id: &Pubkey,
+    amount: u64,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    insurance_account: &Pubkey,
+    token_mint: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::InsuranceTopUp { amount };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*insurance_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `InsuranceWithdrawRequest` instruction
+pub fn insurance_withdraw_request(
+    program_id: &Pubkey,
+    amount: u64,
+    liquidity: &Pubkey,
+    insurance_account: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::InsuranceWithdrawRequest { amount };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*insurance_account, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `InsuranceWithdraw` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn insurance_withdraw(
+    program_id: &Pubkey,
+    liquidity: &Pubkey,
+    insurance_account: &Pubkey,
+    destination: &Pubkey,
+    token_mint: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::InsuranceWithdraw;
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*insurance_account, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateCollateralIsolation` instruction
+pub fn update_collateral_isolation(
+    program_id: &Pubkey,
+    isolated: bool,
+    debt_ceiling: u64,
+    collateral: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    liquidities: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateCollateralIsolation {
+        isolated,
+        debt_ceiling,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let mut accounts = vec![
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+    accounts.extend(
+        liquidities
+            .iter()
+            .map(|liquidity| AccountMeta::new_readonly(*liquidity, false)),
+    );
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `CreatePair` instruction
+pub fn create_pair(
+    program_id: &Pubkey,
+    config: PairConfig,
+    pair: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::CreatePair {
+        borrow_enabled: config.borrow_enabled,
+        ratio_initial: config.ratio_initial,
+        ratio_healthy: config.ratio_healthy,
+    };
+    let data = init_data.try_to_vec()?;
+    let (pair_authority, _) = find_pair_authority(program_id, market, liquidity, collateral);
+
+    let accounts = vec![
+        AccountMeta::new(*pair, false),
+        AccountMeta::new_readonly(*liquidity, false),
+        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new(*market_owner, true),
+        AccountMeta::new_readonly(pair_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdatePair` instruction
+pub fn update_pair(
+    program_id: &Pubkey,
+    config: PairConfig,
+    pair: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdatePair {
+        borrow_enabled: config.borrow_enabled,
+        ratio_initial: config.ratio_initial,
+        ratio_healthy: config.ratio_healthy,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*pair, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `CreateCategory` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_category(
+    program_id: &Pubkey,
+    id: u8,
+    ratio_initial: u64,
+    ratio_healthy: u64,
+    name: [u8; 32],
+    category: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::CreateCategory {
+        id,
+        ratio_initial,
+        ratio_healthy,
+        name,
+    };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*category, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new(*market_owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateCategory` instruction
+pub fn update_category(
+    program_id: &Pubkey,
+    ratio_initial: u64,
+    ratio_healthy: u64,
+    category: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateCategory {
+        ratio_initial,
+        ratio_healthy,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*category, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateLiquidityCategory` instruction
+pub fn update_liquidity_category(
+    program_id: &Pubkey,
+    category_id: u8,
+    liquidity: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    category: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateLiquidityCategory {
+        category: category_id,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let mut accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateCollateralCategory` instruction
+pub fn update_collateral_category(
+    program_id: &Pubkey,
+    category_id: u8,
+    collateral: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    category: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateCollateralCategory {
+        category: category_id,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let mut accounts = vec![
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateObligationCategory` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn update_obligation_category(
+    program_id: &Pubkey,
+    category_id: u8,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    market: &Pubkey,
+    obligation_owner: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateObligationCategory {
+        category: category_id,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new_readonly(*liquidity, false),
+        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*obligation_owner, true),
+    ];
+
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateLiquidityUtilization` instruction
+pub fn update_liquidity_utilization(
+    program_id: &Pubkey,
+    max_utilization: u64,
+    liquidity: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateLiquidityUtilization { max_utilization };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityWithdrawRequest` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_withdraw_request(
+    program_id: &Pubkey,
+    amount: u64,
+    withdrawal_ticket: &Pubkey,
+    liquidity: &Pubkey,
+    source: &Pubkey,
+    token_account: &Pubkey,
+    pool_mint: &Pubkey,
+    market: &Pubkey,
+    provider: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityWithdrawRequest { amount };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*withdrawal_ticket, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new_readonly(*token_account, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*provider, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidityWithdrawClaim` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_withdraw_claim(
+    program_id: &Pubkey,
+    withdrawal_ticket: &Pubkey,
+    liquidity: &Pubkey,
+    destination: &Pubkey,
+    token_account: &Pubkey,
+    token_mint: &Pubkey,
+    market: &Pubkey,
+    owner: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidityWithdrawClaim;
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let accounts = vec![
+        AccountMeta::new(*withdrawal_ticket, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new(*owner, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateMarketGuardian` instruction
+pub fn update_market_guardian(
+    program_id: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+    guardian: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateMarketGuardian;
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(*guardian, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `PauseMarket` instruction
+pub fn pause_market(
+    program_id: &Pubkey,
+    operations: u64,
+    market: &Pubkey,
+    guardian: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::PauseMarket { operations };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*guardian, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UnpauseMarket` instruction
+pub fn unpause_market(
+    program_id: &Pubkey,
+    operations: u64,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UnpauseMarket { operations };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateLiquidityOutflowLimit` instruction
+pub fn update_liquidity_outflow_limit(
+    program_id: &Pubkey,
+    outflow_limit: u64,
+    outflow_window: u64,
+    liquidity: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateLiquidityOutflowLimit {
+        outflow_limit,
+        outflow_window,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `ResetLiquidityOutflow` instruction
+pub fn reset_liquidity_outflow(
+    program_id: &Pubkey,
+    liquidity: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::ResetLiquidityOutflow;
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `UpdateCollateralLiquidation` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn update_collateral_liquidation(
+    program_id: &Pubkey,
+    bonus_start: u64,
+    bonus_max: u64,
+    bonus_slots: u64,
+    close_factor: u64,
+    collateral: &Pubkey,
+    market: &Pubkey,
+    market_owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::UpdateCollateralLiquidation {
+        bonus_start,
+        bonus_max,
+        bonus_slots,
+        close_factor,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let accounts = vec![
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*market_owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `RefreshObligationHealth` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn refresh_obligation_health(
+    program_id: &Pubkey,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    market: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    category: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::RefreshObligationHealth;
+    let data = init_data.try_to_vec()?;
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(*liquidity_token_account, false),
+    ];
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `LiquidateObligation` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidate_obligation(
+    program_id: &Pubkey,
+    amount: u64,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    liquidity_token_mint: &Pubkey,
+    collateral_token_account: &Pubkey,
+    collateral_token_mint: &Pubkey,
+    market: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+    category: Option<&Pubkey>,
+    insurance_account: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::LiquidateObligation { amount };
+    let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
+
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(*liquidity_token_mint, false),
+        AccountMeta::new(*collateral_token_account, false),
+        AccountMeta::new_readonly(*collateral_token_mint, false),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
     ];
 
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    if let Some(insurance_account) = insurance_account {
+        accounts.push(AccountMeta::new(*insurance_account, false));
+    }
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -217,33 +2713,69 @@ pub fn init_market(
     })
 }
 
-/// Create `CreateLiquidityToken` instruction
-pub fn create_liquidity_token(
+/// Create `RepayWithCollateral` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn repay_with_collateral(
     program_id: &Pubkey,
+    collateral_amount: u64,
+    minimum_amount_out: u64,
+    obligation: &Pubkey,
     liquidity: &Pubkey,
-    token_mint: &Pubkey,
-    token_account: &Pubkey,
-    pool_mint: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
+    swap_source: &Pubkey,
+    swap_destination: &Pubkey,
+    collateral_token_account: &Pubkey,
+    collateral_token_mint: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    liquidity_token_mint: &Pubkey,
     market: &Pubkey,
-    market_owner: &Pubkey,
+    obligation_owner: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_program_id: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
+    insurance_account: Option<&Pubkey>,
+    swap_accounts: &[AccountMeta],
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::CreateLiquidityToken;
+    let init_data = LendingInstruction::RepayWithCollateral {
+        collateral_amount,
+        minimum_amount_out,
+    };
     let data = init_data.try_to_vec()?;
     let (market_authority, _) = find_program_address(program_id, market);
 
-    let accounts = vec![
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
         AccountMeta::new(*liquidity, false),
-        AccountMeta::new_readonly(*token_mint, false),
-        AccountMeta::new(*token_account, false),
-        AccountMeta::new(*pool_mint, false),
-        AccountMeta::new(*market, false),
-        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
+        AccountMeta::new(*swap_source, false),
+        AccountMeta::new(*swap_destination, false),
+        AccountMeta::new(*collateral_token_account, false),
+        AccountMeta::new_readonly(*collateral_token_mint, false),
+        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(*liquidity_token_mint, false),
+        AccountMeta::new_readonly(*market, false),
         AccountMeta::new_readonly(market_authority, false),
-        AccountMeta::new_readonly(sysvar::rent::id(), false),
-        AccountMeta::new_readonly(system_program::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*obligation_owner, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*swap_program_id, false),
     ];
 
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    if let Some(insurance_account) = insurance_account {
+        accounts.push(AccountMeta::new(*insurance_account, false));
+    }
+    accounts.extend_from_slice(swap_accounts);
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -251,23 +2783,64 @@ pub fn create_liquidity_token(
     })
 }
 
-/// Create `UpdateLiquidityToken` instruction
-pub fn update_liquidity_token(
+/// Create `SwapObligationCollateral` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn swap_obligation_collateral(
     program_id: &Pubkey,
-    status: LiquidityStatus,
+    minimum_amount_out: u64,
+    obligation: &Pubkey,
     liquidity: &Pubkey,
+    collateral: &Pubkey,
+    new_collateral: &Pubkey,
+    new_pair: &Pubkey,
+    swap_source: &Pubkey,
+    swap_destination: &Pubkey,
+    collateral_token_account: &Pubkey,
+    collateral_token_mint: &Pubkey,
+    new_collateral_token_account: &Pubkey,
+    new_collateral_token_mint: &Pubkey,
     market: &Pubkey,
-    market_owner: &Pubkey,
+    obligation_owner: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_program_id: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
+    swap_accounts: &[AccountMeta],
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::UpdateLiquidityToken { status };
+    let init_data = LendingInstruction::SwapObligationCollateral { minimum_amount_out };
     let data = init_data.try_to_vec()?;
+    let (market_authority, _) = find_program_address(program_id, market);
 
-    let accounts = vec![
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
         AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new(*new_collateral, false),
+        AccountMeta::new_readonly(*new_pair, false),
+        AccountMeta::new(*swap_source, false),
+        AccountMeta::new(*swap_destination, false),
+        AccountMeta::new(*collateral_token_account, false),
+        AccountMeta::new_readonly(*collateral_token_mint, false),
+        AccountMeta::new(*new_collateral_token_account, false),
+        AccountMeta::new_readonly(*new_collateral_token_mint, false),
         AccountMeta::new_readonly(*market, false),
-        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*obligation_owner, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*swap_program_id, false),
+        AccountMeta::new_readonly(*liquidity_token_account, false),
     ];
 
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+    accounts.extend_from_slice(swap_accounts);
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -275,37 +2848,102 @@ pub fn update_liquidity_token(
     })
 }
 
-/// Create `CreateCollateralToken` instruction
+/// Create `RefinanceObligationDebt` instruction
 #[allow(clippy::too_many_arguments)]
-pub fn create_collateral_token(
+pub fn refinance_obligation_debt(
     program_id: &Pubkey,
-    ratio_initial: u64,
-    ratio_healthy: u64,
+    amount: u64,
+    obligation: &Pubkey,
+    liquidity: &Pubkey,
+    new_liquidity: &Pubkey,
     collateral: &Pubkey,
-    token_mint: &Pubkey,
-    token_account: &Pubkey,
+    new_pair: &Pubkey,
+    swap_source: &Pubkey,
+    swap_destination: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    liquidity_token_mint: &Pubkey,
+    new_liquidity_token_account: &Pubkey,
+    new_liquidity_token_mint: &Pubkey,
     market: &Pubkey,
-    market_owner: &Pubkey,
+    obligation_owner: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_program_id: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
+    insurance_account: Option<&Pubkey>,
+    swap_accounts: &[AccountMeta],
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::CreateCollateralToken {
-        ratio_initial,
-        ratio_healthy,
-    };
+    let init_data = LendingInstruction::RefinanceObligationDebt { amount };
     let data = init_data.try_to_vec()?;
     let (market_authority, _) = find_program_address(program_id, market);
 
-    let accounts = vec![
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*new_liquidity, false),
         AccountMeta::new(*collateral, false),
-        AccountMeta::new_readonly(*token_mint, false),
-        AccountMeta::new(*token_account, false),
-        AccountMeta::new(*market, false),
-        AccountMeta::new_readonly(*market_owner, true),
+        AccountMeta::new_readonly(*new_pair, false),
+        AccountMeta::new(*swap_source, false),
+        AccountMeta::new(*swap_destination, false),
+        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(*liquidity_token_mint, false),
+        AccountMeta::new(*new_liquidity_token_account, false),
+        AccountMeta::new_readonly(*new_liquidity_token_mint, false),
+        AccountMeta::new_readonly(*market, false),
         AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*obligation_owner, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*swap_program_id, false),
+    ];
+
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
+    if let Some(insurance_account) = insurance_account {
+        accounts.push(AccountMeta::new(*insurance_account, false));
+    }
+    accounts.extend_from_slice(swap_accounts);
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create `CreateDelegation` instruction
+pub fn create_delegation(
+    program_id: &Pubkey,
+    allowance: u64,
+    delegation: &Pubkey,
+    obligation: &Pubkey,
+    delegate: &Pubkey,
+    obligation_owner: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let init_data = LendingInstruction::CreateDelegation { allowance };
+    let data = init_data.try_to_vec()?;
+    let (delegation_authority, _) = find_delegation_authority(program_id, obligation, delegate);
+
+    let mut accounts = vec![
+        AccountMeta::new(*delegation, false),
+        AccountMeta::new_readonly(*obligation, false),
+        AccountMeta::new_readonly(*delegate, false),
+        AccountMeta::new(*obligation_owner, true),
+        AccountMeta::new_readonly(delegation_authority, false),
         AccountMeta::new_readonly(sysvar::rent::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
     ];
 
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -313,29 +2951,28 @@ pub fn create_collateral_token(
     })
 }
 
-/// Create `UpdateCollateralToken` instruction
-pub fn update_collateral_token(
+/// Create `UpdateDelegation` instruction
+pub fn update_delegation(
     program_id: &Pubkey,
-    status: CollateralStatus,
-    ratio_initial: u64,
-    ratio_healthy: u64,
-    collateral: &Pubkey,
-    market: &Pubkey,
-    market_owner: &Pubkey,
+    allowance: u64,
+    delegation: &Pubkey,
+    obligation: &Pubkey,
+    obligation_owner: &Pubkey,
+    position_token_account: Option<&Pubkey>,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::UpdateCollateralToken {
-        status,
-        ratio_initial,
-        ratio_healthy,
-    };
+    let init_data = LendingInstruction::UpdateDelegation { allowance };
     let data = init_data.try_to_vec()?;
 
-    let accounts = vec![
-        AccountMeta::new(*collateral, false),
-        AccountMeta::new_readonly(*market, false),
-        AccountMeta::new_readonly(*market_owner, true),
+    let mut accounts = vec![
+        AccountMeta::new(*delegation, false),
+        AccountMeta::new_readonly(*obligation, false),
+        AccountMeta::new_readonly(*obligation_owner, true),
     ];
 
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -343,35 +2980,52 @@ pub fn update_collateral_token(
     })
 }
 
-/// Create `LiquidityDeposit` instruction
+/// Create `ObligationLiquidityBorrowDelegated` instruction
 #[allow(clippy::too_many_arguments)]
-pub fn liquidity_deposit(
+pub fn obligation_liquidity_borrow_delegated(
     program_id: &Pubkey,
     amount: u64,
+    obligation: &Pubkey,
     liquidity: &Pubkey,
-    source: &Pubkey,
+    collateral: &Pubkey,
+    pair: &Pubkey,
     destination: &Pubkey,
-    token_account: &Pubkey,
-    pool_mint: &Pubkey,
+    liquidity_token_account: &Pubkey,
+    liquidity_token_mint: &Pubkey,
     market: &Pubkey,
-    user_transfer_authority: &Pubkey,
+    delegate: &Pubkey,
+    delegation: &Pubkey,
+    token_program_id: &Pubkey,
+    position_token_account: Option<&Pubkey>,
+    category: Option<&Pubkey>,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::LiquidityDeposit { amount };
+    let init_data = LendingInstruction::ObligationLiquidityBorrowDelegated { amount };
     let data = init_data.try_to_vec()?;
     let (market_authority, _) = find_program_address(program_id, market);
 
-    let accounts = vec![
-        AccountMeta::new_readonly(*liquidity, false),
-        AccountMeta::new(*source, false),
+    let mut accounts = vec![
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new(*liquidity, false),
+        AccountMeta::new(*collateral, false),
+        AccountMeta::new_readonly(*pair, false),
         AccountMeta::new(*destination, false),
-        AccountMeta::new(*token_account, false),
-        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(*liquidity_token_mint, false),
         AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(*delegate, true),
         AccountMeta::new_readonly(market_authority, false),
-        AccountMeta::new_readonly(*user_transfer_authority, true),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new(*delegation, false),
     ];
 
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
+    if let Some(category) = category {
+        accounts.push(AccountMeta::new_readonly(*category, false));
+    }
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -379,33 +3033,20 @@ pub fn liquidity_deposit(
     })
 }
 
-/// Create `LiquidityWithdraw` instruction
-#[allow(clippy::too_many_arguments)]
-pub fn liquidity_withdraw(
+/// Create `TransferObligation` instruction
+pub fn transfer_obligation(
     program_id: &Pubkey,
-    amount: u64,
-    liquidity: &Pubkey,
-    source: &Pubkey,
-    destination: &Pubkey,
-    token_account: &Pubkey,
-    pool_mint: &Pubkey,
-    market: &Pubkey,
-    user_transfer_authority: &Pubkey,
+    obligation: &Pubkey,
+    obligation_owner: &Pubkey,
+    new_obligation_owner: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::LiquidityWithdraw { amount };
+    let init_data = LendingInstruction::TransferObligation;
     let data = init_data.try_to_vec()?;
-    let (market_authority, _) = find_program_address(program_id, market);
 
     let accounts = vec![
-        AccountMeta::new_readonly(*liquidity, false),
-        AccountMeta::new(*source, false),
-        AccountMeta::new(*destination, false),
-        AccountMeta::new(*token_account, false),
-        AccountMeta::new(*pool_mint, false),
-        AccountMeta::new_readonly(*market, false),
-        AccountMeta::new_readonly(market_authority, false),
-        AccountMeta::new_readonly(*user_transfer_authority, true),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new_readonly(*obligation_owner, true),
+        AccountMeta::new_readonly(*new_obligation_owner, false),
     ];
 
     Ok(Instruction {
@@ -415,29 +3056,29 @@ pub fn liquidity_withdraw(
     })
 }
 
-/// Create `CreateObligation` instruction
-pub fn create_obligation(
+/// Create `TokenizeObligation` instruction
+pub fn tokenize_obligation(
     program_id: &Pubkey,
     obligation: &Pubkey,
-    liquidity: &Pubkey,
-    collateral: &Pubkey,
+    position_mint: &Pubkey,
+    position_token_account: &Pubkey,
     market: &Pubkey,
-    owner: &Pubkey,
+    obligation_owner: &Pubkey,
+    token_program_id: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::CreateObligation;
+    let init_data = LendingInstruction::TokenizeObligation;
     let data = init_data.try_to_vec()?;
-    let (obligation_authority, _) =
-        find_obligation_authority(program_id, owner, market, liquidity, collateral);
+    let (market_authority, _) = find_program_address(program_id, market);
 
     let accounts = vec![
         AccountMeta::new(*obligation, false),
-        AccountMeta::new_readonly(*liquidity, false),
-        AccountMeta::new_readonly(*collateral, false),
+        AccountMeta::new(*position_mint, false),
+        AccountMeta::new(*position_token_account, false),
         AccountMeta::new_readonly(*market, false),
-        AccountMeta::new_readonly(obligation_authority, false),
-        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(market_authority, false),
+        AccountMeta::new_readonly(*obligation_owner, true),
         AccountMeta::new_readonly(sysvar::rent::id(), false),
-        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
     ];
 
     Ok(Instruction {
@@ -447,29 +3088,20 @@ pub fn create_obligation(
     })
 }
 
-/// Create `ObligationCollateralDeposit` instruction
-#[allow(clippy::too_many_arguments)]
-pub fn obligation_collateral_deposit(
+/// Create `ClaimObligation` instruction
+pub fn claim_obligation(
     program_id: &Pubkey,
-    amount: u64,
     obligation: &Pubkey,
-    collateral: &Pubkey,
-    source: &Pubkey,
-    collateral_token_account: &Pubkey,
-    market: &Pubkey,
-    user_transfer_authority: &Pubkey,
+    position_token_account: &Pubkey,
+    position_token_account_owner: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::ObligationCollateralDeposit { amount };
+    let init_data = LendingInstruction::ClaimObligation;
     let data = init_data.try_to_vec()?;
 
     let accounts = vec![
         AccountMeta::new(*obligation, false),
-        AccountMeta::new_readonly(*collateral, false),
-        AccountMeta::new(*source, false),
-        AccountMeta::new(*collateral_token_account, false),
-        AccountMeta::new_readonly(*market, false),
-        AccountMeta::new_readonly(*user_transfer_authority, true),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*position_token_account, false),
+        AccountMeta::new_readonly(*position_token_account_owner, true),
     ];
 
     Ok(Instruction {
@@ -479,33 +3111,27 @@ pub fn obligation_collateral_deposit(
     })
 }
 
-/// Create `ObligationCollateralWithdraw` instruction
-#[allow(clippy::too_many_arguments)]
-pub fn obligation_collateral_withdraw(
+/// Create `SetObligationOperator` instruction
+pub fn set_obligation_operator(
     program_id: &Pubkey,
-    amount: u64,
     obligation: &Pubkey,
-    collateral: &Pubkey,
-    destination: &Pubkey,
-    collateral_token_account: &Pubkey,
-    market: &Pubkey,
     obligation_owner: &Pubkey,
+    operator: &Pubkey,
+    position_token_account: Option<&Pubkey>,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::ObligationCollateralWithdraw { amount };
+    let init_data = LendingInstruction::SetObligationOperator;
     let data = init_data.try_to_vec()?;
-    let (market_authority, _) = find_program_address(program_id, market);
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*obligation, false),
-        AccountMeta::new_readonly(*collateral, false),
-        AccountMeta::new(*destination, false),
-        AccountMeta::new(*collateral_token_account, false),
-        AccountMeta::new_readonly(*market, false),
         AccountMeta::new_readonly(*obligation_owner, true),
-        AccountMeta::new_readonly(market_authority, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*operator, false),
     ];
 
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -513,35 +3139,25 @@ pub fn obligation_collateral_withdraw(
     })
 }
 
-/// Create `ObligationLiquidityBorrow` instruction
-#[allow(clippy::too_many_arguments)]
-pub fn obligation_liquidity_borrow(
+/// Create `ClearObligationOperator` instruction
+pub fn clear_obligation_operator(
     program_id: &Pubkey,
-    amount: u64,
     obligation: &Pubkey,
-    liquidity: &Pubkey,
-    collateral: &Pubkey,
-    destination: &Pubkey,
-    liquidity_token_account: &Pubkey,
-    market: &Pubkey,
-    obligation_owner: &Pubkey,
+    authority: &Pubkey,
+    position_token_account: Option<&Pubkey>,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::ObligationLiquidityBorrow { amount };
+    let init_data = LendingInstruction::ClearObligationOperator;
     let data = init_data.try_to_vec()?;
-    let (market_authority, _) = find_program_address(program_id, market);
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*obligation, false),
-        AccountMeta::new(*liquidity, false),
-        AccountMeta::new_readonly(*collateral, false),
-        AccountMeta::new(*destination, false),
-        AccountMeta::new(*liquidity_token_account, false),
-        AccountMeta::new_readonly(*market, false),
-        AccountMeta::new_readonly(*obligation_owner, true),
-        AccountMeta::new_readonly(market_authority, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*authority, true),
     ];
 
+    if let Some(position_token_account) = position_token_account {
+        accounts.push(AccountMeta::new_readonly(*position_token_account, false));
+    }
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -549,29 +3165,32 @@ pub fn obligation_liquidity_borrow(
     })
 }
 
-/// Create `ObligationLiquidityRepay` instruction
+/// Create `UpdateLiquidityBorrowRate` instruction
 #[allow(clippy::too_many_arguments)]
-pub fn obligation_liquidity_repay(
+pub fn update_liquidity_borrow_rate(
     program_id: &Pubkey,
-    amount: u64,
-    obligation: &Pubkey,
+    optimal_utilization: u64,
+    borrow_rate_min: u64,
+    borrow_rate_optimal: u64,
+    borrow_rate_max: u64,
     liquidity: &Pubkey,
-    source: &Pubkey,
     liquidity_token_account: &Pubkey,
     market: &Pubkey,
-    user_transfer_authority: &Pubkey,
+    market_owner: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = LendingInstruction::ObligationLiquidityRepay { amount };
+    let init_data = LendingInstruction::UpdateLiquidityBorrowRate {
+        optimal_utilization,
+        borrow_rate_min,
+        borrow_rate_optimal,
+        borrow_rate_max,
+    };
     let data = init_data.try_to_vec()?;
 
     let accounts = vec![
-        AccountMeta::new(*obligation, false),
         AccountMeta::new(*liquidity, false),
-        AccountMeta::new(*source, false),
-        AccountMeta::new(*liquidity_token_account, false),
+        AccountMeta::new_readonly(*liquidity_token_account, false),
         AccountMeta::new_readonly(*market, false),
-        AccountMeta::new_readonly(*user_transfer_authority, true),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*market_owner, true),
     ];
 
     Ok(Instruction {
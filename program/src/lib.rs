@@ -3,9 +3,13 @@
 //! Everlend Lending Contract
 
 pub mod error;
+pub mod event;
 pub mod instruction;
+pub mod math;
 pub mod processor;
 pub mod state;
+pub mod swap;
+pub mod token;
 
 #[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
@@ -21,21 +25,71 @@ pub fn find_program_address(program_id: &Pubkey, pubkey: &Pubkey) -> (Pubkey, u8
     Pubkey::find_program_address(&[&pubkey.to_bytes()[..32]], program_id)
 }
 
-/// Generates obligation authority & bump seed
+/// Seed of the obligation account created from the obligation authority,
+/// the first sub-account keeps the seed used before sub-accounts were introduced
+pub fn obligation_seed(index: u16) -> String {
+    if index == 0 {
+        "obligation".to_string()
+    } else {
+        format!("obligation{}", index)
+    }
+}
+
+/// Generates obligation authority & bump seed, the obligation assets aren't in the seeds
+/// as the collateral swap and the debt refinance change them
 pub fn find_obligation_authority(
     program_id: &Pubkey,
     owner: &Pubkey,
     market: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[&owner.to_bytes()[..32], &market.to_bytes()[..32]],
+        program_id,
+    )
+}
+
+/// Generates pair authority & bump seed
+pub fn find_pair_authority(
+    program_id: &Pubkey,
+    market: &Pubkey,
     liquidity: &Pubkey,
     collateral: &Pubkey,
 ) -> (Pubkey, u8) {
     Pubkey::find_program_address(
         &[
-            &owner.to_bytes()[..32],
             &market.to_bytes()[..32],
             &liquidity.to_bytes()[..32],
             &collateral.to_bytes()[..32],
+            b"pair",
+        ],
+        program_id,
+    )
+}
+
+/// Generates delegation authority & bump seed
+pub fn find_delegation_authority(
+    program_id: &Pubkey,
+    obligation: &Pubkey,
+    delegate: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            &obligation.to_bytes()[..32],
+            &delegate.to_bytes()[..32],
+            b"delegation",
         ],
         program_id,
     )
 }
+
+/// Generates temporary native token account address & bump seed
+pub fn find_native_account_address(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    market: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[&owner.to_bytes()[..32], &market.to_bytes()[..32], b"native"],
+        program_id,
+    )
+}
@@ -0,0 +1,43 @@
+//! Common module for Decimal and Rate
+
+use crate::state::RATIO_POWER;
+use solana_program::program_error::ProgramError;
+
+/// Scale of precision
+pub const SCALE: usize = 18;
+/// Identity
+pub const WAD: u64 = 1_000_000_000_000_000_000;
+/// Half of identity
+pub const HALF_WAD: u64 = 500_000_000_000_000_000;
+/// Scaler between the wad and the ratio encoding of the state
+pub const RATIO_SCALER: u64 = WAD / RATIO_POWER;
+
+/// Try to subtract, return an error on underflow
+pub trait TrySub: Sized {
+    /// Subtract
+    fn try_sub(self, rhs: Self) -> Result<Self, ProgramError>;
+}
+
+/// Try to add, return an error on overflow
+pub trait TryAdd: Sized {
+    /// Add
+    fn try_add(self, rhs: Self) -> Result<Self, ProgramError>;
+}
+
+/// Try to divide, return an error on overflow or divide by zero
+pub trait TryDiv<RHS>: Sized {
+    /// Divide, rounding down
+    fn try_div(self, rhs: RHS) -> Result<Self, ProgramError>;
+}
+
+/// Try to divide rounding up, return an error on overflow or divide by zero
+pub trait TryDivCeil<RHS>: Sized {
+    /// Divide, rounding up
+    fn try_div_ceil(self, rhs: RHS) -> Result<Self, ProgramError>;
+}
+
+/// Try to multiply, return an error on overflow
+pub trait TryMul<RHS>: Sized {
+    /// Multiply, rounding down
+    fn try_mul(self, rhs: RHS) -> Result<Self, ProgramError>;
+}
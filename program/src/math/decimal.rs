@@ -0,0 +1,249 @@
+//! Math for preserving precision of token amounts which are limited
+//! by the SPL Token program to be at most u64::MAX.
+//!
+//! Decimals are internally scaled by a WAD (10^18) to preserve
+//! precision up to 18 decimal places. Decimals are sized to support
+//! both serialization and precise math for the full range of
+//! unsigned 64-bit integers. The underlying representation is a
+//! u192 rather than u256 to reduce compute cost while losing
+//! support for arithmetic operations at the high end of u64 range.
+
+use super::*;
+use crate::error::LendingError;
+use solana_program::program_error::ProgramError;
+use std::{convert::TryFrom, fmt};
+
+// U192 with 192 bits consisting of 3 x 64-bit words
+#[allow(clippy::all)]
+mod u192 {
+    uint::construct_uint! {
+        pub struct U192(3);
+    }
+}
+pub use u192::U192;
+
+/// Large decimal values, precise to 18 digits
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Decimal(pub U192);
+
+impl Decimal {
+    /// One
+    pub fn one() -> Self {
+        Self(Self::wad())
+    }
+
+    /// Zero
+    pub fn zero() -> Self {
+        Self(U192::zero())
+    }
+
+    fn wad() -> U192 {
+        U192::from(WAD)
+    }
+
+    fn half_wad() -> U192 {
+        U192::from(HALF_WAD)
+    }
+
+    /// Create scaled decimal from the ratio encoding of the state
+    pub fn from_ratio(ratio: u64) -> Self {
+        Self(U192::from(ratio) * U192::from(RATIO_SCALER))
+    }
+
+    /// Return raw scaled value if it fits within u128
+    pub fn to_scaled_val(&self) -> Result<u128, ProgramError> {
+        u128::try_from(self.0).map_err(|_| LendingError::CalculationFailure.into())
+    }
+
+    /// Create decimal from scaled value
+    pub fn from_scaled_val(scaled_val: u128) -> Self {
+        Self(U192::from(scaled_val))
+    }
+
+    /// Create decimal from the little-endian scaled value stored in the state
+    pub fn from_le_bytes(bytes: [u8; 24]) -> Self {
+        Self(U192::from_little_endian(&bytes))
+    }
+
+    /// Return the little-endian scaled value to store in the state
+    pub fn to_le_bytes(&self) -> [u8; 24] {
+        let mut bytes = [0; 24];
+        self.0.to_little_endian(&mut bytes);
+        bytes
+    }
+
+    /// Round scaled decimal to u64
+    pub fn try_round_u64(&self) -> Result<u64, ProgramError> {
+        let rounded_val = Self::half_wad()
+            .checked_add(self.0)
+            .ok_or(LendingError::CalculationFailure)?
+            .checked_div(Self::wad())
+            .ok_or(LendingError::CalculationFailure)?;
+        u64::try_from(rounded_val).map_err(|_| LendingError::CalculationFailure.into())
+    }
+
+    /// Ceiling scaled decimal to u64
+    pub fn try_ceil_u64(&self) -> Result<u64, ProgramError> {
+        let ceil_val = div_ceil(self.0, Self::wad())?;
+        u64::try_from(ceil_val).map_err(|_| LendingError::CalculationFailure.into())
+    }
+
+    /// Floor scaled decimal to u64
+    pub fn try_floor_u64(&self) -> Result<u64, ProgramError> {
+        let floor_val = self
+            .0
+            .checked_div(Self::wad())
+            .ok_or(LendingError::CalculationFailure)?;
+        u64::try_from(floor_val).map_err(|_| LendingError::CalculationFailure.into())
+    }
+
+    /// Ceiling scaled decimal to the ratio encoding of the state
+    pub fn try_ceil_ratio(&self) -> Result<u64, ProgramError> {
+        let ceil_val = div_ceil(self.0, U192::from(RATIO_SCALER))?;
+        u64::try_from(ceil_val).map_err(|_| LendingError::CalculationFailure.into())
+    }
+
+    /// Floor scaled decimal to the ratio encoding of the state
+    pub fn try_floor_ratio(&self) -> Result<u64, ProgramError> {
+        let floor_val = self
+            .0
+            .checked_div(U192::from(RATIO_SCALER))
+            .ok_or(LendingError::CalculationFailure)?;
+        u64::try_from(floor_val).map_err(|_| LendingError::CalculationFailure.into())
+    }
+}
+
+/// Divide the numerator by the denominator, rounding up
+fn div_ceil(numerator: U192, denominator: U192) -> Result<U192, ProgramError> {
+    let result = denominator
+        .checked_sub(U192::from(1u64))
+        .ok_or(LendingError::CalculationFailure)?
+        .checked_add(numerator)
+        .ok_or(LendingError::CalculationFailure)?
+        .checked_div(denominator)
+        .ok_or(LendingError::CalculationFailure)?;
+    Ok(result)
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut scaled_val = self.0.to_string();
+        if scaled_val.len() <= SCALE {
+            scaled_val.insert_str(0, &vec!["0"; SCALE - scaled_val.len()].join(""));
+            scaled_val.insert_str(0, "0.");
+        } else {
+            scaled_val.insert(scaled_val.len() - SCALE, '.');
+        }
+        f.write_str(&scaled_val)
+    }
+}
+
+impl From<u64> for Decimal {
+    fn from(val: u64) -> Self {
+        Self(Self::wad() * U192::from(val))
+    }
+}
+
+impl From<Rate> for Decimal {
+    fn from(val: Rate) -> Self {
+        Self(U192::from(val.to_scaled_val()))
+    }
+}
+
+impl TryAdd for Decimal {
+    fn try_add(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_add(rhs.0)
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TrySub for Decimal {
+    fn try_sub(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_sub(rhs.0)
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TryDiv<u64> for Decimal {
+    fn try_div(self, rhs: u64) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_div(U192::from(rhs))
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TryDiv<Rate> for Decimal {
+    fn try_div(self, rhs: Rate) -> Result<Self, ProgramError> {
+        self.try_div(Self::from(rhs))
+    }
+}
+
+impl TryDiv<Decimal> for Decimal {
+    fn try_div(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_mul(Self::wad())
+                .ok_or(LendingError::CalculationFailure)?
+                .checked_div(rhs.0)
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TryDivCeil<u64> for Decimal {
+    fn try_div_ceil(self, rhs: u64) -> Result<Self, ProgramError> {
+        Ok(Self(div_ceil(self.0, U192::from(rhs))?))
+    }
+}
+
+impl TryDivCeil<Rate> for Decimal {
+    fn try_div_ceil(self, rhs: Rate) -> Result<Self, ProgramError> {
+        self.try_div_ceil(Self::from(rhs))
+    }
+}
+
+impl TryDivCeil<Decimal> for Decimal {
+    fn try_div_ceil(self, rhs: Self) -> Result<Self, ProgramError> {
+        let numerator = self
+            .0
+            .checked_mul(Self::wad())
+            .ok_or(LendingError::CalculationFailure)?;
+        Ok(Self(div_ceil(numerator, rhs.0)?))
+    }
+}
+
+impl TryMul<u64> for Decimal {
+    fn try_mul(self, rhs: u64) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_mul(U192::from(rhs))
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TryMul<Rate> for Decimal {
+    fn try_mul(self, rhs: Rate) -> Result<Self, ProgramError> {
+        self.try_mul(Self::from(rhs))
+    }
+}
+
+impl TryMul<Decimal> for Decimal {
+    fn try_mul(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_mul(rhs.0)
+                .ok_or(LendingError::CalculationFailure)?
+                .checked_div(Self::wad())
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
@@ -0,0 +1,12 @@
+//! Fixed-point math for token amounts and ratios
+//!
+//! Calculations round in favour of the protocol: amounts paid out by the program
+//! are rounded down, amounts paid in, debts and required collateral are rounded up.
+
+mod common;
+mod decimal;
+mod rate;
+
+pub use common::*;
+pub use decimal::*;
+pub use rate::*;
@@ -0,0 +1,151 @@
+//! Math for preserving precision of ratios and percentages.
+//!
+//! Usage of the program ratios is small, so the rates are sized with
+//! u128 to reduce compute cost. Use `Decimal` for token amounts.
+//!
+//! Rates are internally scaled by a WAD (10^18) to preserve
+//! precision up to 18 decimal places.
+
+use super::*;
+use crate::error::LendingError;
+use solana_program::program_error::ProgramError;
+use std::{convert::TryFrom, fmt};
+
+// U128 with 128 bits consisting of 2 x 64-bit words
+#[allow(clippy::all)]
+mod u128 {
+    uint::construct_uint! {
+        pub struct U128(2);
+    }
+}
+pub use u128::U128;
+
+/// Small decimal values, precise to 18 digits
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Rate(pub U128);
+
+impl Rate {
+    /// One
+    pub fn one() -> Self {
+        Self(Self::wad())
+    }
+
+    /// Zero
+    pub fn zero() -> Self {
+        Self(U128::zero())
+    }
+
+    fn wad() -> U128 {
+        U128::from(WAD)
+    }
+
+    /// Create scaled rate from the ratio encoding of the state
+    pub fn from_ratio(ratio: u64) -> Self {
+        Self(U128::from(ratio) * U128::from(RATIO_SCALER))
+    }
+
+    /// Return raw scaled value
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_scaled_val(&self) -> u128 {
+        self.0.as_u128()
+    }
+
+    /// Create rate from scaled value
+    pub fn from_scaled_val(scaled_val: u128) -> Self {
+        Self(U128::from(scaled_val))
+    }
+
+    /// Ceiling scaled rate to the ratio encoding of the state
+    pub fn try_ceil_ratio(&self) -> Result<u64, ProgramError> {
+        Decimal::from(*self).try_ceil_ratio()
+    }
+
+    /// Floor scaled rate to the ratio encoding of the state
+    pub fn try_floor_ratio(&self) -> Result<u64, ProgramError> {
+        Decimal::from(*self).try_floor_ratio()
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut scaled_val = self.0.to_string();
+        if scaled_val.len() <= SCALE {
+            scaled_val.insert_str(0, &vec!["0"; SCALE - scaled_val.len()].join(""));
+            scaled_val.insert_str(0, "0.");
+        } else {
+            scaled_val.insert(scaled_val.len() - SCALE, '.');
+        }
+        f.write_str(&scaled_val)
+    }
+}
+
+impl TryFrom<Decimal> for Rate {
+    type Error = ProgramError;
+    fn try_from(decimal: Decimal) -> Result<Self, Self::Error> {
+        Ok(Self(U128::from(decimal.to_scaled_val()?)))
+    }
+}
+
+impl TryAdd for Rate {
+    fn try_add(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_add(rhs.0)
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TrySub for Rate {
+    fn try_sub(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_sub(rhs.0)
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TryDiv<u64> for Rate {
+    fn try_div(self, rhs: u64) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_div(U128::from(rhs))
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TryDiv<Rate> for Rate {
+    fn try_div(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_mul(Self::wad())
+                .ok_or(LendingError::CalculationFailure)?
+                .checked_div(rhs.0)
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TryMul<u64> for Rate {
+    fn try_mul(self, rhs: u64) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_mul(U128::from(rhs))
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
+
+impl TryMul<Rate> for Rate {
+    fn try_mul(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(
+            self.0
+                .checked_mul(rhs.0)
+                .ok_or(LendingError::CalculationFailure)?
+                .checked_div(Self::wad())
+                .ok_or(LendingError::CalculationFailure)?,
+        ))
+    }
+}
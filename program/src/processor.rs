@@ -1,12 +1,24 @@
 //! Program state processor
 
 use crate::{error::LendingError, instruction::LendingInstruction};
-use crate::{find_obligation_authority, find_program_address, state::*};
+use crate::{
+    event::{ConfigUpdate, LendingEvent},
+    find_delegation_authority, find_native_account_address, find_obligation_authority,
+    find_pair_authority, find_program_address, obligation_seed,
+    state::*,
+    swap,
+    token::{
+        assert_supported_mint, assert_token_program, has_transfer_fee, is_supported_token_program,
+        unpack_mint, unpack_token_account,
+    },
+};
 use borsh::BorshDeserialize;
 use solana_program::program_pack::IsInitialized;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
+    instruction::AccountMeta,
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
@@ -16,7 +28,7 @@ use solana_program::{
     system_instruction,
     sysvar::Sysvar,
 };
-use spl_token::state::{Account, Mint};
+use spl_token::state::Account;
 
 /// Program state handler.
 pub struct Processor {}
@@ -65,7 +77,7 @@ impl Processor {
         let market_authority_info = next_account_info(account_info_iter)?;
         let rent_info = next_account_info(account_info_iter)?;
         let _system_program_info = next_account_info(account_info_iter)?;
-        let _token_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
         let rent = &Rent::from_account_info(rent_info)?;
 
         if !market_owner_info.is_signer {
@@ -77,6 +89,9 @@ impl Processor {
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
+        assert_token_program(token_program_info, token_mint_info)?;
+        assert_supported_mint(token_mint_info)?;
+
         // Get market state
         let mut market = Market::unpack(&market_info.data.borrow())?;
 
@@ -105,7 +120,7 @@ impl Processor {
         let mut liquidity = Liquidity::unpack_unchecked(&liquidity_info.data.borrow())?;
         assert_uninitialized(&liquidity)?;
 
-        let token_mint = Mint::unpack(&token_mint_info.data.borrow())?;
+        let token_mint = unpack_mint(&token_mint_info.data.borrow())?;
 
         // Initialize token account for spl token
         spl_initialize_account(
@@ -113,6 +128,7 @@ impl Processor {
             token_mint_info.clone(),
             market_authority_info.clone(),
             rent_info.clone(),
+            token_program_info.clone(),
         )?;
 
         // Initialize mint (token) for pool
@@ -120,6 +136,7 @@ impl Processor {
             pool_mint_info.clone(),
             market_authority_info.clone(),
             rent_info.clone(),
+            token_program_info.clone(),
             token_mint.decimals,
         )?;
 
@@ -129,6 +146,7 @@ impl Processor {
             token_mint: *token_mint_info.key,
             token_account: *token_account_info.key,
             pool_mint: *pool_mint_info.key,
+            interest_slot: Clock::get()?.slot,
         });
         market.increase_liquidity_tokens();
 
@@ -174,6 +192,14 @@ impl Processor {
 
         Liquidity::pack(liquidity, *liquidity_info.data.borrow_mut())?;
 
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::Liquidity {
+                liquidity: *liquidity_info.key,
+                status,
+            },
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -193,7 +219,7 @@ impl Processor {
         let market_authority_info = next_account_info(account_info_iter)?;
         let rent_info = next_account_info(account_info_iter)?;
         let _system_program_info = next_account_info(account_info_iter)?;
-        let _token_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
         let rent = &Rent::from_account_info(rent_info)?;
 
         if !market_owner_info.is_signer {
@@ -205,6 +231,9 @@ impl Processor {
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
+        assert_token_program(token_program_info, token_mint_info)?;
+        assert_supported_mint(token_mint_info)?;
+
         // Get market state
         let mut market = Market::unpack(&market_info.data.borrow())?;
 
@@ -239,6 +268,7 @@ impl Processor {
             token_mint_info.clone(),
             market_authority_info.clone(),
             rent_info.clone(),
+            token_program_info.clone(),
         )?;
 
         // Update collateral state & increase collateral tokens counter
@@ -297,6 +327,16 @@ impl Processor {
 
         Collateral::pack(collateral, *collateral_info.data.borrow_mut())?;
 
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::Collateral {
+                collateral: *collateral_info.key,
+                status,
+                ratio_initial,
+                ratio_healthy,
+            },
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -304,6 +344,7 @@ impl Processor {
     pub fn liquidity_deposit(
         program_id: &Pubkey,
         amount: u64,
+        min_pool_tokens_out: u64,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
@@ -311,19 +352,28 @@ impl Processor {
         let source_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
         let token_account_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
         let pool_mint_info = next_account_info(account_info_iter)?;
         let market_info = next_account_info(account_info_iter)?;
         let market_authority_info = next_account_info(account_info_iter)?;
         let user_transfer_authority_info = next_account_info(account_info_iter)?;
-        let _token_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
 
         if market_info.owner != program_id {
             msg!("Market provided is not owned by the market program");
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        assert_token_program(token_program_info, token_account_info)?;
+
         // Get liquidity state
-        let liquidity = Liquidity::unpack(&liquidity_info.data.borrow())?;
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
 
         if liquidity.market != *market_info.key {
             msg!("Liquidity market does not match the market provided");
@@ -335,25 +385,55 @@ impl Processor {
             return Err(ProgramError::InvalidArgument);
         }
 
+        if liquidity.token_mint != *token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
         if liquidity.pool_mint != *pool_mint_info.key {
             msg!("Liquidity pool mint does not match the pool mint provided");
             return Err(ProgramError::InvalidArgument);
         }
 
         // TODO: We can store total values in the liquidity state
-        let token_account_amount =
-            Account::unpack_unchecked(&token_account_info.data.borrow())?.amount;
-        let pool_mint_supply = Mint::unpack_unchecked(&pool_mint_info.data.borrow())?.supply;
+        let token_account_amount = unpack_token_account(&token_account_info.data.borrow())?.amount;
+        let pool_mint_supply = unpack_mint(&pool_mint_info.data.borrow())?.supply;
+
+        // Pool tokens are minted at the exchange rate with the interest accrued
+        liquidity.accrue_interest(token_account_amount, Clock::get()?.slot)?;
 
         // Transfer liquidity from source provider to token account
         spl_token_transfer(
             source_info.clone(),
+            token_mint_info.clone(),
             token_account_info.clone(),
             user_transfer_authority_info.clone(),
+            token_program_info.clone(),
             amount,
             &[],
         )?;
 
+        // Transfer fees of the token mint are charged from the amount received
+        let received_amount = unpack_token_account(&token_account_info.data.borrow())?
+            .amount
+            .checked_sub(token_account_amount)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        let pool_tokens_amount = liquidity.calc_deposit_exchange_amount(
+            received_amount,
+            token_account_amount,
+            pool_mint_supply,
+        )?;
+
+        if pool_tokens_amount < min_pool_tokens_out {
+            msg!(
+                "Pool tokens amount {} is less than the minimum {}",
+                pool_tokens_amount,
+                min_pool_tokens_out
+            );
+            return Err(LendingError::SlippageExceeded.into());
+        }
+
         let (_, bump_seed) = find_program_address(program_id, market_info.key);
         let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
 
@@ -362,14 +442,19 @@ impl Processor {
             pool_mint_info.clone(),
             destination_info.clone(),
             market_authority_info.clone(),
-            liquidity.calc_deposit_exchange_amount(
-                amount,
-                token_account_amount,
-                pool_mint_supply,
-            )?,
+            token_program_info.clone(),
+            pool_tokens_amount,
             &[signers_seeds],
         )?;
 
+        LendingEvent::Deposit {
+            liquidity: *liquidity_info.key,
+            amount: received_amount,
+            pool_tokens_amount,
+            exchange_rate: liquidity.calc_exchange_rate(token_account_amount, pool_mint_supply)?,
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -377,6 +462,7 @@ impl Processor {
     pub fn liquidity_withdraw(
         program_id: &Pubkey,
         amount: u64,
+        min_liquidity_out: u64,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
@@ -384,19 +470,28 @@ impl Processor {
         let source_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
         let token_account_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
         let pool_mint_info = next_account_info(account_info_iter)?;
         let market_info = next_account_info(account_info_iter)?;
         let market_authority_info = next_account_info(account_info_iter)?;
         let user_transfer_authority_info = next_account_info(account_info_iter)?;
-        let _token_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
 
         if market_info.owner != program_id {
             msg!("Market provided is not owned by the market program");
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        assert_token_program(token_program_info, token_account_info)?;
+
         // Get liquidity state
-        let liquidity = Liquidity::unpack(&liquidity_info.data.borrow())?;
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
 
         if liquidity.market != *market_info.key {
             msg!("Liquidity market does not match the market provided");
@@ -408,20 +503,66 @@ impl Processor {
             return Err(ProgramError::InvalidArgument);
         }
 
+        if liquidity.token_mint != *token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
         if liquidity.pool_mint != *pool_mint_info.key {
             msg!("Liquidity pool mint does not match the pool mint provided");
             return Err(ProgramError::InvalidArgument);
         }
 
-        let token_account_amount =
-            Account::unpack_unchecked(&token_account_info.data.borrow())?.amount;
-        let pool_mint_supply = Mint::unpack_unchecked(&pool_mint_info.data.borrow())?.supply;
+        // Withdraw the whole pool token balance of the source
+        let amount = if amount == u64::MAX {
+            let amount = unpack_token_account(&source_info.data.borrow())?.amount;
+            msg!("Withdraw amount: {}", amount);
+            amount
+        } else {
+            amount
+        };
+
+        let token_account_amount = unpack_token_account(&token_account_info.data.borrow())?.amount;
+        let pool_mint_supply = unpack_mint(&pool_mint_info.data.borrow())?.supply;
+
+        // Liquidity is paid out at the exchange rate with the interest accrued
+        let slot = Clock::get()?.slot;
+        liquidity.accrue_interest(token_account_amount, slot)?;
+
+        let liquidity_amount = liquidity.calc_withdraw_exchange_amount(
+            amount,
+            token_account_amount,
+            pool_mint_supply,
+        )?;
+
+        if liquidity_amount < min_liquidity_out {
+            msg!(
+                "Liquidity amount {} is less than the minimum {}",
+                liquidity_amount,
+                min_liquidity_out
+            );
+            return Err(LendingError::SlippageExceeded.into());
+        }
+
+        // Liquidity in the token account pays out the withdrawal queue first
+        if liquidity_amount > liquidity.calc_free_amount(token_account_amount)? {
+            msg!("Liquidity is reserved for the withdrawal queue");
+            return Err(LendingError::UtilizationExceeded.into());
+        }
+
+        // Lenders can't withdraw the liquidity buffer kept by the maximum utilization
+        let token_account_amount_after = token_account_amount
+            .checked_sub(liquidity_amount)
+            .ok_or(LendingError::UtilizationExceeded)?;
+        liquidity.check_utilization(token_account_amount_after)?;
+        liquidity.record_outflow(liquidity_amount, slot)?;
 
         // Burn from soruce provider pool token
         spl_token_burn(
             pool_mint_info.clone(),
             source_info.clone(),
             user_transfer_authority_info.clone(),
+            token_program_info.clone(),
             amount,
             &[],
         )?;
@@ -432,25 +573,140 @@ impl Processor {
         // Transfer liquidity from token account to destination provider
         spl_token_transfer(
             token_account_info.clone(),
+            token_mint_info.clone(),
             destination_info.clone(),
             market_authority_info.clone(),
-            liquidity.calc_withdraw_exchange_amount(
-                amount,
-                token_account_amount,
-                pool_mint_supply,
-            )?,
+            token_program_info.clone(),
+            liquidity_amount,
             &[signers_seeds],
         )?;
 
+        LendingEvent::Withdraw {
+            liquidity: *liquidity_info.key,
+            amount: liquidity_amount,
+            pool_tokens_amount: amount,
+            exchange_rate: liquidity.calc_exchange_rate(token_account_amount, pool_mint_supply)?,
+        }
+        .emit();
+
         Ok(())
     }
 
+    /// Process LiquidityDepositExactOut instruction
+    pub fn liquidity_deposit_exact_out(
+        program_id: &Pubkey,
+        pool_tokens_out: u64,
+        max_amount_in: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let _source_info = next_account_info(account_info_iter)?;
+        let _destination_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // The amount received after a transfer fee can't be known in advance
+        if has_transfer_fee(&token_mint_info.data.borrow())? {
+            msg!("Exact out deposit is not supported for mints with a transfer fee");
+            return Err(LendingError::UnsupportedMintExtension.into());
+        }
+
+        // Accounts are checked by the deposit itself, which also guarantees the pool tokens amount,
+        // the interest accrued here is rolled back with it
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+        let token_account_amount = unpack_token_account(&token_account_info.data.borrow())?.amount;
+        liquidity.accrue_interest(token_account_amount, Clock::get()?.slot)?;
+        let amount = liquidity.calc_deposit_amount_in(
+            pool_tokens_out,
+            token_account_amount,
+            unpack_mint(&pool_mint_info.data.borrow())?.supply,
+        )?;
+
+        if amount > max_amount_in {
+            msg!(
+                "Deposit amount {} is more than the maximum {}",
+                amount,
+                max_amount_in
+            );
+            return Err(LendingError::SlippageExceeded.into());
+        }
+
+        drop(liquidity_data);
+
+        Self::liquidity_deposit(program_id, amount, pool_tokens_out, accounts)
+    }
+
+    /// Process LiquidityWithdrawExactOut instruction
+    pub fn liquidity_withdraw_exact_out(
+        program_id: &Pubkey,
+        liquidity_out: u64,
+        max_pool_tokens_in: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let _source_info = next_account_info(account_info_iter)?;
+        let _destination_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // The amount received after a transfer fee can't be guaranteed
+        if has_transfer_fee(&token_mint_info.data.borrow())? {
+            msg!("Exact out withdraw is not supported for mints with a transfer fee");
+            return Err(LendingError::UnsupportedMintExtension.into());
+        }
+
+        // Accounts are checked by the withdraw itself, which also guarantees the liquidity amount,
+        // the interest accrued here is rolled back with it
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+        let token_account_amount = unpack_token_account(&token_account_info.data.borrow())?.amount;
+        liquidity.accrue_interest(token_account_amount, Clock::get()?.slot)?;
+        let amount = liquidity.calc_withdraw_amount_in(
+            liquidity_out,
+            token_account_amount,
+            unpack_mint(&pool_mint_info.data.borrow())?.supply,
+        )?;
+
+        if amount > max_pool_tokens_in {
+            msg!(
+                "Pool tokens amount {} is more than the maximum {}",
+                amount,
+                max_pool_tokens_in
+            );
+            return Err(LendingError::SlippageExceeded.into());
+        }
+
+        drop(liquidity_data);
+
+        Self::liquidity_withdraw(program_id, amount, liquidity_out, accounts)
+    }
+
     /// Process CreateObligation instruction
-    pub fn create_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    pub fn create_obligation(
+        program_id: &Pubkey,
+        index: u16,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let obligation_info = next_account_info(account_info_iter)?;
         let liquidity_info = next_account_info(account_info_iter)?;
         let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
         let market_info = next_account_info(account_info_iter)?;
         let obligation_authority_info = next_account_info(account_info_iter)?;
         let obligation_owner_info = next_account_info(account_info_iter)?;
@@ -477,6 +733,11 @@ impl Processor {
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
+        if pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
         // Get liquidity state
         let liquidity = Liquidity::unpack(&liquidity_info.data.borrow())?;
 
@@ -503,19 +764,30 @@ impl Processor {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        let (obligation_authority, bump_seed) = find_obligation_authority(
-            program_id,
-            obligation_owner_info.key,
-            market_info.key,
-            liquidity_info.key,
-            collateral_info.key,
-        );
+        // Isolated collateral backs only the approved liquidity, and an obligation
+        // holds a single collateral so it can't be mixed with any other
+        if collateral.isolated {
+            collateral.check_isolated_liquidity(liquidity_info.key)?;
+        }
+
+        // Get pair state
+        let pair_data = pair_info.data.borrow();
+        let pair = Pair::load(&pair_data)?;
+
+        if pair.market != *market_info.key
+            || pair.liquidity != *liquidity_info.key
+            || pair.collateral != *collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (obligation_authority, bump_seed) =
+            find_obligation_authority(program_id, obligation_owner_info.key, market_info.key);
         // TODO: refactor in the future
         let signers_seeds = &[
             &obligation_owner_info.key.to_bytes()[..32],
             &market_info.key.to_bytes()[..32],
-            &liquidity_info.key.to_bytes()[..32],
-            &collateral_info.key.to_bytes()[..32],
             &[bump_seed],
         ];
 
@@ -525,7 +797,7 @@ impl Processor {
             obligation_owner_info.clone(),
             obligation_info.clone(),
             obligation_authority_info.clone(),
-            "obligation",
+            &obligation_seed(index),
             &obligation_authority,
             &[signers_seeds],
             rent,
@@ -541,10 +813,21 @@ impl Processor {
             owner: *obligation_owner_info.key,
             liquidity: *liquidity_info.key,
             collateral: *collateral_info.key,
+            isolated: collateral.isolated,
+            index,
         });
 
         Obligation::pack(obligation, *obligation_info.data.borrow_mut())?;
 
+        LendingEvent::ObligationCreated {
+            obligation: *obligation_info.key,
+            owner: *obligation_owner_info.key,
+            liquidity: *liquidity_info.key,
+            collateral: *collateral_info.key,
+            index,
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -559,9 +842,12 @@ impl Processor {
         let collateral_info = next_account_info(account_info_iter)?;
         let source_info = next_account_info(account_info_iter)?;
         let collateral_token_account_info = next_account_info(account_info_iter)?;
+        let collateral_token_mint_info = next_account_info(account_info_iter)?;
         let market_info = next_account_info(account_info_iter)?;
         let user_transfer_authority_info = next_account_info(account_info_iter)?;
-        let _token_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        assert_token_program(token_program_info, collateral_token_account_info)?;
 
         if market_info.owner != program_id {
             msg!("Market provided is not owned by the market program");
@@ -579,7 +865,8 @@ impl Processor {
         }
 
         // Get obligation state
-        let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
 
         if obligation.collateral != *collateral_info.key {
             msg!("Obligation collateral does not match the collateral provided");
@@ -592,25 +879,51 @@ impl Processor {
         }
 
         // Get collateral state
-        let collateral = Collateral::unpack(&collateral_info.data.borrow())?;
+        let collateral_data = collateral_info.data.borrow();
+        let collateral = Collateral::load(&collateral_data)?;
 
         if collateral.token_account != *collateral_token_account_info.key {
             msg!("Collateral token account does not match the token account provided");
             return Err(ProgramError::InvalidArgument);
         }
 
-        obligation.collateral_deposit(amount)?;
-        Obligation::pack(obligation, *obligation_info.data.borrow_mut())?;
+        if collateral.token_mint != *collateral_token_mint_info.key {
+            msg!("Collateral token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let token_account_amount =
+            unpack_token_account(&collateral_token_account_info.data.borrow())?.amount;
 
         // Transfer collateral from source borrower to token account
         spl_token_transfer(
             source_info.clone(),
+            collateral_token_mint_info.clone(),
             collateral_token_account_info.clone(),
             user_transfer_authority_info.clone(),
+            token_program_info.clone(),
             amount,
             &[],
         )?;
 
+        // Transfer fees of the token mint are charged from the amount received
+        let received_amount = unpack_token_account(&collateral_token_account_info.data.borrow())?
+            .amount
+            .checked_sub(token_account_amount)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        obligation.collateral_deposit(received_amount)?;
+        obligation.reset_unhealthy()?;
+        let health = obligation.calc_health()?;
+
+        LendingEvent::CollateralDeposit {
+            obligation: *obligation_info.key,
+            collateral: *collateral_info.key,
+            amount: received_amount,
+            health,
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -622,41 +935,63 @@ impl Processor {
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
         let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
         let collateral_token_account_info = next_account_info(account_info_iter)?;
+        let collateral_token_mint_info = next_account_info(account_info_iter)?;
         let market_info = next_account_info(account_info_iter)?;
         let obligation_owner_info = next_account_info(account_info_iter)?;
         let market_authority_info = next_account_info(account_info_iter)?;
-        let _token_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
 
         if !obligation_owner_info.is_signer {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
+        assert_token_program(token_program_info, collateral_token_account_info)?;
+
         if market_info.owner != program_id {
             msg!("Market provided is not owned by the market program");
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
         if collateral_info.owner != program_id {
             msg!("Collateral provided is not owned by the market program");
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
+        if pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
         if obligation_info.owner != program_id {
             msg!("Obligation provided is not owned by the market program");
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
         // Get obligation state
-        let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
 
-        if obligation.owner != *obligation_owner_info.key {
+        if obligation_owner(obligation, account_info_iter)? != *obligation_owner_info.key {
             msg!("Obligation owner does not match the owner provided");
             return Err(ProgramError::InvalidArgument);
         }
 
+        if obligation.liquidity != *liquidity_info.key {
+            msg!("Obligation liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
         if obligation.collateral != *collateral_info.key {
             msg!("Obligation collateral does not match the collateral provided");
             return Err(ProgramError::InvalidArgument);
@@ -667,20 +1002,76 @@ impl Processor {
             return Err(ProgramError::InvalidArgument);
         }
 
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.token_account != *liquidity_token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Debt is compounded by the interest the liquidity accrued up to the current slot
+        liquidity.accrue_interest(
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount,
+            Clock::get()?.slot,
+        )?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
         // Get collateral state
-        let collateral = Collateral::unpack(&collateral_info.data.borrow())?;
+        let collateral_data = collateral_info.data.borrow();
+        let collateral = Collateral::load(&collateral_data)?;
 
         if collateral.token_account != *collateral_token_account_info.key {
             msg!("Collateral token account does not match the token account provided");
             return Err(ProgramError::InvalidArgument);
         }
 
+        if collateral.token_mint != *collateral_token_mint_info.key {
+            msg!("Collateral token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get pair state
+        let pair_data = pair_info.data.borrow();
+        let pair = Pair::load(&pair_data)?;
+
+        if pair.market != *market_info.key
+            || pair.liquidity != *liquidity_info.key
+            || pair.collateral != *collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Category risk parameters apply while the obligation assets share the category
+        let ratio_initial = if obligation.category != NO_CATEGORY {
+            category_ratios(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                obligation.category,
+                liquidity.category,
+                collateral.category,
+            )?
+            .map_or(pair.ratio_initial, |(ratio_initial, _)| ratio_initial)
+        } else {
+            pair.ratio_initial
+        };
+
+        // Withdraw all collateral available for the obligation
+        let amount = if amount == u64::MAX {
+            let amount = obligation.calc_withdrawal_limit(ratio_initial)?;
+            msg!("Withdraw amount: {}", amount);
+            amount
+        } else {
+            amount
+        };
+
         obligation.collateral_withdraw(amount)?;
-        
-        // Check obligation health
-        collateral.check_health(obligation.calc_health()?)?;
 
-        Obligation::pack(obligation, *obligation_info.data.borrow_mut())?;
+        // Check obligation health
+        let health = obligation.check_health(ratio_initial)?;
 
         let (_, bump_seed) = find_program_address(program_id, market_info.key);
         let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
@@ -688,12 +1079,22 @@ impl Processor {
         // Transfer collateral from token account to destination borrower
         spl_token_transfer(
             collateral_token_account_info.clone(),
+            collateral_token_mint_info.clone(),
             destination_info.clone(),
             market_authority_info.clone(),
+            token_program_info.clone(),
             amount,
             &[signers_seeds],
         )?;
 
+        LendingEvent::CollateralWithdraw {
+            obligation: *obligation_info.key,
+            collateral: *collateral_info.key,
+            amount,
+            health,
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -703,21 +1104,85 @@ impl Processor {
         amount: u64,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
+        Self::borrow(program_id, amount, accounts, None)?;
+        Ok(())
+    }
+
+    /// Process ObligationLiquidityBorrowDelegated instruction
+    pub fn obligation_liquidity_borrow_delegated(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mut borrow_accounts: Vec<AccountInfo> = account_info_iter.take(11).cloned().collect();
+        let delegation_info = next_account_info(account_info_iter)?;
+        let obligation_info = &borrow_accounts[0];
+        let delegate_info = &borrow_accounts[8];
+
+        if delegation_info.owner != program_id {
+            msg!("Delegation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get delegation state
+        let mut delegation_data = delegation_info.data.borrow_mut();
+        let delegation = Delegation::load_mut(&mut delegation_data)?;
+
+        if delegation.obligation != *obligation_info.key {
+            msg!("Delegation obligation does not match the obligation provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if delegation.delegate != *delegate_info.key {
+            msg!("Delegation delegate does not match the delegate provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Position token and category accounts of the obligation
+        borrow_accounts.extend(account_info_iter.cloned());
+
+        let amount = Self::borrow(program_id, amount, &borrow_accounts, Some(delegation))?;
+        delegation.borrow(amount)?;
+
+        LendingEvent::DelegationUpdated {
+            delegation: *delegation_info.key,
+            obligation: delegation.obligation,
+            delegate: delegation.delegate,
+            allowance: delegation.allowance,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Borrow liquidity for the obligation owner or the delegate within the allowance,
+    /// returns the amount borrowed
+    fn borrow(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+        delegation: Option<&Delegation>,
+    ) -> Result<u64, ProgramError> {
         let account_info_iter = &mut accounts.iter();
         let obligation_info = next_account_info(account_info_iter)?;
         let liquidity_info = next_account_info(account_info_iter)?;
         let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
         let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let liquidity_token_mint_info = next_account_info(account_info_iter)?;
         let market_info = next_account_info(account_info_iter)?;
         let obligation_owner_info = next_account_info(account_info_iter)?;
         let market_authority_info = next_account_info(account_info_iter)?;
-        let _token_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
 
         if !obligation_owner_info.is_signer {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
+        assert_token_program(token_program_info, liquidity_token_account_info)?;
+
         if market_info.owner != program_id {
             msg!("Market provided is not owned by the market program");
             return Err(LendingError::InvalidAccountOwner.into());
@@ -733,17 +1198,32 @@ impl Processor {
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
+        if pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
         if obligation_info.owner != program_id {
             msg!("Obligation provided is not owned by the market program");
             return Err(LendingError::InvalidAccountOwner.into());
         }
 
         // Get obligation state
-        let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
-
-        if obligation.owner != *obligation_owner_info.key {
-            msg!("Obligation owner does not match the owner provided");
-            return Err(ProgramError::InvalidArgument);
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        // Delegate signs instead of the owner, the rest of the delegation is checked by the caller
+        let owner = obligation_owner(obligation, account_info_iter)?;
+        match delegation {
+            Some(delegation) if delegation.owner != owner => {
+                msg!("Delegation was granted by the previous obligation owner");
+                return Err(ProgramError::InvalidArgument);
+            }
+            None if owner != *obligation_owner_info.key => {
+                msg!("Obligation owner does not match the owner provided");
+                return Err(ProgramError::InvalidArgument);
+            }
+            _ => {}
         }
 
         if obligation.liquidity != *liquidity_info.key {
@@ -762,24 +1242,94 @@ impl Processor {
         }
 
         // Get collateral state
-        let collateral = Collateral::unpack(&collateral_info.data.borrow())?;
+        let mut collateral_data = collateral_info.data.borrow_mut();
+        let collateral = Collateral::load_mut(&mut collateral_data)?;
 
         // Get liquidity state
-        let mut liquidity = Liquidity::unpack(&liquidity_info.data.borrow())?;
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
 
         if liquidity.token_account != *liquidity_token_account_info.key {
             msg!("Liquidity token account does not match the token account provided");
             return Err(ProgramError::InvalidArgument);
         }
 
+        if liquidity.token_mint != *liquidity_token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get pair state
+        let pair_data = pair_info.data.borrow();
+        let pair = Pair::load(&pair_data)?;
+
+        if pair.market != *market_info.key
+            || pair.liquidity != *liquidity_info.key
+            || pair.collateral != *collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        pair.check_borrow_enabled()?;
+
+        // Category risk parameters apply while the obligation assets share the category
+        let ratio_initial = if obligation.category != NO_CATEGORY {
+            category_ratios(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                obligation.category,
+                liquidity.category,
+                collateral.category,
+            )?
+            .map_or(pair.ratio_initial, |(ratio_initial, _)| ratio_initial)
+        } else {
+            pair.ratio_initial
+        };
+
+        let token_account_amount =
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount;
+        let slot = Clock::get()?.slot;
+        liquidity.accrue_interest(token_account_amount, slot)?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
+        // Borrowers can't take the liquidity buffer kept by the maximum utilization
+        let available_amount = liquidity.calc_available_amount(token_account_amount)?;
+
+        // Borrow all liquidity available for the obligation, the delegate within the allowance
+        let amount = if amount == u64::MAX {
+            let amount = obligation
+                .calc_borrowing_limit(ratio_initial)?
+                .min(available_amount)
+                .min(delegation.map_or(u64::MAX, |delegation| delegation.allowance));
+            msg!("Borrow amount: {}", amount);
+            amount
+        } else {
+            amount
+        };
+
+        if amount > available_amount {
+            msg!(
+                "Borrow amount {} is more than the available {}",
+                amount,
+                available_amount
+            );
+            return Err(LendingError::UtilizationExceeded.into());
+        }
+        liquidity.record_outflow(amount, slot)?;
+
         obligation.liquidity_borrow(amount)?;
         liquidity.borrow(amount)?;
 
-        // Check obligation health
-        collateral.check_health(obligation.calc_health()?)?;
+        // Restrictions are lifted along with the isolation of the collateral
+        if obligation.isolated && collateral.isolated {
+            collateral.check_isolated_liquidity(liquidity_info.key)?;
+            collateral.isolated_borrow(amount)?;
+        }
 
-        Obligation::pack(obligation, *obligation_info.data.borrow_mut())?;
-        Liquidity::pack(liquidity, *liquidity_info.data.borrow_mut())?;
+        // Check obligation health
+        let health = obligation.check_health(ratio_initial)?;
 
         let (_, bump_seed) = find_program_address(program_id, market_info.key);
         let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
@@ -787,13 +1337,23 @@ impl Processor {
         // Transfer liquidity from token account to destination borrower
         spl_token_transfer(
             liquidity_token_account_info.clone(),
+            liquidity_token_mint_info.clone(),
             destination_info.clone(),
             market_authority_info.clone(),
+            token_program_info.clone(),
             amount,
             &[signers_seeds],
         )?;
 
-        Ok(())
+        LendingEvent::Borrow {
+            obligation: *obligation_info.key,
+            liquidity: *liquidity_info.key,
+            amount,
+            health,
+        }
+        .emit();
+
+        Ok(amount)
     }
 
     /// Process ObligationLiquidityRepay instruction
@@ -805,11 +1365,15 @@ impl Processor {
         let account_info_iter = &mut accounts.iter();
         let obligation_info = next_account_info(account_info_iter)?;
         let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
         let source_info = next_account_info(account_info_iter)?;
         let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let liquidity_token_mint_info = next_account_info(account_info_iter)?;
         let market_info = next_account_info(account_info_iter)?;
         let user_transfer_authority_info = next_account_info(account_info_iter)?;
-        let _token_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        assert_token_program(token_program_info, liquidity_token_account_info)?;
 
         if market_info.owner != program_id {
             msg!("Market provided is not owned by the market program");
@@ -827,129 +1391,4288 @@ impl Processor {
         }
 
         // Get obligation state
-        let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
 
         if obligation.liquidity != *liquidity_info.key {
             msg!("Obligation liquidity does not match the liquidity provided");
             return Err(ProgramError::InvalidArgument);
         }
 
-        if obligation.market != *market_info.key {
-            msg!("Obligation market does not match the market provided");
+        if obligation.collateral != *collateral_info.key {
+            msg!("Obligation collateral does not match the collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
             return Err(ProgramError::InvalidArgument);
         }
 
         // Get liquidity state
-        let mut liquidity = Liquidity::unpack(&liquidity_info.data.borrow())?;
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
 
         if liquidity.token_account != *liquidity_token_account_info.key {
             msg!("Collateral token account does not match the token account provided");
             return Err(ProgramError::InvalidArgument);
         }
 
+        if liquidity.token_mint != *liquidity_token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let token_account_amount =
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount;
+        liquidity.accrue_interest(token_account_amount, Clock::get()?.slot)?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
         let repay_limit = obligation.amount_liquidity_borrowed;
-        if amount > repay_limit {
+        let amount = if amount == u64::MAX {
+            // Repay the whole debt
+            msg!("Repay amount: {}", repay_limit);
+            repay_limit
+        } else if amount > repay_limit {
             msg!("Repay limit exceeded");
             return Err(ProgramError::InvalidArgument);
+        } else {
+            amount
+        };
+
+        // Transfer liquidity from source borrower to token account
+        spl_token_transfer(
+            source_info.clone(),
+            liquidity_token_mint_info.clone(),
+            liquidity_token_account_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_program_info.clone(),
+            amount,
+            &[],
+        )?;
+
+        // Transfer fees of the token mint are charged from the amount received
+        let received_amount = unpack_token_account(&liquidity_token_account_info.data.borrow())?
+            .amount
+            .checked_sub(token_account_amount)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        // Market authority and insurance account follow if the liquidity has insurance
+        let insurance = if liquidity.has_insurance() {
+            let market_authority_info = next_account_info(account_info_iter)?;
+            insurance_account(liquidity, account_info_iter)?
+                .map(|info| (info, market_authority_info))
+        } else {
+            None
+        };
+
+        obligation.liquidity_repay(received_amount)?;
+        repay_liquidity(
+            program_id,
+            liquidity,
+            received_amount,
+            insurance,
+            liquidity_token_account_info,
+            liquidity_token_mint_info,
+            market_info,
+            token_program_info,
+        )?;
+
+        if obligation.isolated {
+            if collateral_info.owner != program_id {
+                msg!("Collateral provided is not owned by the market program");
+                return Err(LendingError::InvalidAccountOwner.into());
+            }
+
+            Collateral::load_mut(&mut collateral_info.data.borrow_mut())?
+                .isolated_repay(received_amount)?;
+        }
+        obligation.reset_unhealthy()?;
+        let health = obligation.calc_health()?;
+
+        LendingEvent::Repay {
+            obligation: *obligation_info.key,
+            liquidity: *liquidity_info.key,
+            amount: received_amount,
+            health,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process WriteOffBadDebt instruction
+    pub fn write_off_bad_debt(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        assert_token_program(token_program_info, token_account_info)?;
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        if obligation.liquidity != *liquidity_info.key {
+            msg!("Obligation liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.collateral != *collateral_info.key {
+            msg!("Obligation collateral does not match the collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.token_account != *token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_mint != *token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.pool_mint != *pool_mint_info.key {
+            msg!("Liquidity pool mint does not match the pool mint provided");
+            return Err(ProgramError::InvalidArgument);
         }
-        obligation.liquidity_repay(amount)?;
+
+        let token_account_amount = unpack_token_account(&token_account_info.data.borrow())?.amount;
+        let pool_mint_supply = unpack_mint(&pool_mint_info.data.borrow())?.supply;
+        liquidity.accrue_interest(token_account_amount, Clock::get()?.slot)?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+        let exchange_rate_before =
+            liquidity.calc_exchange_rate(token_account_amount, pool_mint_supply)?;
+
+        let amount = obligation.write_off_bad_debt()?;
         liquidity.repay(amount)?;
 
-        Obligation::pack(obligation, *obligation_info.data.borrow_mut())?;
-        Liquidity::pack(liquidity, *liquidity_info.data.borrow_mut())?;
+        if obligation.isolated {
+            if collateral_info.owner != program_id {
+                msg!("Collateral provided is not owned by the market program");
+                return Err(LendingError::InvalidAccountOwner.into());
+            }
+
+            Collateral::load_mut(&mut collateral_info.data.borrow_mut())?.isolated_repay(amount)?;
+        }
+        msg!("Bad debt written off: {}", amount);
+
+        // Insurance covers as much of the debt as it can
+        let insurance_amount = if liquidity.has_insurance() {
+            let insurance_account_info = next_account_info(account_info_iter)?;
+
+            if liquidity.insurance_account != *insurance_account_info.key {
+                msg!("Liquidity insurance account does not match the insurance account provided");
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let draw_amount = unpack_token_account(&insurance_account_info.data.borrow())?
+                .amount
+                .min(amount);
+
+            let (_, bump_seed) = find_program_address(program_id, market_info.key);
+            let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+            spl_token_transfer(
+                insurance_account_info.clone(),
+                token_mint_info.clone(),
+                token_account_info.clone(),
+                market_authority_info.clone(),
+                token_program_info.clone(),
+                draw_amount,
+                &[signers_seeds],
+            )?;
+
+            // Transfer fees of the token mint are charged from the amount received
+            unpack_token_account(&token_account_info.data.borrow())?
+                .amount
+                .checked_sub(token_account_amount)
+                .ok_or(LendingError::CalculationFailure)?
+        } else {
+            0
+        };
+        msg!("Covered by insurance: {}", insurance_amount);
+
+        // The rest of the debt is socialized among the liquidity providers
+        let token_account_amount = unpack_token_account(&token_account_info.data.borrow())?.amount;
+        LendingEvent::BadDebtWrittenOff {
+            obligation: *obligation_info.key,
+            liquidity: *liquidity_info.key,
+            amount,
+            insurance_amount,
+            exchange_rate_before,
+            exchange_rate: liquidity.calc_exchange_rate(token_account_amount, pool_mint_supply)?,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process CreateLiquidityInsurance instruction
+    pub fn create_liquidity_insurance(
+        program_id: &Pubkey,
+        reserve_factor: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let insurance_account_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        assert_token_program(token_program_info, token_mint_info)?;
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_mint != *token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.has_insurance() {
+            msg!("Liquidity insurance account is already created");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        assert_ratio(reserve_factor)?;
+
+        // Initialize insurance token account owned by the market authority
+        spl_initialize_account(
+            insurance_account_info.clone(),
+            token_mint_info.clone(),
+            market_authority_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+        )?;
+
+        liquidity.insurance_account = *insurance_account_info.key;
+        liquidity.reserve_factor = reserve_factor;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::LiquidityInsurance {
+                liquidity: *liquidity_info.key,
+                insurance_account: *insurance_account_info.key,
+                reserve_factor,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateLiquidityInsurance instruction
+    pub fn update_liquidity_insurance(
+        program_id: &Pubkey,
+        reserve_factor: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !liquidity.has_insurance() {
+            msg!("Liquidity insurance account is not created");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        assert_ratio(reserve_factor)?;
+        liquidity.reserve_factor = reserve_factor;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::LiquidityInsurance {
+                liquidity: *liquidity_info.key,
+                insurance_account: liquidity.insurance_account,
+                reserve_factor,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateLiquidityUtilization instruction
+    pub fn update_liquidity_utilization(
+        program_id: &Pubkey,
+        max_utilization: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        assert_ratio(max_utilization)?;
+        liquidity.max_utilization = max_utilization;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::LiquidityUtilization {
+                liquidity: *liquidity_info.key,
+                max_utilization,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateLiquidityBorrowRate instruction
+    pub fn update_liquidity_borrow_rate(
+        program_id: &Pubkey,
+        optimal_utilization: u64,
+        borrow_rate_min: u64,
+        borrow_rate_optimal: u64,
+        borrow_rate_max: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        assert_ratio(optimal_utilization)?;
+
+        if borrow_rate_min > borrow_rate_optimal || borrow_rate_optimal > borrow_rate_max {
+            msg!(
+                "Borrow rates {}, {}, {} must not decrease with the utilization",
+                borrow_rate_min,
+                borrow_rate_optimal,
+                borrow_rate_max
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_account != *liquidity_token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Interest up to now is accrued at the previous rate
+        liquidity.accrue_interest(
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount,
+            Clock::get()?.slot,
+        )?;
+
+        liquidity.optimal_utilization = optimal_utilization;
+        liquidity.borrow_rate_min = borrow_rate_min;
+        liquidity.borrow_rate_optimal = borrow_rate_optimal;
+        liquidity.borrow_rate_max = borrow_rate_max;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::LiquidityBorrowRate {
+                liquidity: *liquidity_info.key,
+                optimal_utilization,
+                borrow_rate_min,
+                borrow_rate_optimal,
+                borrow_rate_max,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process LiquidityWithdrawRequest instruction
+    pub fn liquidity_withdraw_request(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let withdrawal_ticket_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let provider_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(rent_info)?;
+
+        if !provider_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        assert_token_program(token_program_info, token_account_info)?;
+        assert_rent_exempt(rent, withdrawal_ticket_info)?;
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if withdrawal_ticket_info.owner != program_id {
+            msg!("Withdrawal ticket provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get withdrawal ticket state
+        let mut withdrawal_ticket =
+            WithdrawalTicket::unpack_unchecked(&withdrawal_ticket_info.data.borrow())?;
+        assert_uninitialized(&withdrawal_ticket)?;
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_account != *token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.pool_mint != *pool_mint_info.key {
+            msg!("Liquidity pool mint does not match the pool mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Queue the whole pool token balance of the source
+        let amount = if amount == u64::MAX {
+            let amount = unpack_token_account(&source_info.data.borrow())?.amount;
+            msg!("Withdraw amount: {}", amount);
+            amount
+        } else {
+            amount
+        };
+
+        let token_account_amount = unpack_token_account(&token_account_info.data.borrow())?.amount;
+        let pool_mint_supply = unpack_mint(&pool_mint_info.data.borrow())?.supply;
+        liquidity.accrue_interest(token_account_amount, Clock::get()?.slot)?;
+
+        // The ticket locks the exchange rate of the request with the interest accrued
+        let liquidity_amount = liquidity.calc_withdraw_exchange_amount(
+            amount,
+            token_account_amount,
+            pool_mint_supply,
+        )?;
+        let exchange_rate = liquidity.calc_exchange_rate(token_account_amount, pool_mint_supply)?;
+
+        // Burn from source provider pool token
+        spl_token_burn(
+            pool_mint_info.clone(),
+            source_info.clone(),
+            provider_info.clone(),
+            token_program_info.clone(),
+            amount,
+            &[],
+        )?;
+
+        let queue_offset = liquidity.request_withdraw(liquidity_amount)?;
+
+        withdrawal_ticket.init(InitWithdrawalTicketParams {
+            liquidity: *liquidity_info.key,
+            owner: *provider_info.key,
+            amount: liquidity_amount,
+            pool_tokens_amount: amount,
+            queue_offset,
+        });
+
+        WithdrawalTicket::pack(withdrawal_ticket, *withdrawal_ticket_info.data.borrow_mut())?;
+
+        LendingEvent::WithdrawRequested {
+            withdrawal_ticket: *withdrawal_ticket_info.key,
+            liquidity: *liquidity_info.key,
+            amount: liquidity_amount,
+            pool_tokens_amount: amount,
+            queue_offset,
+            exchange_rate,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process LiquidityWithdrawClaim instruction
+    pub fn liquidity_withdraw_claim(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let withdrawal_ticket_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        assert_token_program(token_program_info, token_account_info)?;
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if withdrawal_ticket_info.owner != program_id {
+            msg!("Withdrawal ticket provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get withdrawal ticket state
+        let withdrawal_ticket = WithdrawalTicket::unpack(&withdrawal_ticket_info.data.borrow())?;
+
+        if withdrawal_ticket.liquidity != *liquidity_info.key {
+            msg!("Withdrawal ticket liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if withdrawal_ticket.owner != *owner_info.key {
+            msg!("Withdrawal ticket owner does not match the owner provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_account != *token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_mint != *token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let token_account_amount = unpack_token_account(&token_account_info.data.borrow())?.amount;
+        liquidity.claim_withdraw(
+            withdrawal_ticket.queue_offset,
+            withdrawal_ticket.amount,
+            token_account_amount,
+        )?;
+        liquidity.record_outflow(withdrawal_ticket.amount, Clock::get()?.slot)?;
+
+        let (_, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        // Transfer liquidity from token account to destination provider
+        spl_token_transfer(
+            token_account_info.clone(),
+            token_mint_info.clone(),
+            destination_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            withdrawal_ticket.amount,
+            &[signers_seeds],
+        )?;
+
+        // Close the ticket and return the rent to the owner
+        let ticket_lamports = withdrawal_ticket_info.lamports();
+        **withdrawal_ticket_info.lamports.borrow_mut() = 0;
+        **owner_info.lamports.borrow_mut() = owner_info
+            .lamports()
+            .checked_add(ticket_lamports)
+            .ok_or(LendingError::CalculationFailure)?;
+        withdrawal_ticket_info.data.borrow_mut().fill(0);
+
+        LendingEvent::WithdrawClaimed {
+            withdrawal_ticket: *withdrawal_ticket_info.key,
+            liquidity: *liquidity_info.key,
+            amount: withdrawal_ticket.amount,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateMarketGuardian instruction
+    pub fn update_market_guardian(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+        let guardian_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let mut market_data = market_info.data.borrow_mut();
+        let market = Market::load_mut(&mut market_data)?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        market.guardian = *guardian_info.key;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::MarketGuardian {
+                market: *market_info.key,
+                guardian: market.guardian,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process PauseMarket instruction
+    pub fn pause_market(
+        program_id: &Pubkey,
+        operations: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let market_info = next_account_info(account_info_iter)?;
+        let guardian_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let clock = Clock::from_account_info(clock_info)?;
+
+        if !guardian_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let mut market_data = market_info.data.borrow_mut();
+        let market = Market::load_mut(&mut market_data)?;
+
+        market.check_guardian(guardian_info.key)?;
+        market.pause(operations & PAUSE_ALL, clock.unix_timestamp);
+
+        LendingEvent::PauseUpdated {
+            market: *market_info.key,
+            paused: market.paused,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UnpauseMarket instruction
+    pub fn unpause_market(
+        program_id: &Pubkey,
+        operations: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let mut market_data = market_info.data.borrow_mut();
+        let market = Market::load_mut(&mut market_data)?;
+
+        // Only the owner unpauses, the guardian can't undo the owner decision
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        market.unpause(operations);
+
+        LendingEvent::PauseUpdated {
+            market: *market_info.key,
+            paused: market.paused,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateLiquidityOutflowLimit instruction
+    pub fn update_liquidity_outflow_limit(
+        program_id: &Pubkey,
+        outflow_limit: u64,
+        outflow_window: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if outflow_limit != 0 && outflow_window == 0 {
+            msg!("Outflow window must be at least one slot");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        liquidity.outflow_limit = outflow_limit;
+        liquidity.outflow_window = outflow_window;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::LiquidityOutflowLimit {
+                liquidity: *liquidity_info.key,
+                outflow_limit,
+                outflow_window,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process ResetLiquidityOutflow instruction
+    pub fn reset_liquidity_outflow(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let outflow_amount = liquidity.outflow_amount;
+        liquidity.reset_outflow(Clock::get()?.slot);
+
+        LendingEvent::OutflowReset {
+            liquidity: *liquidity_info.key,
+            outflow_amount,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateCollateralLiquidation instruction
+    pub fn update_collateral_liquidation(
+        program_id: &Pubkey,
+        bonus_start: u64,
+        bonus_max: u64,
+        bonus_slots: u64,
+        close_factor: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let collateral_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        assert_ratio(bonus_start)?;
+        assert_ratio(bonus_max)?;
+
+        if bonus_start > bonus_max {
+            msg!(
+                "Liquidation bonus start {} is more than the maximum {}",
+                bonus_start,
+                bonus_max
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Liquidations would be blocked without a share of the debt to repay
+        assert_ratio(close_factor)?;
+        if close_factor == 0 {
+            msg!("Liquidation close factor must be positive");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get collateral state
+        let mut collateral_data = collateral_info.data.borrow_mut();
+        let collateral = Collateral::load_mut(&mut collateral_data)?;
+
+        if collateral.market != *market_info.key {
+            msg!("Collateral market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        collateral.liquidation_bonus_start = bonus_start;
+        collateral.liquidation_bonus_max = bonus_max;
+        collateral.liquidation_bonus_slots = bonus_slots;
+        collateral.liquidation_close_factor = close_factor;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::CollateralLiquidation {
+                collateral: *collateral_info.key,
+                bonus_start,
+                bonus_max,
+                bonus_slots,
+                close_factor,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process RefreshObligationHealth instruction
+    pub fn refresh_obligation_health(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let clock = Clock::from_account_info(clock_info)?;
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        if obligation.liquidity != *liquidity_info.key {
+            msg!("Obligation liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.collateral != *collateral_info.key {
+            msg!("Obligation collateral does not match the collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.token_account != *liquidity_token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get pair state
+        let pair_data = pair_info.data.borrow();
+        let pair = Pair::load(&pair_data)?;
+
+        if pair.market != *market_info.key
+            || pair.liquidity != *liquidity_info.key
+            || pair.collateral != *collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Category risk parameters apply while the obligation assets share the category
+        let ratio_healthy = if obligation.category != NO_CATEGORY {
+            category_ratios(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                obligation.category,
+                liquidity.category,
+                Collateral::load(&collateral_info.data.borrow())?.category,
+            )?
+            .map_or(pair.ratio_healthy, |(_, ratio_healthy)| ratio_healthy)
+        } else {
+            pair.ratio_healthy
+        };
+
+        // Debt is compounded by the interest the liquidity accrued up to the current slot
+        liquidity.accrue_interest(
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount,
+            clock.slot,
+        )?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
+        // Liquidation auction starts from the slot the crank saw the obligation unhealthy
+        let health = obligation.record_health(ratio_healthy, clock.slot)?;
+
+        LendingEvent::HealthRefreshed {
+            obligation: *obligation_info.key,
+            health,
+            unhealthy_since: obligation.unhealthy_since,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process LiquidateObligation instruction
+    pub fn liquidate_obligation(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let liquidity_token_mint_info = next_account_info(account_info_iter)?;
+        let collateral_token_account_info = next_account_info(account_info_iter)?;
+        let collateral_token_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let clock = Clock::from_account_info(clock_info)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        assert_token_program(token_program_info, liquidity_token_account_info)?;
+        assert_token_program(token_program_info, collateral_token_account_info)?;
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        if obligation.liquidity != *liquidity_info.key {
+            msg!("Obligation liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.collateral != *collateral_info.key {
+            msg!("Obligation collateral does not match the collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.token_account != *liquidity_token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_mint != *liquidity_token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get collateral state
+        let mut collateral_data = collateral_info.data.borrow_mut();
+        let collateral = Collateral::load_mut(&mut collateral_data)?;
+
+        if collateral.token_account != *collateral_token_account_info.key {
+            msg!("Collateral token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if collateral.token_mint != *collateral_token_mint_info.key {
+            msg!("Collateral token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get pair state
+        let pair_data = pair_info.data.borrow();
+        let pair = Pair::load(&pair_data)?;
+
+        if pair.market != *market_info.key
+            || pair.liquidity != *liquidity_info.key
+            || pair.collateral != *collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Category risk parameters apply while the obligation assets share the category
+        let ratio_healthy = if obligation.category != NO_CATEGORY {
+            category_ratios(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                obligation.category,
+                liquidity.category,
+                collateral.category,
+            )?
+            .map_or(pair.ratio_healthy, |(_, ratio_healthy)| ratio_healthy)
+        } else {
+            pair.ratio_healthy
+        };
+
+        // Reserve share of the repaid interest goes to the insurance account
+        let insurance = insurance_account(liquidity, account_info_iter)?
+            .map(|info| (info, market_authority_info));
+
+        let token_account_amount =
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount;
+        liquidity.accrue_interest(token_account_amount, clock.slot)?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
+        obligation.check_unhealthy(ratio_healthy)?;
+
+        if obligation.unhealthy_since == 0 {
+            msg!("Obligation health must be refreshed before the liquidation");
+            return Err(LendingError::LiquidationNotStarted.into());
+        }
+
+        // Liquidator is charged the bonus current at the execution
+        let liquidation_bonus = collateral
+            .calc_liquidation_bonus(clock.slot.saturating_sub(obligation.unhealthy_since))?;
+        msg!("Liquidation bonus: {}", liquidation_bonus);
+
+        // Repay the close factor share of the debt at most
+        let amount = amount
+            .min(collateral.calc_liquidation_close_amount(obligation.amount_liquidity_borrowed)?);
+        msg!("Repay amount: {}", amount);
+
+        // Transfer liquidity from source liquidator to token account
+        spl_token_transfer(
+            source_info.clone(),
+            liquidity_token_mint_info.clone(),
+            liquidity_token_account_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_program_info.clone(),
+            amount,
+            &[],
+        )?;
+
+        // Transfer fees of the token mint are charged from the amount received
+        let received_amount = unpack_token_account(&liquidity_token_account_info.data.borrow())?
+            .amount
+            .checked_sub(token_account_amount)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        obligation.liquidity_repay(received_amount)?;
+        repay_liquidity(
+            program_id,
+            liquidity,
+            received_amount,
+            insurance,
+            liquidity_token_account_info,
+            liquidity_token_mint_info,
+            market_info,
+            token_program_info,
+        )?;
+
+        if obligation.isolated {
+            collateral.isolated_repay(received_amount)?;
+        }
+
+        let collateral_amount =
+            obligation.calc_liquidation_amount(received_amount, liquidation_bonus)?;
+        obligation.collateral_withdraw(collateral_amount)?;
+
+        // Debt left without collateral is written off as the bad debt
+        let health = obligation.calc_health_saturating()?;
+        // Auction of the partially liquidated obligation goes on until it's healthy against
+        // the limit it was recorded with
+        obligation.reset_unhealthy()?;
+
+        let (_, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        // Transfer collateral from token account to destination liquidator
+        spl_token_transfer(
+            collateral_token_account_info.clone(),
+            collateral_token_mint_info.clone(),
+            destination_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            collateral_amount,
+            &[signers_seeds],
+        )?;
+
+        LendingEvent::Liquidation {
+            obligation: *obligation_info.key,
+            liquidity: *liquidity_info.key,
+            collateral: *collateral_info.key,
+            repay_amount: received_amount,
+            collateral_amount,
+            liquidation_bonus,
+            health,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process RepayWithCollateral instruction
+    pub fn repay_with_collateral(
+        program_id: &Pubkey,
+        collateral_amount: u64,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let collateral_token_account_info = next_account_info(account_info_iter)?;
+        let collateral_token_mint_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let liquidity_token_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let swap_program_info = next_account_info(account_info_iter)?;
+
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        assert_token_program(token_program_info, collateral_token_account_info)?;
+        assert_token_program(token_program_info, liquidity_token_account_info)?;
+
+        if swap_program_info.key == program_id {
+            msg!("Swap program can't be the market program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        let owner = obligation_owner(obligation, account_info_iter)?;
+        if !obligation.is_owner_or_operator(&owner, authority_info.key) {
+            msg!("Obligation owner or operator does not match the authority provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let by_operator = owner != *authority_info.key;
+
+        if obligation.liquidity != *liquidity_info.key {
+            msg!("Obligation liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.collateral != *collateral_info.key {
+            msg!("Obligation collateral does not match the collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.token_account != *liquidity_token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_mint != *liquidity_token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get collateral state
+        let mut collateral_data = collateral_info.data.borrow_mut();
+        let collateral = Collateral::load_mut(&mut collateral_data)?;
+
+        if collateral.token_account != *collateral_token_account_info.key {
+            msg!("Collateral token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if collateral.token_mint != *collateral_token_mint_info.key {
+            msg!("Collateral token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get pair state
+        let pair_data = pair_info.data.borrow();
+        let pair = Pair::load(&pair_data)?;
+
+        if pair.market != *market_info.key
+            || pair.liquidity != *liquidity_info.key
+            || pair.collateral != *collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Category risk parameters apply while the obligation assets share the category
+        let ratio_initial = if obligation.category != NO_CATEGORY {
+            category_ratios(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                obligation.category,
+                liquidity.category,
+                collateral.category,
+            )?
+            .map_or(pair.ratio_initial, |(ratio_initial, _)| ratio_initial)
+        } else {
+            pair.ratio_initial
+        };
+
+        // Reserve share of the repaid interest goes to the insurance account
+        let insurance = insurance_account(liquidity, account_info_iter)?
+            .map(|info| (info, market_authority_info));
+
+        liquidity.accrue_interest(
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount,
+            Clock::get()?.slot,
+        )?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
+        // Operator swaps straight into the liquidity token account at par by the obligation
+        // valuation, so the swapped value can't leave the obligation
+        let minimum_amount_out = if by_operator {
+            if *swap_destination_info.key != liquidity.token_account {
+                msg!("Operator swaps into the liquidity token account only");
+                return Err(ProgramError::InvalidArgument);
+            }
+            minimum_amount_out.max(collateral_amount)
+        } else {
+            minimum_amount_out
+        };
+
+        let health_before = obligation.calc_health()?;
+        obligation.collateral_withdraw(collateral_amount)?;
+
+        let (_, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        // Transfer collateral from token account to the swap source
+        spl_token_transfer(
+            collateral_token_account_info.clone(),
+            collateral_token_mint_info.clone(),
+            swap_source_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            collateral_amount,
+            &[signers_seeds],
+        )?;
+
+        // Swap the collateral, the rest of the accounts are passed to the swap program
+        let swap_amount = swap_tokens(
+            swap_program_info.clone(),
+            swap_source_info.clone(),
+            swap_destination_info.clone(),
+            authority_info.clone(),
+            token_program_info.clone(),
+            account_info_iter.cloned().collect(),
+            collateral_amount,
+            minimum_amount_out,
+        )?;
+
+        let received_amount = if by_operator {
+            // Liquidity over the debt would be left to the lenders
+            if swap_amount > obligation.amount_liquidity_borrowed {
+                msg!(
+                    "Swap amount {} exceeds the debt {}",
+                    swap_amount,
+                    obligation.amount_liquidity_borrowed
+                );
+                return Err(LendingError::SwapAmountExceedsDebt.into());
+            }
+            msg!("Repay amount: {}", swap_amount);
+            swap_amount
+        } else {
+            // Liquidity left over the debt stays in the swap destination of the owner
+            let amount = swap_amount.min(obligation.amount_liquidity_borrowed);
+            msg!("Repay amount: {}", amount);
+
+            let token_account_amount =
+                unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount;
+
+            // Transfer liquidity from the swap destination to token account
+            spl_token_transfer(
+                swap_destination_info.clone(),
+                liquidity_token_mint_info.clone(),
+                liquidity_token_account_info.clone(),
+                authority_info.clone(),
+                token_program_info.clone(),
+                amount,
+                &[],
+            )?;
+
+            // Transfer fees of the token mint are charged from the amount received
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?
+                .amount
+                .checked_sub(token_account_amount)
+                .ok_or(LendingError::CalculationFailure)?
+        };
+
+        obligation.liquidity_repay(received_amount)?;
+        repay_liquidity(
+            program_id,
+            liquidity,
+            received_amount,
+            insurance,
+            liquidity_token_account_info,
+            liquidity_token_mint_info,
+            market_info,
+            token_program_info,
+        )?;
+
+        if obligation.isolated {
+            collateral.isolated_repay(received_amount)?;
+        }
+
+        obligation.reset_unhealthy()?;
+
+        // Deleveraging can't leave the obligation less healthy beyond the initial ratio
+        let health = obligation.calc_health()?;
+        if health > ratio_initial && health > health_before {
+            msg!(
+                "Obligation health {} is worse than {} before the repay",
+                health,
+                health_before
+            );
+            return Err(LendingError::CollateralHealthCheckFailed.into());
+        }
+
+        LendingEvent::RepayWithCollateral {
+            obligation: *obligation_info.key,
+            liquidity: *liquidity_info.key,
+            collateral_amount,
+            repay_amount: received_amount,
+            health,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process SwapObligationCollateral instruction
+    pub fn swap_obligation_collateral(
+        program_id: &Pubkey,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let new_collateral_info = next_account_info(account_info_iter)?;
+        let new_pair_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let collateral_token_account_info = next_account_info(account_info_iter)?;
+        let collateral_token_mint_info = next_account_info(account_info_iter)?;
+        let new_collateral_token_account_info = next_account_info(account_info_iter)?;
+        let new_collateral_token_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let swap_program_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        assert_token_program(token_program_info, collateral_token_account_info)?;
+        assert_token_program(token_program_info, new_collateral_token_account_info)?;
+
+        if swap_program_info.key == program_id {
+            msg!("Swap program can't be the market program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if collateral_info.owner != program_id || new_collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if new_pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if collateral_info.key == new_collateral_info.key {
+            msg!("New collateral must differ from the obligation collateral");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        let owner = obligation_owner(obligation, account_info_iter)?;
+        if !obligation.is_owner_or_operator(&owner, authority_info.key) {
+            msg!("Obligation owner or operator does not match the authority provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let by_operator = owner != *authority_info.key;
+
+        if obligation.liquidity != *liquidity_info.key {
+            msg!("Obligation liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.collateral != *collateral_info.key {
+            msg!("Obligation collateral does not match the collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.token_account != *liquidity_token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Debt is compounded by the interest the liquidity accrued up to the current slot
+        liquidity.accrue_interest(
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount,
+            Clock::get()?.slot,
+        )?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
+        // Get collateral state
+        let mut collateral_data = collateral_info.data.borrow_mut();
+        let collateral = Collateral::load_mut(&mut collateral_data)?;
+
+        if collateral.token_account != *collateral_token_account_info.key {
+            msg!("Collateral token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if collateral.token_mint != *collateral_token_mint_info.key {
+            msg!("Collateral token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get new collateral state
+        let mut new_collateral_data = new_collateral_info.data.borrow_mut();
+        let new_collateral = Collateral::load_mut(&mut new_collateral_data)?;
+
+        if new_collateral.market != *market_info.key {
+            msg!("Collateral market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if new_collateral.status != CollateralStatus::Active {
+            msg!("Collateral does not active");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if new_collateral.token_account != *new_collateral_token_account_info.key {
+            msg!("Collateral token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if new_collateral.token_mint != *new_collateral_token_mint_info.key {
+            msg!("Collateral token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get new pair state
+        let new_pair_data = new_pair_info.data.borrow();
+        let new_pair = Pair::load(&new_pair_data)?;
+
+        if new_pair.market != *market_info.key
+            || new_pair.liquidity != *liquidity_info.key
+            || new_pair.collateral != *new_collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Category risk parameters apply while the obligation assets share the category
+        let ratio_initial = if obligation.category != NO_CATEGORY {
+            category_ratios(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                obligation.category,
+                liquidity.category,
+                new_collateral.category,
+            )?
+            .map_or(new_pair.ratio_initial, |(ratio_initial, _)| ratio_initial)
+        } else {
+            new_pair.ratio_initial
+        };
+
+        // Isolated debt moves along with the obligation to the new collateral
+        let debt_amount = obligation.amount_liquidity_borrowed;
+        if obligation.isolated {
+            collateral.isolated_repay(debt_amount)?;
+        }
+
+        if new_collateral.isolated {
+            new_collateral.check_isolated_liquidity(liquidity_info.key)?;
+            new_collateral.isolated_borrow(debt_amount)?;
+        }
+
+        // Withdraw the whole old collateral
+        let amount = obligation.amount_collateral_deposited;
+        obligation.collateral_withdraw(amount)?;
+        msg!("Swap amount: {}", amount);
+
+        // Operator swaps straight into the new collateral token account at par by the obligation
+        // valuation, so the swapped value can't leave the obligation
+        let minimum_amount_out = if by_operator {
+            if *swap_destination_info.key != new_collateral.token_account {
+                msg!("Operator swaps into the new collateral token account only");
+                return Err(ProgramError::InvalidArgument);
+            }
+            minimum_amount_out.max(amount)
+        } else {
+            minimum_amount_out
+        };
+
+        let (_, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        // Transfer collateral from token account to the swap source
+        spl_token_transfer(
+            collateral_token_account_info.clone(),
+            collateral_token_mint_info.clone(),
+            swap_source_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            amount,
+            &[signers_seeds],
+        )?;
+
+        // Swap the collateral, the rest of the accounts are passed to the swap program
+        let swap_amount = swap_tokens(
+            swap_program_info.clone(),
+            swap_source_info.clone(),
+            swap_destination_info.clone(),
+            authority_info.clone(),
+            token_program_info.clone(),
+            account_info_iter.cloned().collect(),
+            amount,
+            minimum_amount_out,
+        )?;
+
+        let received_amount = if by_operator {
+            swap_amount
+        } else {
+            let token_account_amount =
+                unpack_token_account(&new_collateral_token_account_info.data.borrow())?.amount;
+
+            // Transfer new collateral from the swap destination to token account
+            spl_token_transfer(
+                swap_destination_info.clone(),
+                new_collateral_token_mint_info.clone(),
+                new_collateral_token_account_info.clone(),
+                authority_info.clone(),
+                token_program_info.clone(),
+                swap_amount,
+                &[],
+            )?;
+
+            // Transfer fees of the token mint are charged from the amount received
+            unpack_token_account(&new_collateral_token_account_info.data.borrow())?
+                .amount
+                .checked_sub(token_account_amount)
+                .ok_or(LendingError::CalculationFailure)?
+        };
+
+        obligation.collateral = *new_collateral_info.key;
+        obligation.isolated = new_collateral.isolated;
+        obligation.collateral_deposit(received_amount)?;
+
+        // Health is recorded again by the crank against the new collateral
+        obligation.unhealthy_since = 0;
+
+        // Check obligation health with the new risk parameters
+        let health = obligation.check_health(ratio_initial)?;
+
+        LendingEvent::CollateralSwapped {
+            obligation: *obligation_info.key,
+            collateral: *collateral_info.key,
+            new_collateral: *new_collateral_info.key,
+            amount,
+            new_amount: received_amount,
+            health,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process RefinanceObligationDebt instruction
+    pub fn refinance_obligation_debt(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let new_liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let new_pair_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let liquidity_token_mint_info = next_account_info(account_info_iter)?;
+        let new_liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let new_liquidity_token_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let swap_program_info = next_account_info(account_info_iter)?;
+
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        assert_token_program(token_program_info, liquidity_token_account_info)?;
+        assert_token_program(token_program_info, new_liquidity_token_account_info)?;
+
+        if swap_program_info.key == program_id {
+            msg!("Swap program can't be the market program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id || new_liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if new_pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.key == new_liquidity_info.key {
+            msg!("New liquidity must differ from the obligation liquidity");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        let owner = obligation_owner(obligation, account_info_iter)?;
+        if !obligation.is_owner_or_operator(&owner, authority_info.key) {
+            msg!("Obligation owner or operator does not match the authority provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let by_operator = owner != *authority_info.key;
+
+        if obligation.liquidity != *liquidity_info.key {
+            msg!("Obligation liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.collateral != *collateral_info.key {
+            msg!("Obligation collateral does not match the collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.token_account != *liquidity_token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_mint != *liquidity_token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get new liquidity state
+        let mut new_liquidity_data = new_liquidity_info.data.borrow_mut();
+        let new_liquidity = Liquidity::load_mut(&mut new_liquidity_data)?;
+
+        if new_liquidity.status != LiquidityStatus::Active {
+            msg!("Liquidity does not active");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if new_liquidity.token_account != *new_liquidity_token_account_info.key {
+            msg!("Liquidity token account does not match the token account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if new_liquidity.token_mint != *new_liquidity_token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get collateral state
+        let mut collateral_data = collateral_info.data.borrow_mut();
+        let collateral = Collateral::load_mut(&mut collateral_data)?;
+
+        // Get new pair state
+        let new_pair_data = new_pair_info.data.borrow();
+        let new_pair = Pair::load(&new_pair_data)?;
+
+        if new_pair.market != *market_info.key
+            || new_pair.liquidity != *new_liquidity_info.key
+            || new_pair.collateral != *collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        new_pair.check_borrow_enabled()?;
+
+        // Category risk parameters apply while the obligation assets share the category
+        let ratio_initial = if obligation.category != NO_CATEGORY {
+            category_ratios(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                obligation.category,
+                new_liquidity.category,
+                collateral.category,
+            )?
+            .map_or(new_pair.ratio_initial, |(ratio_initial, _)| ratio_initial)
+        } else {
+            new_pair.ratio_initial
+        };
+
+        // Reserve share of the repaid interest goes to the insurance account
+        let insurance = insurance_account(liquidity, account_info_iter)?
+            .map(|info| (info, market_authority_info));
+
+        // Operator swaps straight into the liquidity token account and borrows no more than
+        // the debt at par by the obligation valuation, so the swapped value can't leave
+        // the obligation
+        if by_operator && *swap_destination_info.key != liquidity.token_account {
+            msg!("Operator swaps into the liquidity token account only");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let slot = Clock::get()?.slot;
+        liquidity.accrue_interest(
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount,
+            slot,
+        )?;
+        obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
+        let token_account_amount =
+            unpack_token_account(&new_liquidity_token_account_info.data.borrow())?.amount;
+        new_liquidity.accrue_interest(token_account_amount, slot)?;
+
+        // Borrowers can't take the liquidity buffer kept by the maximum utilization
+        let available_amount = new_liquidity.calc_available_amount(token_account_amount)?;
+
+        if amount > available_amount {
+            msg!(
+                "Borrow amount {} is more than the available {}",
+                amount,
+                available_amount
+            );
+            return Err(LendingError::UtilizationExceeded.into());
+        }
+        if by_operator && amount > obligation.amount_liquidity_borrowed {
+            msg!(
+                "Operator borrows {} over the debt {}",
+                amount,
+                obligation.amount_liquidity_borrowed
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        new_liquidity.record_outflow(amount, slot)?;
+        new_liquidity.borrow(amount)?;
+
+        let (_, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        // Transfer new liquidity from token account to the swap source
+        spl_token_transfer(
+            new_liquidity_token_account_info.clone(),
+            new_liquidity_token_mint_info.clone(),
+            swap_source_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            amount,
+            &[signers_seeds],
+        )?;
+
+        // Swap must cover the whole debt, the rest of the accounts are passed to the swap program
+        let repay_amount = obligation.amount_liquidity_borrowed;
+        let swap_amount = swap_tokens(
+            swap_program_info.clone(),
+            swap_source_info.clone(),
+            swap_destination_info.clone(),
+            authority_info.clone(),
+            token_program_info.clone(),
+            account_info_iter.cloned().collect(),
+            amount,
+            repay_amount,
+        )?;
+
+        let received_amount = if by_operator {
+            // Liquidity over the debt would be left to the lenders
+            if swap_amount > repay_amount {
+                msg!(
+                    "Swap amount {} exceeds the debt {}",
+                    swap_amount,
+                    repay_amount
+                );
+                return Err(LendingError::SwapAmountExceedsDebt.into());
+            }
+            swap_amount
+        } else {
+            let token_account_amount =
+                unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount;
+
+            // Transfer liquidity from the swap destination to token account,
+            // liquidity left over the debt stays in the swap destination
+            spl_token_transfer(
+                swap_destination_info.clone(),
+                liquidity_token_mint_info.clone(),
+                liquidity_token_account_info.clone(),
+                authority_info.clone(),
+                token_program_info.clone(),
+                repay_amount,
+                &[],
+            )?;
+
+            // Transfer fees of the token mint are charged from the amount received
+            unpack_token_account(&liquidity_token_account_info.data.borrow())?
+                .amount
+                .checked_sub(token_account_amount)
+                .ok_or(LendingError::CalculationFailure)?
+        };
+
+        if received_amount != repay_amount {
+            msg!(
+                "Repay amount {} doesn't cover the debt {}",
+                received_amount,
+                repay_amount
+            );
+            return Err(LendingError::SlippageExceeded.into());
+        }
+
+        obligation.liquidity_repay(repay_amount)?;
+        repay_liquidity(
+            program_id,
+            liquidity,
+            repay_amount,
+            insurance,
+            liquidity_token_account_info,
+            liquidity_token_mint_info,
+            market_info,
+            token_program_info,
+        )?;
+
+        // Isolated debt moves along with the obligation to the new liquidity
+        if obligation.isolated && collateral.isolated {
+            collateral.check_isolated_liquidity(new_liquidity_info.key)?;
+            collateral.isolated_repay(repay_amount)?;
+            collateral.isolated_borrow(amount)?;
+        }
+
+        // New debt compounds from the current rate of the new liquidity
+        obligation.liquidity = *new_liquidity_info.key;
+        obligation.cumulative_borrow_rate = new_liquidity.cumulative_borrow_rate;
+        obligation.liquidity_borrow(amount)?;
+
+        // Health is recorded again by the crank against the new liquidity
+        obligation.unhealthy_since = 0;
+
+        // Check obligation health once with the new risk parameters
+        let health = obligation.check_health(ratio_initial)?;
+
+        LendingEvent::DebtRefinanced {
+            obligation: *obligation_info.key,
+            liquidity: *liquidity_info.key,
+            new_liquidity: *new_liquidity_info.key,
+            repay_amount,
+            borrow_amount: amount,
+            health,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process CreateDelegation instruction
+    pub fn create_delegation(
+        program_id: &Pubkey,
+        allowance: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let delegation_info = next_account_info(account_info_iter)?;
+        let obligation_info = next_account_info(account_info_iter)?;
+        let delegate_info = next_account_info(account_info_iter)?;
+        let obligation_owner_info = next_account_info(account_info_iter)?;
+        let delegation_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(rent_info)?;
+
+        if !obligation_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_owner(
+            Obligation::load(&obligation_info.data.borrow())?,
+            account_info_iter,
+        )? != *obligation_owner_info.key
+        {
+            msg!("Obligation owner does not match the owner provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (delegation_authority, bump_seed) =
+            find_delegation_authority(program_id, obligation_info.key, delegate_info.key);
+        let signers_seeds = &[
+            &obligation_info.key.to_bytes()[..32],
+            &delegate_info.key.to_bytes()[..32],
+            b"delegation",
+            &[bump_seed],
+        ];
+
+        // Create delegation account
+        create_account_with_seed::<Delegation>(
+            program_id,
+            obligation_owner_info.clone(),
+            delegation_info.clone(),
+            delegation_authority_info.clone(),
+            "delegation",
+            &delegation_authority,
+            &[signers_seeds],
+            rent,
+        )?;
+
+        // Get delegation state
+        let mut delegation_data = delegation_info.data.borrow_mut();
+        let delegation = Delegation::load_mut(&mut delegation_data)?;
+        assert_uninitialized(delegation)?;
+
+        delegation.init(InitDelegationParams {
+            obligation: *obligation_info.key,
+            owner: *obligation_owner_info.key,
+            delegate: *delegate_info.key,
+            allowance,
+        });
+
+        LendingEvent::DelegationUpdated {
+            delegation: *delegation_info.key,
+            obligation: *obligation_info.key,
+            delegate: *delegate_info.key,
+            allowance,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateDelegation instruction
+    pub fn update_delegation(
+        program_id: &Pubkey,
+        allowance: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let delegation_info = next_account_info(account_info_iter)?;
+        let obligation_info = next_account_info(account_info_iter)?;
+        let obligation_owner_info = next_account_info(account_info_iter)?;
+
+        if !obligation_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if delegation_info.owner != program_id {
+            msg!("Delegation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_owner(
+            Obligation::load(&obligation_info.data.borrow())?,
+            account_info_iter,
+        )? != *obligation_owner_info.key
+        {
+            msg!("Obligation owner does not match the owner provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get delegation state
+        let mut delegation_data = delegation_info.data.borrow_mut();
+        let delegation = Delegation::load_mut(&mut delegation_data)?;
+
+        if delegation.obligation != *obligation_info.key {
+            msg!("Delegation obligation does not match the obligation provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Granted again by the current owner
+        delegation.owner = *obligation_owner_info.key;
+        delegation.allowance = allowance;
+
+        LendingEvent::DelegationUpdated {
+            delegation: *delegation_info.key,
+            obligation: delegation.obligation,
+            delegate: delegation.delegate,
+            allowance,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process TransferObligation instruction
+    pub fn transfer_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let obligation_owner_info = next_account_info(account_info_iter)?;
+        let new_obligation_owner_info = next_account_info(account_info_iter)?;
+
+        if !obligation_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Obligation transferred to the default address could never be signed for
+        if *new_obligation_owner_info.key == Pubkey::default() {
+            msg!("New obligation owner must not be the default address");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        if obligation.owner != *obligation_owner_info.key {
+            msg!("Obligation owner does not match the owner provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Tokenized obligation moves with the position token
+        if obligation.is_tokenized() {
+            return Err(LendingError::ObligationTokenized.into());
+        }
+
+        obligation.owner = *new_obligation_owner_info.key;
+        // Operator appointed by the previous owner lapses
+        obligation.operator = Pubkey::default();
+
+        LendingEvent::ObligationTransferred {
+            obligation: *obligation_info.key,
+            owner: obligation.owner,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process TokenizeObligation instruction
+    pub fn tokenize_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let position_mint_info = next_account_info(account_info_iter)?;
+        let position_token_account_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let obligation_owner_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !obligation_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        assert_token_program(token_program_info, position_mint_info)?;
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        if obligation.owner != *obligation_owner_info.key {
+            msg!("Obligation owner does not match the owner provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.is_tokenized() {
+            return Err(LendingError::ObligationTokenized.into());
+        }
+
+        let (_, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        spl_initialize_mint(
+            position_mint_info.clone(),
+            market_authority_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+            0,
+        )?;
+
+        spl_initialize_account(
+            position_token_account_info.clone(),
+            position_mint_info.clone(),
+            obligation_owner_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+        )?;
+
+        spl_token_mint_to(
+            position_mint_info.clone(),
+            position_token_account_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            1,
+            &[signers_seeds],
+        )?;
+
+        // No more position tokens can be minted
+        spl_token_set_authority(
+            position_mint_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            spl_token::instruction::AuthorityType::MintTokens,
+            None,
+            &[signers_seeds],
+        )?;
+
+        obligation.position_mint = *position_mint_info.key;
+
+        LendingEvent::ObligationTokenized {
+            obligation: *obligation_info.key,
+            position_mint: obligation.position_mint,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process ClaimObligation instruction
+    pub fn claim_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let position_token_account_info = next_account_info(account_info_iter)?;
+        let position_token_account_owner_info = next_account_info(account_info_iter)?;
+
+        if !position_token_account_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        if !obligation.is_tokenized() {
+            return Err(LendingError::ObligationNotTokenized.into());
+        }
+
+        if position_token_holder(obligation, position_token_account_info)?
+            != *position_token_account_owner_info.key
+        {
+            msg!("Position token is not held by the owner provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        obligation.owner = *position_token_account_owner_info.key;
+        // Operator appointed by the previous owner lapses
+        obligation.operator = Pubkey::default();
+
+        LendingEvent::ObligationTransferred {
+            obligation: *obligation_info.key,
+            owner: obligation.owner,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process SetObligationOperator instruction
+    pub fn set_obligation_operator(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let obligation_owner_info = next_account_info(account_info_iter)?;
+        let operator_info = next_account_info(account_info_iter)?;
+
+        if !obligation_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        if obligation_owner(obligation, account_info_iter)? != *obligation_owner_info.key {
+            msg!("Obligation owner does not match the owner provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        obligation.operator = *operator_info.key;
+
+        LendingEvent::OperatorUpdated {
+            obligation: *obligation_info.key,
+            operator: obligation.operator,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process ClearObligationOperator instruction
+    pub fn clear_obligation_operator(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        // Operator can step down by itself
+        let owner = obligation_owner(obligation, account_info_iter)?;
+        if !obligation.is_owner_or_operator(&owner, authority_info.key) {
+            msg!("Obligation owner or operator does not match the authority provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        obligation.operator = Pubkey::default();
+
+        LendingEvent::OperatorUpdated {
+            obligation: *obligation_info.key,
+            operator: obligation.operator,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process InsuranceTopUp instruction
+    pub fn insurance_top_up(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let insurance_account_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        assert_token_program(token_program_info, insurance_account_info)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let liquidity_data = liquidity_info.data.borrow();
+        let liquidity = Liquidity::load(&liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.insurance_account != *insurance_account_info.key {
+            msg!("Liquidity insurance account does not match the insurance account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_mint != *token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let insurance_amount = unpack_token_account(&insurance_account_info.data.borrow())?.amount;
+
+        // Transfer liquidity from the owner to the insurance account
+        spl_token_transfer(
+            source_info.clone(),
+            token_mint_info.clone(),
+            insurance_account_info.clone(),
+            market_owner_info.clone(),
+            token_program_info.clone(),
+            amount,
+            &[],
+        )?;
+
+        // Transfer fees of the token mint are charged from the amount received
+        let received_amount = unpack_token_account(&insurance_account_info.data.borrow())?
+            .amount
+            .checked_sub(insurance_amount)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        LendingEvent::InsuranceTopUp {
+            liquidity: *liquidity_info.key,
+            amount: received_amount,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process InsuranceWithdrawRequest instruction
+    pub fn insurance_withdraw_request(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let insurance_account_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let clock = Clock::from_account_info(clock_info)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !liquidity.has_insurance() {
+            msg!("Liquidity insurance account is not created");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        if liquidity.insurance_account != *insurance_account_info.key {
+            msg!("Liquidity insurance account does not match the insurance account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Withdrawal can't exceed the insurance balance at the request
+        let amount =
+            amount.min(unpack_token_account(&insurance_account_info.data.borrow())?.amount);
+        liquidity.request_insurance_withdraw(amount, clock.unix_timestamp)?;
+
+        LendingEvent::InsuranceWithdrawRequested {
+            liquidity: *liquidity_info.key,
+            amount,
+            unlock_at: liquidity.insurance_withdraw_unlock_at,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process InsuranceWithdraw instruction
+    pub fn insurance_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let insurance_account_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock = Clock::from_account_info(clock_info)?;
+
+        assert_token_program(token_program_info, insurance_account_info)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.insurance_account != *insurance_account_info.key {
+            msg!("Liquidity insurance account does not match the insurance account provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if liquidity.token_mint != *token_mint_info.key {
+            msg!("Liquidity token mint does not match the token mint provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let amount = liquidity.take_insurance_withdraw(clock.unix_timestamp)?;
+
+        let (_, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        // Transfer liquidity from the insurance account to the destination
+        spl_token_transfer(
+            insurance_account_info.clone(),
+            token_mint_info.clone(),
+            destination_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            amount,
+            &[signers_seeds],
+        )?;
+
+        LendingEvent::InsuranceWithdraw {
+            liquidity: *liquidity_info.key,
+            amount,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateCollateralIsolation instruction
+    pub fn update_collateral_isolation(
+        program_id: &Pubkey,
+        isolated: bool,
+        debt_ceiling: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let collateral_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Approved liquidity must belong to the market
+        let mut liquidities = Vec::new();
+        for liquidity_info in account_info_iter {
+            if liquidity_info.owner != program_id {
+                msg!("Liquidity provided is not owned by the market program");
+                return Err(LendingError::InvalidAccountOwner.into());
+            }
+
+            if Liquidity::load(&liquidity_info.data.borrow())?.market != *market_info.key {
+                msg!("Liquidity market does not match the market provided");
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            liquidities.push(*liquidity_info.key);
+        }
+
+        // Get collateral state
+        let mut collateral_data = collateral_info.data.borrow_mut();
+        let collateral = Collateral::load_mut(&mut collateral_data)?;
+
+        if collateral.market != *market_info.key {
+            msg!("Collateral market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        collateral.set_isolation(isolated, debt_ceiling, &liquidities)?;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::CollateralIsolation {
+                collateral: *collateral_info.key,
+                isolated,
+                debt_ceiling,
+                liquidities,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process CreatePair instruction
+    pub fn create_pair(
+        program_id: &Pubkey,
+        config: PairConfig,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pair_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+        let pair_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(rent_info)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if Liquidity::load(&liquidity_info.data.borrow())?.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if Collateral::load(&collateral_info.data.borrow())?.market != *market_info.key {
+            msg!("Collateral market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        assert_ratio(config.ratio_initial)?;
+        assert_ratio(config.ratio_healthy)?;
+
+        let (pair_authority, bump_seed) = find_pair_authority(
+            program_id,
+            market_info.key,
+            liquidity_info.key,
+            collateral_info.key,
+        );
+        let signers_seeds = &[
+            &market_info.key.to_bytes()[..32],
+            &liquidity_info.key.to_bytes()[..32],
+            &collateral_info.key.to_bytes()[..32],
+            b"pair",
+            &[bump_seed],
+        ];
+
+        // Create pair account
+        create_account_with_seed::<Pair>(
+            program_id,
+            market_owner_info.clone(),
+            pair_info.clone(),
+            pair_authority_info.clone(),
+            "pair",
+            &pair_authority,
+            &[signers_seeds],
+            rent,
+        )?;
+
+        // Get pair state
+        let mut pair_data = pair_info.data.borrow_mut();
+        let pair = Pair::load_mut(&mut pair_data)?;
+        assert_uninitialized(pair)?;
+
+        pair.init(InitPairParams {
+            market: *market_info.key,
+            liquidity: *liquidity_info.key,
+            collateral: *collateral_info.key,
+            config,
+        });
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::Pair {
+                pair: *pair_info.key,
+                borrow_enabled: config.borrow_enabled,
+                ratio_initial: config.ratio_initial,
+                ratio_healthy: config.ratio_healthy,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdatePair instruction
+    pub fn update_pair(
+        program_id: &Pubkey,
+        config: PairConfig,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pair_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get pair state
+        let mut pair_data = pair_info.data.borrow_mut();
+        let pair = Pair::load_mut(&mut pair_data)?;
+
+        if pair.market != *market_info.key {
+            msg!("Pair market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        assert_ratio(config.ratio_initial)?;
+        assert_ratio(config.ratio_healthy)?;
+
+        pair.update(config);
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::Pair {
+                pair: *pair_info.key,
+                borrow_enabled: config.borrow_enabled,
+                ratio_initial: config.ratio_initial,
+                ratio_healthy: config.ratio_healthy,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process CreateCategory instruction
+    pub fn create_category(
+        program_id: &Pubkey,
+        id: u8,
+        ratio_initial: u64,
+        ratio_healthy: u64,
+        name: [u8; 32],
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let category_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(rent_info)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if market_info.owner != program_id {
+            msg!("Market provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if id == NO_CATEGORY {
+            msg!(
+                "Category id {} is reserved for the assets without category",
+                id
+            );
+            return Err(LendingError::InvalidCategory.into());
+        }
+
+        assert_ratio(ratio_initial)?;
+        assert_ratio(ratio_healthy)?;
+
+        // Create category account
+        let seed = format!("category{}", id);
+        let (authority, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        create_account_with_seed::<Category>(
+            program_id,
+            market_owner_info.clone(),
+            category_info.clone(),
+            market_authority_info.clone(),
+            &seed,
+            &authority,
+            &[signers_seeds],
+            rent,
+        )?;
+
+        // Get category state
+        let mut category_data = category_info.data.borrow_mut();
+        let category = Category::load_mut(&mut category_data)?;
+        assert_uninitialized(category)?;
+
+        category.init(InitCategoryParams {
+            id,
+            market: *market_info.key,
+            ratio_initial,
+            ratio_healthy,
+            name,
+        });
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::Category {
+                category: *category_info.key,
+                id,
+                ratio_initial,
+                ratio_healthy,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateCategory instruction
+    pub fn update_category(
+        program_id: &Pubkey,
+        ratio_initial: u64,
+        ratio_healthy: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let category_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if category_info.owner != program_id {
+            msg!("Category provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get category state
+        let mut category_data = category_info.data.borrow_mut();
+        let category = Category::load_mut(&mut category_data)?;
+
+        if category.market != *market_info.key {
+            msg!("Category market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        assert_ratio(ratio_initial)?;
+        assert_ratio(ratio_healthy)?;
+
+        category.ratio_initial = ratio_initial;
+        category.ratio_healthy = ratio_healthy;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::Category {
+                category: *category_info.key,
+                id: category.id,
+                ratio_initial,
+                ratio_healthy,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateLiquidityCategory instruction
+    pub fn update_liquidity_category(
+        program_id: &Pubkey,
+        category: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get liquidity state
+        let mut liquidity_data = liquidity_info.data.borrow_mut();
+        let liquidity = Liquidity::load_mut(&mut liquidity_data)?;
+
+        if liquidity.market != *market_info.key {
+            msg!("Liquidity market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Category must exist in the market unless removing the liquidity from it
+        if category != NO_CATEGORY {
+            assert_category(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                category,
+            )?;
+        }
+
+        liquidity.category = category;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::LiquidityCategory {
+                liquidity: *liquidity_info.key,
+                category,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateCollateralCategory instruction
+    pub fn update_collateral_category(
+        program_id: &Pubkey,
+        category: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let collateral_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_owner_info = next_account_info(account_info_iter)?;
+
+        if !market_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get market state
+        let market = Market::unpack(&market_info.data.borrow())?;
+
+        if market.owner != *market_owner_info.key {
+            msg!("Market owner provided does not match owner in the market state");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get collateral state
+        let mut collateral_data = collateral_info.data.borrow_mut();
+        let collateral = Collateral::load_mut(&mut collateral_data)?;
+
+        if collateral.market != *market_info.key {
+            msg!("Collateral market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Category must exist in the market unless removing the collateral from it
+        if category != NO_CATEGORY {
+            assert_category(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                category,
+            )?;
+        }
+
+        collateral.category = category;
+
+        LendingEvent::ConfigUpdated {
+            config: ConfigUpdate::CollateralCategory {
+                collateral: *collateral_info.key,
+                category,
+            },
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process UpdateObligationCategory instruction
+    pub fn update_obligation_category(
+        program_id: &Pubkey,
+        category: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let obligation_owner_info = next_account_info(account_info_iter)?;
+
+        if !obligation_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if liquidity_info.owner != program_id {
+            msg!("Liquidity provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if collateral_info.owner != program_id {
+            msg!("Collateral provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if pair_info.owner != program_id {
+            msg!("Pair provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Get obligation state
+        let mut obligation_data = obligation_info.data.borrow_mut();
+        let obligation = Obligation::load_mut(&mut obligation_data)?;
+
+        if obligation_owner(obligation, account_info_iter)? != *obligation_owner_info.key {
+            msg!("Obligation owner does not match the owner provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.liquidity != *liquidity_info.key {
+            msg!("Obligation liquidity does not match the liquidity provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.collateral != *collateral_info.key {
+            msg!("Obligation collateral does not match the collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if obligation.market != *market_info.key {
+            msg!("Obligation market does not match the market provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Get pair state
+        let pair_data = pair_info.data.borrow();
+        let pair = Pair::load(&pair_data)?;
+
+        if pair.market != *market_info.key
+            || pair.liquidity != *liquidity_info.key
+            || pair.collateral != *collateral_info.key
+        {
+            msg!("Pair does not match the market, liquidity and collateral provided");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Obligation can opt only into the category its assets share
+        let ratio_initial = if category != NO_CATEGORY {
+            category_ratios(
+                program_id,
+                next_account_info(account_info_iter)?,
+                market_info.key,
+                category,
+                Liquidity::load(&liquidity_info.data.borrow())?.category,
+                Collateral::load(&collateral_info.data.borrow())?.category,
+            )?
+            .ok_or(LendingError::InvalidCategory)?
+            .0
+        } else {
+            pair.ratio_initial
+        };
+
+        obligation.category = category;
+
+        // Check obligation health with the new risk parameters
+        obligation.check_health(ratio_initial)?;
+
+        LendingEvent::ObligationCategoryUpdated {
+            obligation: *obligation_info.key,
+            category,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Process LiquidityDepositNative instruction
+    pub fn liquidity_deposit_native(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let native_account_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let native_mint_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Wrap lamports of the owner
+        create_native_account(
+            program_id,
+            market_info.key,
+            owner_info.clone(),
+            native_account_info.clone(),
+            native_mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+            amount,
+        )?;
+
+        Self::liquidity_deposit(
+            program_id,
+            amount,
+            0,
+            &[
+                liquidity_info.clone(),
+                native_account_info.clone(),
+                destination_info.clone(),
+                token_account_info.clone(),
+                native_mint_info.clone(),
+                pool_mint_info.clone(),
+                market_info.clone(),
+                market_authority_info.clone(),
+                owner_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        spl_token_close_account(
+            native_account_info.clone(),
+            owner_info.clone(),
+            owner_info.clone(),
+            token_program_info.clone(),
+            &[],
+        )
+    }
+
+    /// Process LiquidityWithdrawNative instruction
+    pub fn liquidity_withdraw_native(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let native_account_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let native_mint_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        create_native_account(
+            program_id,
+            market_info.key,
+            owner_info.clone(),
+            native_account_info.clone(),
+            native_mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+            0,
+        )?;
+
+        Self::liquidity_withdraw(
+            program_id,
+            amount,
+            0,
+            &[
+                liquidity_info.clone(),
+                source_info.clone(),
+                native_account_info.clone(),
+                token_account_info.clone(),
+                native_mint_info.clone(),
+                pool_mint_info.clone(),
+                market_info.clone(),
+                market_authority_info.clone(),
+                owner_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        // Unwrap withdrawn lamports to the owner
+        spl_token_close_account(
+            native_account_info.clone(),
+            owner_info.clone(),
+            owner_info.clone(),
+            token_program_info.clone(),
+            &[],
+        )
+    }
+
+    /// Process ObligationCollateralDepositNative instruction
+    pub fn obligation_collateral_deposit_native(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let native_account_info = next_account_info(account_info_iter)?;
+        let collateral_token_account_info = next_account_info(account_info_iter)?;
+        let native_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        create_native_account(
+            program_id,
+            market_info.key,
+            owner_info.clone(),
+            native_account_info.clone(),
+            native_mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+            amount,
+        )?;
+
+        Self::obligation_collateral_deposit(
+            program_id,
+            amount,
+            &[
+                obligation_info.clone(),
+                collateral_info.clone(),
+                native_account_info.clone(),
+                collateral_token_account_info.clone(),
+                native_mint_info.clone(),
+                market_info.clone(),
+                owner_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        spl_token_close_account(
+            native_account_info.clone(),
+            owner_info.clone(),
+            owner_info.clone(),
+            token_program_info.clone(),
+            &[],
+        )
+    }
+
+    /// Process ObligationCollateralWithdrawNative instruction
+    pub fn obligation_collateral_withdraw_native(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
+        let obligation_owner_info = next_account_info(account_info_iter)?;
+        let native_account_info = next_account_info(account_info_iter)?;
+        let collateral_token_account_info = next_account_info(account_info_iter)?;
+        let native_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+
+        if !obligation_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        create_native_account(
+            program_id,
+            market_info.key,
+            obligation_owner_info.clone(),
+            native_account_info.clone(),
+            native_mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+            0,
+        )?;
+
+        let mut withdraw_accounts = vec![
+            obligation_info.clone(),
+            liquidity_info.clone(),
+            collateral_info.clone(),
+            pair_info.clone(),
+            native_account_info.clone(),
+            collateral_token_account_info.clone(),
+            native_mint_info.clone(),
+            market_info.clone(),
+            obligation_owner_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            liquidity_token_account_info.clone(),
+        ];
+        // Position token and category accounts of the obligation
+        withdraw_accounts.extend(account_info_iter.cloned());
+
+        Self::obligation_collateral_withdraw(program_id, amount, &withdraw_accounts)?;
+
+        spl_token_close_account(
+            native_account_info.clone(),
+            obligation_owner_info.clone(),
+            obligation_owner_info.clone(),
+            token_program_info.clone(),
+            &[],
+        )
+    }
+
+    /// Process ObligationLiquidityBorrowNative instruction
+    pub fn obligation_liquidity_borrow_native(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let pair_info = next_account_info(account_info_iter)?;
+        let obligation_owner_info = next_account_info(account_info_iter)?;
+        let native_account_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let native_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let market_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !obligation_owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        create_native_account(
+            program_id,
+            market_info.key,
+            obligation_owner_info.clone(),
+            native_account_info.clone(),
+            native_mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+            0,
+        )?;
+
+        let mut borrow_accounts = vec![
+            obligation_info.clone(),
+            liquidity_info.clone(),
+            collateral_info.clone(),
+            pair_info.clone(),
+            native_account_info.clone(),
+            liquidity_token_account_info.clone(),
+            native_mint_info.clone(),
+            market_info.clone(),
+            obligation_owner_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+        ];
+        // Category account of the obligation
+        borrow_accounts.extend(account_info_iter.cloned());
+
+        Self::obligation_liquidity_borrow(program_id, amount, &borrow_accounts)?;
+
+        spl_token_close_account(
+            native_account_info.clone(),
+            obligation_owner_info.clone(),
+            obligation_owner_info.clone(),
+            token_program_info.clone(),
+            &[],
+        )
+    }
+
+    /// Process ObligationLiquidityRepayNative instruction
+    pub fn obligation_liquidity_repay_native(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let obligation_info = next_account_info(account_info_iter)?;
+        let liquidity_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let native_account_info = next_account_info(account_info_iter)?;
+        let liquidity_token_account_info = next_account_info(account_info_iter)?;
+        let native_mint_info = next_account_info(account_info_iter)?;
+        let market_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let _system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        // Market authority and insurance account if the liquidity has insurance
+        let insurance_infos = account_info_iter.as_slice();
+
+        if !owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if obligation_info.owner != program_id {
+            msg!("Obligation provided is not owned by the market program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+
+        // Wrap the debt compounded up to the current slot to repay the whole debt, the repay
+        // clamps to the exact debt and the rest of the lamports return on the close
+        let (wrap_amount, amount) = if amount == u64::MAX {
+            let mut liquidity = Liquidity::unpack(&liquidity_info.data.borrow())?;
+            liquidity.accrue_interest(
+                unpack_token_account(&liquidity_token_account_info.data.borrow())?.amount,
+                Clock::get()?.slot,
+            )?;
+
+            let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+            obligation.accrue_interest(liquidity.cumulative_borrow_rate())?;
+
+            let wrap_amount = obligation
+                .amount_liquidity_borrowed
+                .checked_add(REPAY_NATIVE_BUFFER)
+                .ok_or(LendingError::CalculationFailure)?;
+            (wrap_amount, u64::MAX)
+        } else {
+            (amount, amount)
+        };
+
+        create_native_account(
+            program_id,
+            market_info.key,
+            owner_info.clone(),
+            native_account_info.clone(),
+            native_mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+            wrap_amount,
+        )?;
+
+        Self::obligation_liquidity_repay(
+            program_id,
+            amount,
+            &[
+                &[
+                    obligation_info.clone(),
+                    liquidity_info.clone(),
+                    collateral_info.clone(),
+                    native_account_info.clone(),
+                    liquidity_token_account_info.clone(),
+                    native_mint_info.clone(),
+                    market_info.clone(),
+                    owner_info.clone(),
+                    token_program_info.clone(),
+                ],
+                insurance_infos,
+            ]
+            .concat(),
+        )?;
+
+        spl_token_close_account(
+            native_account_info.clone(),
+            owner_info.clone(),
+            owner_info.clone(),
+            token_program_info.clone(),
+            &[],
+        )
+    }
+
+    /// Instruction processing router
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        input: &[u8],
+    ) -> ProgramResult {
+        let instruction = LendingInstruction::try_from_slice(input)?;
+
+        // Operations paused market-wide by the guardian
+        if let Some((operation, market_index)) = instruction.pause_operation() {
+            let market_info = accounts
+                .get(market_index)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            assert_not_paused(program_id, market_info, operation)?;
+        }
+
+        match instruction {
+            LendingInstruction::InitMarket => {
+                msg!("LendingInstruction: InitMarket");
+                Self::init_market(program_id, accounts)
+            }
+
+            LendingInstruction::CreateLiquidityToken => {
+                msg!("LendingInstruction: CreateLiquidityToken");
+                Self::create_liquidity_token(program_id, accounts)
+            }
+
+            LendingInstruction::UpdateLiquidityToken { status } => {
+                msg!("LendingInstruction: UpdateLiquidityToken");
+                Self::update_liquidity_token(program_id, status, accounts)
+            }
+
+            LendingInstruction::CreateCollateralToken {
+                ratio_initial,
+                ratio_healthy,
+            } => {
+                msg!("LendingInstruction: CreateCollateralToken");
+                Self::create_collateral_token(program_id, ratio_initial, ratio_healthy, accounts)
+            }
+
+            LendingInstruction::UpdateCollateralToken {
+                status,
+                ratio_initial,
+                ratio_healthy,
+            } => {
+                msg!("LendingInstruction: UpdateCollateralToken");
+                Self::update_collateral_token(
+                    program_id,
+                    status,
+                    ratio_initial,
+                    ratio_healthy,
+                    accounts,
+                )
+            }
+
+            LendingInstruction::LiquidityDeposit { amount } => {
+                msg!("LendingInstruction: LiquidityDeposit");
+                Self::liquidity_deposit(program_id, amount, 0, accounts)
+            }
+
+            LendingInstruction::LiquidityWithdraw { amount } => {
+                msg!("LendingInstruction: LiquidityWithdraw");
+                Self::liquidity_withdraw(program_id, amount, 0, accounts)
+            }
+
+            LendingInstruction::CreateObligation { index } => {
+                msg!("LendingInstruction: CreateObligation");
+                Self::create_obligation(program_id, index, accounts)
+            }
+
+            LendingInstruction::ObligationCollateralDeposit { amount } => {
+                msg!("LendingInstruction: ObligationCollateralDeposit");
+                Self::obligation_collateral_deposit(program_id, amount, accounts)
+            }
+
+            LendingInstruction::ObligationCollateralWithdraw { amount } => {
+                msg!("LendingInstruction: ObligationCollateralWithdraw");
+                Self::obligation_collateral_withdraw(program_id, amount, accounts)
+            }
+
+            LendingInstruction::ObligationLiquidityBorrow { amount } => {
+                msg!("LendingInstruction: ObligationLiquidityBorrow");
+                Self::obligation_liquidity_borrow(program_id, amount, accounts)
+            }
+
+            LendingInstruction::ObligationLiquidityRepay { amount } => {
+                msg!("LendingInstruction: ObligationLiquidityRepay");
+                Self::obligation_liquidity_repay(program_id, amount, accounts)
+            }
+
+            LendingInstruction::LiquidityDepositNative { amount } => {
+                msg!("LendingInstruction: LiquidityDepositNative");
+                Self::liquidity_deposit_native(program_id, amount, accounts)
+            }
+
+            LendingInstruction::LiquidityWithdrawNative { amount } => {
+                msg!("LendingInstruction: LiquidityWithdrawNative");
+                Self::liquidity_withdraw_native(program_id, amount, accounts)
+            }
+
+            LendingInstruction::ObligationCollateralDepositNative { amount } => {
+                msg!("LendingInstruction: ObligationCollateralDepositNative");
+                Self::obligation_collateral_deposit_native(program_id, amount, accounts)
+            }
+
+            LendingInstruction::ObligationCollateralWithdrawNative { amount } => {
+                msg!("LendingInstruction: ObligationCollateralWithdrawNative");
+                Self::obligation_collateral_withdraw_native(program_id, amount, accounts)
+            }
+
+            LendingInstruction::ObligationLiquidityBorrowNative { amount } => {
+                msg!("LendingInstruction: ObligationLiquidityBorrowNative");
+                Self::obligation_liquidity_borrow_native(program_id, amount, accounts)
+            }
+
+            LendingInstruction::ObligationLiquidityRepayNative { amount } => {
+                msg!("LendingInstruction: ObligationLiquidityRepayNative");
+                Self::obligation_liquidity_repay_native(program_id, amount, accounts)
+            }
+
+            LendingInstruction::LiquidityDepositChecked {
+                amount,
+                min_pool_tokens_out,
+            } => {
+                msg!("LendingInstruction: LiquidityDepositChecked");
+                Self::liquidity_deposit(program_id, amount, min_pool_tokens_out, accounts)
+            }
+
+            LendingInstruction::LiquidityWithdrawChecked {
+                amount,
+                min_liquidity_out,
+            } => {
+                msg!("LendingInstruction: LiquidityWithdrawChecked");
+                Self::liquidity_withdraw(program_id, amount, min_liquidity_out, accounts)
+            }
+
+            LendingInstruction::LiquidityDepositExactOut {
+                pool_tokens_out,
+                max_amount_in,
+            } => {
+                msg!("LendingInstruction: LiquidityDepositExactOut");
+                Self::liquidity_deposit_exact_out(
+                    program_id,
+                    pool_tokens_out,
+                    max_amount_in,
+                    accounts,
+                )
+            }
+
+            LendingInstruction::LiquidityWithdrawExactOut {
+                liquidity_out,
+                max_pool_tokens_in,
+            } => {
+                msg!("LendingInstruction: LiquidityWithdrawExactOut");
+                Self::liquidity_withdraw_exact_out(
+                    program_id,
+                    liquidity_out,
+                    max_pool_tokens_in,
+                    accounts,
+                )
+            }
+
+            LendingInstruction::WriteOffBadDebt => {
+                msg!("LendingInstruction: WriteOffBadDebt");
+                Self::write_off_bad_debt(program_id, accounts)
+            }
 
-        // Transfer liquidity from source borrower to token account
-        spl_token_transfer(
-            source_info.clone(),
-            liquidity_token_account_info.clone(),
-            user_transfer_authority_info.clone(),
-            amount,
-            &[],
-        )?;
+            LendingInstruction::CreateLiquidityInsurance { reserve_factor } => {
+                msg!("LendingInstruction: CreateLiquidityInsurance");
+                Self::create_liquidity_insurance(program_id, reserve_factor, accounts)
+            }
 
-        Ok(())
-    }
+            LendingInstruction::UpdateLiquidityInsurance { reserve_factor } => {
+                msg!("LendingInstruction: UpdateLiquidityInsurance");
+                Self::update_liquidity_insurance(program_id, reserve_factor, accounts)
+            }
 
-    /// Instruction processing router
-    pub fn process_instruction(
-        program_id: &Pubkey,
-        accounts: &[AccountInfo],
-        input: &[u8],
-    ) -> ProgramResult {
-        let instruction = LendingInstruction::try_from_slice(input)?;
+            LendingInstruction::InsuranceTopUp { amount } => {
+                msg!("LendingInstruction: InsuranceTopUp");
+                Self::insurance_top_up(program_id, amount, accounts)
+            }
 
-        match instruction {
-            LendingInstruction::InitMarket => {
-                msg!("LendingInstruction: InitMarket");
-                Self::init_market(program_id, accounts)
+            LendingInstruction::InsuranceWithdrawRequest { amount } => {
+                msg!("LendingInstruction: InsuranceWithdrawRequest");
+                Self::insurance_withdraw_request(program_id, amount, accounts)
             }
 
-            LendingInstruction::CreateLiquidityToken => {
-                msg!("LendingInstruction: CreateLiquidityToken");
-                Self::create_liquidity_token(program_id, accounts)
+            LendingInstruction::InsuranceWithdraw => {
+                msg!("LendingInstruction: InsuranceWithdraw");
+                Self::insurance_withdraw(program_id, accounts)
             }
 
-            LendingInstruction::UpdateLiquidityToken { status } => {
-                msg!("LendingInstruction: UpdateLiquidityToken");
-                Self::update_liquidity_token(program_id, status, accounts)
+            LendingInstruction::UpdateCollateralIsolation {
+                isolated,
+                debt_ceiling,
+            } => {
+                msg!("LendingInstruction: UpdateCollateralIsolation");
+                Self::update_collateral_isolation(program_id, isolated, debt_ceiling, accounts)
             }
 
-            LendingInstruction::CreateCollateralToken {
+            LendingInstruction::CreatePair {
+                borrow_enabled,
                 ratio_initial,
                 ratio_healthy,
             } => {
-                msg!("LendingInstruction: CreateCollateralToken");
-                Self::create_collateral_token(program_id, ratio_initial, ratio_healthy, accounts)
+                msg!("LendingInstruction: CreatePair");
+                let config = PairConfig {
+                    borrow_enabled,
+                    ratio_initial,
+                    ratio_healthy,
+                };
+                Self::create_pair(program_id, config, accounts)
             }
 
-            LendingInstruction::UpdateCollateralToken {
-                status,
+            LendingInstruction::UpdatePair {
+                borrow_enabled,
                 ratio_initial,
                 ratio_healthy,
             } => {
-                msg!("LendingInstruction: UpdateCollateralToken");
-                Self::update_collateral_token(
-                    program_id,
-                    status,
+                msg!("LendingInstruction: UpdatePair");
+                let config = PairConfig {
+                    borrow_enabled,
                     ratio_initial,
                     ratio_healthy,
+                };
+                Self::update_pair(program_id, config, accounts)
+            }
+
+            LendingInstruction::CreateCategory {
+                id,
+                ratio_initial,
+                ratio_healthy,
+                name,
+            } => {
+                msg!("LendingInstruction: CreateCategory");
+                Self::create_category(program_id, id, ratio_initial, ratio_healthy, name, accounts)
+            }
+
+            LendingInstruction::UpdateCategory {
+                ratio_initial,
+                ratio_healthy,
+            } => {
+                msg!("LendingInstruction: UpdateCategory");
+                Self::update_category(program_id, ratio_initial, ratio_healthy, accounts)
+            }
+
+            LendingInstruction::UpdateLiquidityCategory { category } => {
+                msg!("LendingInstruction: UpdateLiquidityCategory");
+                Self::update_liquidity_category(program_id, category, accounts)
+            }
+
+            LendingInstruction::UpdateCollateralCategory { category } => {
+                msg!("LendingInstruction: UpdateCollateralCategory");
+                Self::update_collateral_category(program_id, category, accounts)
+            }
+
+            LendingInstruction::UpdateObligationCategory { category } => {
+                msg!("LendingInstruction: UpdateObligationCategory");
+                Self::update_obligation_category(program_id, category, accounts)
+            }
+
+            LendingInstruction::UpdateLiquidityUtilization { max_utilization } => {
+                msg!("LendingInstruction: UpdateLiquidityUtilization");
+                Self::update_liquidity_utilization(program_id, max_utilization, accounts)
+            }
+
+            LendingInstruction::LiquidityWithdrawRequest { amount } => {
+                msg!("LendingInstruction: LiquidityWithdrawRequest");
+                Self::liquidity_withdraw_request(program_id, amount, accounts)
+            }
+
+            LendingInstruction::LiquidityWithdrawClaim => {
+                msg!("LendingInstruction: LiquidityWithdrawClaim");
+                Self::liquidity_withdraw_claim(program_id, accounts)
+            }
+
+            LendingInstruction::UpdateMarketGuardian => {
+                msg!("LendingInstruction: UpdateMarketGuardian");
+                Self::update_market_guardian(program_id, accounts)
+            }
+
+            LendingInstruction::PauseMarket { operations } => {
+                msg!("LendingInstruction: PauseMarket");
+                Self::pause_market(program_id, operations, accounts)
+            }
+
+            LendingInstruction::UnpauseMarket { operations } => {
+                msg!("LendingInstruction: UnpauseMarket");
+                Self::unpause_market(program_id, operations, accounts)
+            }
+
+            LendingInstruction::UpdateLiquidityOutflowLimit {
+                outflow_limit,
+                outflow_window,
+            } => {
+                msg!("LendingInstruction: UpdateLiquidityOutflowLimit");
+                Self::update_liquidity_outflow_limit(
+                    program_id,
+                    outflow_limit,
+                    outflow_window,
                     accounts,
                 )
             }
 
-            LendingInstruction::LiquidityDeposit { amount } => {
-                msg!("LendingInstruction: LiquidityDeposit");
-                Self::liquidity_deposit(program_id, amount, accounts)
+            LendingInstruction::ResetLiquidityOutflow => {
+                msg!("LendingInstruction: ResetLiquidityOutflow");
+                Self::reset_liquidity_outflow(program_id, accounts)
             }
 
-            LendingInstruction::LiquidityWithdraw { amount } => {
-                msg!("LendingInstruction: LiquidityWithdraw");
-                Self::liquidity_withdraw(program_id, amount, accounts)
+            LendingInstruction::UpdateCollateralLiquidation {
+                bonus_start,
+                bonus_max,
+                bonus_slots,
+                close_factor,
+            } => {
+                msg!("LendingInstruction: UpdateCollateralLiquidation");
+                Self::update_collateral_liquidation(
+                    program_id,
+                    bonus_start,
+                    bonus_max,
+                    bonus_slots,
+                    close_factor,
+                    accounts,
+                )
             }
 
-            LendingInstruction::CreateObligation => {
-                msg!("LendingInstruction: CreateObligation");
-                Self::create_obligation(program_id, accounts)
+            LendingInstruction::RefreshObligationHealth => {
+                msg!("LendingInstruction: RefreshObligationHealth");
+                Self::refresh_obligation_health(program_id, accounts)
             }
 
-            LendingInstruction::ObligationCollateralDeposit { amount } => {
-                msg!("LendingInstruction: ObligationCollateralDeposit");
-                Self::obligation_collateral_deposit(program_id, amount, accounts)
+            LendingInstruction::LiquidateObligation { amount } => {
+                msg!("LendingInstruction: LiquidateObligation");
+                Self::liquidate_obligation(program_id, amount, accounts)
             }
 
-            LendingInstruction::ObligationCollateralWithdraw { amount } => {
-                msg!("LendingInstruction: ObligationCollateralWithdraw");
-                Self::obligation_collateral_withdraw(program_id, amount, accounts)
+            LendingInstruction::RepayWithCollateral {
+                collateral_amount,
+                minimum_amount_out,
+            } => {
+                msg!("LendingInstruction: RepayWithCollateral");
+                Self::repay_with_collateral(
+                    program_id,
+                    collateral_amount,
+                    minimum_amount_out,
+                    accounts,
+                )
             }
 
-            LendingInstruction::ObligationLiquidityBorrow { amount } => {
-                msg!("LendingInstruction: ObligationLiquidityBorrow");
-                Self::obligation_liquidity_borrow(program_id, amount, accounts)
+            LendingInstruction::SwapObligationCollateral { minimum_amount_out } => {
+                msg!("LendingInstruction: SwapObligationCollateral");
+                Self::swap_obligation_collateral(program_id, minimum_amount_out, accounts)
             }
 
-            LendingInstruction::ObligationLiquidityRepay { amount } => {
-                msg!("LendingInstruction: ObligationLiquidityRepay");
-                Self::obligation_liquidity_repay(program_id, amount, accounts)
+            LendingInstruction::RefinanceObligationDebt { amount } => {
+                msg!("LendingInstruction: RefinanceObligationDebt");
+                Self::refinance_obligation_debt(program_id, amount, accounts)
+            }
+
+            LendingInstruction::CreateDelegation { allowance } => {
+                msg!("LendingInstruction: CreateDelegation");
+                Self::create_delegation(program_id, allowance, accounts)
+            }
+
+            LendingInstruction::UpdateDelegation { allowance } => {
+                msg!("LendingInstruction: UpdateDelegation");
+                Self::update_delegation(program_id, allowance, accounts)
+            }
+
+            LendingInstruction::ObligationLiquidityBorrowDelegated { amount } => {
+                msg!("LendingInstruction: ObligationLiquidityBorrowDelegated");
+                Self::obligation_liquidity_borrow_delegated(program_id, amount, accounts)
+            }
+
+            LendingInstruction::TransferObligation => {
+                msg!("LendingInstruction: TransferObligation");
+                Self::transfer_obligation(program_id, accounts)
+            }
+
+            LendingInstruction::TokenizeObligation => {
+                msg!("LendingInstruction: TokenizeObligation");
+                Self::tokenize_obligation(program_id, accounts)
+            }
+
+            LendingInstruction::ClaimObligation => {
+                msg!("LendingInstruction: ClaimObligation");
+                Self::claim_obligation(program_id, accounts)
+            }
+
+            LendingInstruction::SetObligationOperator => {
+                msg!("LendingInstruction: SetObligationOperator");
+                Self::set_obligation_operator(program_id, accounts)
+            }
+
+            LendingInstruction::ClearObligationOperator => {
+                msg!("LendingInstruction: ClearObligationOperator");
+                Self::clear_obligation_operator(program_id, accounts)
+            }
+
+            LendingInstruction::UpdateLiquidityBorrowRate {
+                optimal_utilization,
+                borrow_rate_min,
+                borrow_rate_optimal,
+                borrow_rate_max,
+            } => {
+                msg!("LendingInstruction: UpdateLiquidityBorrowRate");
+                Self::update_liquidity_borrow_rate(
+                    program_id,
+                    optimal_utilization,
+                    borrow_rate_min,
+                    borrow_rate_optimal,
+                    borrow_rate_max,
+                    accounts,
+                )
             }
         }
     }
@@ -989,21 +5712,101 @@ pub fn create_account_with_seed<'a, S: Pack>(
     invoke_signed(&ix, &[from, to, base], signers_seeds)
 }
 
+/// Create temporary native account owned by the owner and wrap lamports into it
+#[allow(clippy::too_many_arguments)]
+pub fn create_native_account<'a>(
+    program_id: &Pubkey,
+    market: &Pubkey,
+    owner: AccountInfo<'a>,
+    native_account: AccountInfo<'a>,
+    native_mint: AccountInfo<'a>,
+    rent_info: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    if *native_mint.key != spl_token::native_mint::id() {
+        msg!("Native mint does not match the mint provided");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (native_account_address, bump_seed) =
+        find_native_account_address(program_id, owner.key, market);
+    if native_account_address != *native_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let signers_seeds = &[
+        &owner.key.to_bytes()[..32],
+        &market.to_bytes()[..32],
+        b"native",
+        &[bump_seed],
+    ];
+
+    let rent = &Rent::from_account_info(&rent_info)?;
+    let lamports = rent
+        .minimum_balance(Account::LEN)
+        .checked_add(amount)
+        .ok_or(LendingError::CalculationFailure)?;
+
+    if native_account.lamports() == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                owner.key,
+                native_account.key,
+                lamports,
+                Account::LEN as u64,
+                &spl_token::id(),
+            ),
+            &[owner.clone(), native_account.clone()],
+            &[signers_seeds],
+        )?;
+    } else {
+        // Account can be funded in advance, so it can't be created with the system instruction
+        let required_lamports = lamports.saturating_sub(native_account.lamports());
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(owner.key, native_account.key, required_lamports),
+                &[owner.clone(), native_account.clone()],
+            )?;
+        }
+
+        invoke_signed(
+            &system_instruction::allocate(native_account.key, Account::LEN as u64),
+            std::slice::from_ref(&native_account),
+            &[signers_seeds],
+        )?;
+
+        invoke_signed(
+            &system_instruction::assign(native_account.key, &spl_token::id()),
+            std::slice::from_ref(&native_account),
+            &[signers_seeds],
+        )?;
+    }
+
+    spl_initialize_account(native_account, native_mint, owner, rent_info, token_program)
+}
+
 /// Initialize SPL accont instruction.
 pub fn spl_initialize_account<'a>(
     account: AccountInfo<'a>,
     mint: AccountInfo<'a>,
     authority: AccountInfo<'a>,
     rent: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
 ) -> ProgramResult {
-    let ix = spl_token::instruction::initialize_account(
+    let mut ix = spl_token::instruction::initialize_account(
         &spl_token::id(),
         account.key,
         mint.key,
         authority.key,
     )?;
+    // Token-2022 shares the instruction layout with SPL Token
+    ix.program_id = *token_program.key;
 
-    invoke(&ix, &[account, mint, authority, rent])
+    invoke(&ix, &[account, mint, authority, rent, token_program])
 }
 
 /// Initialize SPL mint instruction.
@@ -1011,37 +5814,110 @@ pub fn spl_initialize_mint<'a>(
     mint: AccountInfo<'a>,
     mint_authority: AccountInfo<'a>,
     rent: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
     decimals: u8,
 ) -> ProgramResult {
-    let ix = spl_token::instruction::initialize_mint(
+    let mut ix = spl_token::instruction::initialize_mint(
         &spl_token::id(),
         mint.key,
         mint_authority.key,
         None,
         decimals,
     )?;
+    ix.program_id = *token_program.key;
 
-    invoke(&ix, &[mint, rent])
+    invoke(&ix, &[mint, rent, token_program])
 }
 
 /// SPL transfer instruction.
 pub fn spl_token_transfer<'a>(
     source: AccountInfo<'a>,
+    mint: AccountInfo<'a>,
     destination: AccountInfo<'a>,
     authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
     amount: u64,
     signers_seeds: &[&[&[u8]]],
 ) -> Result<(), ProgramError> {
-    let ix = spl_token::instruction::transfer(
+    // Checked transfer is required by Token-2022 mints with transfer fees
+    let decimals = unpack_mint(&mint.data.borrow())?.decimals;
+    let mut ix = spl_token::instruction::transfer_checked(
         &spl_token::id(),
         source.key,
+        mint.key,
         destination.key,
         authority.key,
         &[],
         amount,
+        decimals,
+    )?;
+    ix.program_id = *token_program.key;
+
+    invoke_signed(
+        &ix,
+        &[source, mint, destination, authority, token_program],
+        signers_seeds,
+    )
+}
+
+/// Swap tokens through the swap program, returns the amount received by the destination
+#[allow(clippy::too_many_arguments)]
+pub fn swap_tokens<'a>(
+    swap_program: AccountInfo<'a>,
+    source: AccountInfo<'a>,
+    destination: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    swap_accounts: Vec<AccountInfo<'a>>,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Result<u64, ProgramError> {
+    let ix = swap::swap(
+        swap_program.key,
+        amount_in,
+        minimum_amount_out,
+        source.key,
+        destination.key,
+        authority.key,
+        token_program.key,
+        &swap_accounts
+            .iter()
+            .map(|account_info| AccountMeta {
+                pubkey: *account_info.key,
+                is_signer: account_info.is_signer,
+                is_writable: account_info.is_writable,
+            })
+            .collect::<Vec<_>>(),
     )?;
 
-    invoke_signed(&ix, &[source, destination, authority], signers_seeds)
+    let amount_before = unpack_token_account(&destination.data.borrow())?.amount;
+
+    let mut account_infos = vec![
+        source,
+        destination.clone(),
+        authority,
+        token_program,
+        swap_program,
+    ];
+    account_infos.extend(swap_accounts);
+    invoke(&ix, &account_infos)?;
+
+    // The swap program is trusted only for the amount received
+    let amount = unpack_token_account(&destination.data.borrow())?
+        .amount
+        .checked_sub(amount_before)
+        .ok_or(LendingError::CalculationFailure)?;
+
+    if amount < minimum_amount_out {
+        msg!(
+            "Swap amount {} is less than the minimum {}",
+            amount,
+            minimum_amount_out
+        );
+        return Err(LendingError::SlippageExceeded.into());
+    }
+
+    Ok(amount)
 }
 
 /// SPL mint instruction.
@@ -1049,10 +5925,11 @@ pub fn spl_token_mint_to<'a>(
     mint: AccountInfo<'a>,
     destination: AccountInfo<'a>,
     authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
     amount: u64,
     signers_seeds: &[&[&[u8]]],
 ) -> Result<(), ProgramError> {
-    let ix = spl_token::instruction::mint_to(
+    let mut ix = spl_token::instruction::mint_to(
         &spl_token::id(),
         mint.key,
         destination.key,
@@ -1060,8 +5937,35 @@ pub fn spl_token_mint_to<'a>(
         &[],
         amount,
     )?;
+    ix.program_id = *token_program.key;
+
+    invoke_signed(
+        &ix,
+        &[mint, destination, authority, token_program],
+        signers_seeds,
+    )
+}
+
+/// SPL set authority instruction.
+pub fn spl_token_set_authority<'a>(
+    account: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    authority_type: spl_token::instruction::AuthorityType,
+    new_authority: Option<&Pubkey>,
+    signers_seeds: &[&[&[u8]]],
+) -> Result<(), ProgramError> {
+    let mut ix = spl_token::instruction::set_authority(
+        &spl_token::id(),
+        account.key,
+        new_authority,
+        authority_type,
+        authority.key,
+        &[],
+    )?;
+    ix.program_id = *token_program.key;
 
-    invoke_signed(&ix, &[mint, destination, authority], signers_seeds)
+    invoke_signed(&ix, &[account, authority, token_program], signers_seeds)
 }
 
 /// SPL burn instruction.
@@ -1069,10 +5973,11 @@ pub fn spl_token_burn<'a>(
     mint: AccountInfo<'a>,
     account: AccountInfo<'a>,
     authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
     amount: u64,
     signers_seeds: &[&[&[u8]]],
 ) -> Result<(), ProgramError> {
-    let ix = spl_token::instruction::burn(
+    let mut ix = spl_token::instruction::burn(
         &spl_token::id(),
         account.key,
         mint.key,
@@ -1080,8 +5985,37 @@ pub fn spl_token_burn<'a>(
         &[],
         amount,
     )?;
+    ix.program_id = *token_program.key;
+
+    invoke_signed(
+        &ix,
+        &[mint, account, authority, token_program],
+        signers_seeds,
+    )
+}
+
+/// SPL close account instruction.
+pub fn spl_token_close_account<'a>(
+    account: AccountInfo<'a>,
+    destination: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    signers_seeds: &[&[&[u8]]],
+) -> Result<(), ProgramError> {
+    let mut ix = spl_token::instruction::close_account(
+        &spl_token::id(),
+        account.key,
+        destination.key,
+        authority.key,
+        &[],
+    )?;
+    ix.program_id = *token_program.key;
 
-    invoke_signed(&ix, &[mint, account, authority], signers_seeds)
+    invoke_signed(
+        &ix,
+        &[account, destination, authority, token_program],
+        signers_seeds,
+    )
 }
 
 fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramResult {
@@ -1093,6 +6027,28 @@ fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramResult
     }
 }
 
+fn assert_ratio(ratio: u64) -> ProgramResult {
+    if ratio > RATIO_POWER {
+        msg!("Ratio {} is more than {}", ratio, RATIO_POWER);
+        Err(ProgramError::InvalidArgument)
+    } else {
+        Ok(())
+    }
+}
+
+fn assert_not_paused(
+    program_id: &Pubkey,
+    market_info: &AccountInfo,
+    operation: u64,
+) -> ProgramResult {
+    if market_info.owner != program_id {
+        msg!("Market provided is not owned by the market program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    Market::load(&market_info.data.borrow())?.check_not_paused(operation)
+}
+
 fn assert_uninitialized<T: IsInitialized>(account: &T) -> ProgramResult {
     if account.is_initialized() {
         Err(ProgramError::AccountAlreadyInitialized)
@@ -1100,3 +6056,140 @@ fn assert_uninitialized<T: IsInitialized>(account: &T) -> ProgramResult {
         Ok(())
     }
 }
+
+/// Initial and healthy collateralization ratios of the category the obligation opted into,
+/// `None` if the obligation assets no longer share the category
+fn category_ratios(
+    program_id: &Pubkey,
+    category_info: &AccountInfo,
+    market: &Pubkey,
+    category_id: u8,
+    liquidity_category: u8,
+    collateral_category: u8,
+) -> Result<Option<(u64, u64)>, ProgramError> {
+    assert_category(program_id, category_info, market, category_id)?;
+
+    let category_data = category_info.data.borrow();
+    let category = Category::load(&category_data)?;
+
+    if category.applies(liquidity_category, collateral_category) {
+        Ok(Some((category.ratio_initial, category.ratio_healthy)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Check the category account to be the category of the market with the id
+fn assert_category(
+    program_id: &Pubkey,
+    category_info: &AccountInfo,
+    market: &Pubkey,
+    category_id: u8,
+) -> ProgramResult {
+    if category_info.owner != program_id {
+        msg!("Category provided is not owned by the market program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let category_data = category_info.data.borrow();
+    let category = Category::load(&category_data)?;
+
+    if category.market != *market || category.id != category_id {
+        msg!("Category does not match the market and category id provided");
+        return Err(LendingError::InvalidCategory.into());
+    }
+
+    Ok(())
+}
+
+/// Insurance account of the liquidity, it follows if the liquidity has insurance
+fn insurance_account<'a, 'b: 'a>(
+    liquidity: &Liquidity,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    if !liquidity.has_insurance() {
+        return Ok(None);
+    }
+
+    let insurance_account_info = next_account_info(account_info_iter)?;
+
+    if liquidity.insurance_account != *insurance_account_info.key {
+        msg!("Liquidity insurance account does not match the insurance account provided");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(Some(insurance_account_info))
+}
+
+/// Repay the liquidity, the reserve share of the interest is transferred by the market authority
+/// from the liquidity token account to the insurance account
+#[allow(clippy::too_many_arguments)]
+fn repay_liquidity<'a>(
+    program_id: &Pubkey,
+    liquidity: &mut Liquidity,
+    amount: u64,
+    insurance: Option<(&AccountInfo<'a>, &AccountInfo<'a>)>,
+    liquidity_token_account_info: &AccountInfo<'a>,
+    liquidity_token_mint_info: &AccountInfo<'a>,
+    market_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let reserve_amount = liquidity.take_reserve(amount);
+    liquidity.repay(amount)?;
+
+    if let Some((insurance_account_info, market_authority_info)) = insurance {
+        let (_, bump_seed) = find_program_address(program_id, market_info.key);
+        let signers_seeds = &[&market_info.key.to_bytes()[..32], &[bump_seed]];
+
+        spl_token_transfer(
+            liquidity_token_account_info.clone(),
+            liquidity_token_mint_info.clone(),
+            insurance_account_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            reserve_amount,
+            &[signers_seeds],
+        )?;
+        msg!("Reserve amount: {}", reserve_amount);
+    }
+
+    Ok(())
+}
+
+/// Holder of the position token of the tokenized obligation
+fn position_token_holder(
+    obligation: &Obligation,
+    position_token_account_info: &AccountInfo,
+) -> Result<Pubkey, ProgramError> {
+    if !is_supported_token_program(position_token_account_info.owner) {
+        msg!("Position token account is not owned by the token program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let position_token_account = unpack_token_account(&position_token_account_info.data.borrow())?;
+
+    if position_token_account.mint != obligation.position_mint {
+        msg!("Position token account mint does not match the obligation position mint");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if position_token_account.amount != 1 {
+        msg!("Position token is not held by the account provided");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(position_token_account.owner)
+}
+
+/// Current owner of the obligation, ownership of the tokenized obligation follows the position
+/// token, so the next account is its position token account
+fn obligation_owner<'a, 'b: 'a>(
+    obligation: &Obligation,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Pubkey, ProgramError> {
+    if obligation.is_tokenized() {
+        position_token_holder(obligation, next_account_info(account_info_iter)?)
+    } else {
+        Ok(obligation.owner)
+    }
+}
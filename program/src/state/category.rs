@@ -0,0 +1,82 @@
+//! Program state definitions
+use super::*;
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::mem::size_of;
+
+/// Category of the correlated assets, like stablecoins, with the higher risk parameters
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Category {
+    /// State version
+    pub version: u8,
+    /// Category id, assets and obligations refer to the category by it
+    pub id: u8,
+    /// Padding for the alignment of the fields
+    pub padding: [u8; 6],
+    /// Market
+    pub market: Pubkey,
+    /// Fractional initial collateralization ratio (multiplied by 10e9)
+    pub ratio_initial: u64,
+    /// Fractional limit for the healthy collateralization ratio (multiplied by 10e9)
+    pub ratio_healthy: u64,
+    /// Human readable name, zero padded
+    pub name: [u8; 32],
+}
+
+impl Category {
+    /// Initialize a category
+    pub fn init(&mut self, params: InitCategoryParams) {
+        self.version = PROGRAM_VERSION;
+        self.id = params.id;
+        self.market = params.market;
+        self.ratio_initial = params.ratio_initial;
+        self.ratio_healthy = params.ratio_healthy;
+        self.name = params.name;
+    }
+
+    /// Category applies while the obligation liquidity and collateral share it
+    pub fn applies(&self, liquidity_category: u8, collateral_category: u8) -> bool {
+        liquidity_category == self.id && collateral_category == self.id
+    }
+}
+
+/// Initialize a category params
+pub struct InitCategoryParams {
+    /// Category id
+    pub id: u8,
+    /// Market
+    pub market: Pubkey,
+    /// Fractional initial collateralization ratio (multiplied by 10e9)
+    pub ratio_initial: u64,
+    /// Fractional limit for the healthy collateralization ratio (multiplied by 10e9)
+    pub ratio_healthy: u64,
+    /// Human readable name
+    pub name: [u8; 32],
+}
+
+assert_state_len!(Category, 1 + 1 + 6 + 32 + 8 + 8 + 32);
+
+impl Sealed for Category {}
+impl Pack for Category {
+    const LEN: usize = size_of::<Self>();
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        self.write(dst)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::read(src)
+    }
+}
+
+unsafe impl ZeroCopy for Category {}
+
+impl IsInitialized for Category {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
@@ -1,18 +1,24 @@
 //! Program state definitions
 use super::*;
-use crate::error::LendingError;
+use crate::{
+    error::LendingError,
+    math::{Decimal, Rate, TryDiv, TryMul},
+};
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use num_enum::TryFromPrimitive;
 use solana_program::{
     entrypoint::ProgramResult,
-    msg,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
+use std::{convert::TryFrom, mem::size_of};
 
 /// Collateral status
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+#[repr(u8)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema, TryFromPrimitive,
+)]
 pub enum CollateralStatus {
     /// Inactive and invisible
     Inactive = 0,
@@ -30,12 +36,19 @@ impl Default for CollateralStatus {
 
 /// Collateral
 #[repr(C)]
-#[derive(Debug, BorshDeserialize, BorshSerialize, BorshSchema, Default)]
+#[derive(Debug, Default)]
 pub struct Collateral {
     /// State version
     pub version: u8,
     /// Token status
     pub status: CollateralStatus,
+    /// Isolation mode, obligations created while isolated can borrow only the approved liquidity
+    /// up to the debt ceiling until the isolation is lifted
+    pub isolated: bool,
+    /// Category of the correlated assets, `NO_CATEGORY` if none
+    pub category: u8,
+    /// Padding for the alignment of the fields
+    pub padding: [u8; 4],
     /// Market
     pub market: Pubkey,
     /// Supply token mint
@@ -46,6 +59,20 @@ pub struct Collateral {
     pub ratio_initial: u64,
     /// Fractional limit for the healthy collateralization ratio (multiplied by 10e9)
     pub ratio_healthy: u64,
+    /// Maximum amount of liquidity borrowed by the isolated obligations
+    pub debt_ceiling: u64,
+    /// Amount of liquidity borrowed by the isolated obligations
+    pub isolated_debt: u64,
+    /// Liquidity approved for the isolated obligations, default for the empty slots
+    pub isolated_liquidities: [Pubkey; MAX_ISOLATED_LIQUIDITIES],
+    /// Liquidation bonus once the obligation became unhealthy (multiplied by 10e9)
+    pub liquidation_bonus_start: u64,
+    /// Maximum liquidation bonus reached after the auction slots (multiplied by 10e9)
+    pub liquidation_bonus_max: u64,
+    /// Number of slots the liquidation bonus rises from the start to the maximum
+    pub liquidation_bonus_slots: u64,
+    /// Maximum fraction of the debt repaid by a single liquidation (multiplied by 10e9)
+    pub liquidation_close_factor: u64,
 }
 
 impl Collateral {
@@ -58,6 +85,93 @@ impl Collateral {
         self.token_account = params.token_account;
         self.ratio_initial = params.ratio_initial;
         self.ratio_healthy = params.ratio_healthy;
+        self.liquidation_close_factor = RATIO_POWER;
+    }
+
+    /// Update isolation mode with the approved liquidity
+    pub fn set_isolation(
+        &mut self,
+        isolated: bool,
+        debt_ceiling: u64,
+        liquidities: &[Pubkey],
+    ) -> ProgramResult {
+        if liquidities.len() > MAX_ISOLATED_LIQUIDITIES {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        self.isolated = isolated;
+        self.debt_ceiling = debt_ceiling;
+        self.isolated_liquidities = Default::default();
+        self.isolated_liquidities[..liquidities.len()].copy_from_slice(liquidities);
+
+        Ok(())
+    }
+
+    /// Check that the liquidity is approved for the isolated obligations
+    pub fn check_isolated_liquidity(&self, liquidity: &Pubkey) -> ProgramResult {
+        if *liquidity != Pubkey::default() && self.isolated_liquidities.contains(liquidity) {
+            Ok(())
+        } else {
+            Err(LendingError::IsolatedLiquidityNotApproved.into())
+        }
+    }
+
+    /// Increase debt of the isolated obligations within the debt ceiling
+    pub fn isolated_borrow(&mut self, amount: u64) -> ProgramResult {
+        let isolated_debt = self
+            .isolated_debt
+            .checked_add(amount)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        if isolated_debt > self.debt_ceiling {
+            return Err(LendingError::DebtCeilingExceeded.into());
+        }
+
+        self.isolated_debt = isolated_debt;
+        Ok(())
+    }
+
+    /// Decrease debt of the isolated obligations, debt isn't tracked once the isolation is lifted
+    pub fn isolated_repay(&mut self, amount: u64) -> ProgramResult {
+        if !self.isolated {
+            return Ok(());
+        }
+
+        // Debt borrowed while the isolation was lifted was never tracked
+        self.isolated_debt = self.isolated_debt.saturating_sub(amount);
+        Ok(())
+    }
+
+    /// Liquidation bonus of the Dutch auction after the slots elapsed since the obligation
+    /// became unhealthy, rises linearly from the start to the maximum
+    pub fn calc_liquidation_bonus(&self, slots_elapsed: u64) -> Result<u64, ProgramError> {
+        if slots_elapsed >= self.liquidation_bonus_slots {
+            return Ok(self.liquidation_bonus_max);
+        }
+
+        let bonus_range = self
+            .liquidation_bonus_max
+            .checked_sub(self.liquidation_bonus_start)
+            .ok_or(LendingError::CalculationFailure)?;
+        let bonus_increase = Decimal::from(bonus_range)
+            .try_mul(slots_elapsed)?
+            .try_div(self.liquidation_bonus_slots)?
+            .try_floor_u64()?;
+
+        let result = self
+            .liquidation_bonus_start
+            .checked_add(bonus_increase)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        Ok(result)
+    }
+
+    /// Maximum amount of the debt repaid by a single liquidation, rounded up so the dust debt
+    /// can be liquidated
+    pub fn calc_liquidation_close_amount(&self, debt: u64) -> Result<u64, ProgramError> {
+        Decimal::from(debt)
+            .try_mul(Rate::from_ratio(self.liquidation_close_factor))?
+            .try_ceil_u64()
     }
 
     /// Check health to be within the collateral limits
@@ -70,21 +184,21 @@ impl Collateral {
     }
 }
 
+assert_state_len!(
+    Collateral,
+    1 + 1 + 1 + 1 + 4 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 32 * 4 + 8 + 8 + 8 + 8
+);
+
 impl Sealed for Collateral {}
 impl Pack for Collateral {
-    // 1 + 1 + 32 + 32 + 32 + 8 + 8
-    const LEN: usize = 114;
+    const LEN: usize = size_of::<Self>();
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut slice = dst;
-        self.serialize(&mut slice).unwrap()
+        self.write(dst)
     }
 
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
-        Self::try_from_slice(src).map_err(|_| {
-            msg!("Failed to deserialize");
-            ProgramError::InvalidAccountData
-        })
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::read(src)
     }
 }
 
@@ -102,6 +216,17 @@ pub struct InitCollateralParams {
     pub ratio_healthy: u64,
 }
 
+unsafe impl ZeroCopy for Collateral {
+    fn check_bit_pattern(data: &[u8]) -> ProgramResult {
+        // Status and isolation follow the version
+        CollateralStatus::try_from(data[1]).map_err(|_| ProgramError::InvalidAccountData)?;
+        if data[2] > 1 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+}
+
 impl IsInitialized for Collateral {
     fn is_initialized(&self) -> bool {
         self.version != UNINITIALIZED_VERSION
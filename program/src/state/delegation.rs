@@ -0,0 +1,90 @@
+//! Program state definitions
+use super::*;
+use crate::error::LendingError;
+use solana_program::{
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::mem::size_of;
+
+/// Borrowing power of the obligation granted by its owner to the delegate
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Delegation {
+    /// State version
+    pub version: u8,
+    /// Padding for the alignment of the fields
+    pub padding: [u8; 7],
+    /// Obligation
+    pub obligation: Pubkey,
+    /// Obligation owner granted the delegation, it lapses when the obligation changes hands
+    pub owner: Pubkey,
+    /// Delegate borrowing against the obligation collateral
+    pub delegate: Pubkey,
+    /// Amount of liquidity the delegate can still borrow
+    pub allowance: u64,
+}
+
+impl Delegation {
+    /// Initialize a delegation
+    pub fn init(&mut self, params: InitDelegationParams) {
+        self.version = PROGRAM_VERSION;
+        self.obligation = params.obligation;
+        self.owner = params.owner;
+        self.delegate = params.delegate;
+        self.allowance = params.allowance;
+    }
+
+    /// Borrow within the allowance
+    pub fn borrow(&mut self, amount: u64) -> ProgramResult {
+        if amount > self.allowance {
+            msg!(
+                "Borrow amount {} is more than the allowance {}",
+                amount,
+                self.allowance
+            );
+            return Err(LendingError::DelegationAllowanceExceeded.into());
+        }
+        self.allowance -= amount;
+
+        Ok(())
+    }
+}
+
+/// Initialize a delegation params
+pub struct InitDelegationParams {
+    /// Obligation
+    pub obligation: Pubkey,
+    /// Obligation owner
+    pub owner: Pubkey,
+    /// Delegate borrowing against the obligation collateral
+    pub delegate: Pubkey,
+    /// Amount of liquidity the delegate can borrow
+    pub allowance: u64,
+}
+
+assert_state_len!(Delegation, 1 + 7 + 32 + 32 + 32 + 8);
+
+impl Sealed for Delegation {}
+impl Pack for Delegation {
+    const LEN: usize = size_of::<Self>();
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        self.write(dst)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::read(src)
+    }
+}
+
+unsafe impl ZeroCopy for Delegation {}
+
+impl IsInitialized for Delegation {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
@@ -1,20 +1,28 @@
 //! Program state definitions
 
-use crate::error::LendingError;
+use crate::{
+    error::LendingError,
+    math::{Decimal, Rate, TryAdd, TryDiv, TryDivCeil, TryMul},
+};
 
 use super::*;
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use num_enum::TryFromPrimitive;
 use solana_program::{
+    clock::Slot,
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
+use std::{convert::TryFrom, mem::size_of};
 
 /// Liqudiity status
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+#[repr(u8)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema, TryFromPrimitive,
+)]
 pub enum LiquidityStatus {
     /// Inactive and invisible
     Inactive = 0,
@@ -32,12 +40,16 @@ impl Default for LiquidityStatus {
 
 /// Liquidity
 #[repr(C)]
-#[derive(Debug, BorshDeserialize, BorshSerialize, BorshSchema, Default)]
+#[derive(Debug, Default)]
 pub struct Liquidity {
     /// State version
     pub version: u8,
     /// Token status
     pub status: LiquidityStatus,
+    /// Category of the correlated assets, `NO_CATEGORY` if none
+    pub category: u8,
+    /// Padding for the alignment of the fields
+    pub padding: [u8; 5],
     /// Market
     pub market: Pubkey,
     /// Supply token mint
@@ -48,6 +60,42 @@ pub struct Liquidity {
     pub pool_mint: Pubkey,
     /// Amount borrowed from the liquidity pool
     pub amount_borrowed: u64,
+    /// Insurance token account covering the bad debt, default if not created
+    pub insurance_account: Pubkey,
+    /// Share of the income transferred to the insurance account multiplied by `RATIO_POWER`
+    pub reserve_factor: u64,
+    /// Amount of the requested insurance withdrawal
+    pub insurance_withdraw_amount: u64,
+    /// Unix timestamp after which the requested insurance withdrawal is unlocked
+    pub insurance_withdraw_unlock_at: i64,
+    /// Maximum share of the total liquidity borrowed multiplied by `RATIO_POWER`
+    pub max_utilization: u64,
+    /// Amount of liquidity ever requested by the withdrawal tickets
+    pub queue_amount_requested: u64,
+    /// Amount of liquidity ever claimed by the withdrawal tickets
+    pub queue_amount_claimed: u64,
+    /// Maximum amount of liquidity leaving per window, zero if unlimited
+    pub outflow_limit: u64,
+    /// Length of the outflow window in slots
+    pub outflow_window: u64,
+    /// Slot the current outflow window started at
+    pub outflow_window_start: Slot,
+    /// Amount of liquidity left within the current outflow window
+    pub outflow_amount: u64,
+    /// Utilization the borrow rate bends at multiplied by `RATIO_POWER`
+    pub optimal_utilization: u64,
+    /// Annual borrow rate at zero utilization multiplied by `RATIO_POWER`
+    pub borrow_rate_min: u64,
+    /// Annual borrow rate at the optimal utilization multiplied by `RATIO_POWER`
+    pub borrow_rate_optimal: u64,
+    /// Annual borrow rate at the full utilization multiplied by `RATIO_POWER`
+    pub borrow_rate_max: u64,
+    /// Cumulative borrow rate compounding the debt, little-endian `Decimal` scaled value
+    pub cumulative_borrow_rate: [u8; 24],
+    /// Slot the interest was accrued at
+    pub interest_slot: Slot,
+    /// Share of the accrued interest owed to the insurance account, paid out of the repayments
+    pub amount_reserve: u64,
 }
 
 impl Liquidity {
@@ -60,6 +108,130 @@ impl Liquidity {
         self.token_account = params.token_account;
         self.pool_mint = params.pool_mint;
         self.amount_borrowed = 0;
+        self.max_utilization = RATIO_POWER;
+        self.optimal_utilization = RATIO_POWER;
+        self.cumulative_borrow_rate = Decimal::one().to_le_bytes();
+        self.interest_slot = params.interest_slot;
+    }
+
+    /// Check that the insurance account is created
+    pub fn has_insurance(&self) -> bool {
+        self.insurance_account != Pubkey::default()
+    }
+
+    /// Request the insurance withdrawal unlocked after the timelock, zero amount cancels the request
+    pub fn request_insurance_withdraw(&mut self, amount: u64, now: i64) -> ProgramResult {
+        self.insurance_withdraw_amount = amount;
+        self.insurance_withdraw_unlock_at = now
+            .checked_add(INSURANCE_WITHDRAW_TIMELOCK)
+            .ok_or(LendingError::CalculationFailure)?;
+        Ok(())
+    }
+
+    /// Take the unlocked insurance withdrawal, returns the amount to withdraw
+    pub fn take_insurance_withdraw(&mut self, now: i64) -> Result<u64, ProgramError> {
+        if self.insurance_withdraw_amount == 0 {
+            return Err(LendingError::InsuranceWithdrawNotRequested.into());
+        }
+
+        if now < self.insurance_withdraw_unlock_at {
+            return Err(LendingError::InsuranceWithdrawLocked.into());
+        }
+
+        let amount = self.insurance_withdraw_amount;
+        self.insurance_withdraw_amount = 0;
+        self.insurance_withdraw_unlock_at = 0;
+
+        Ok(amount)
+    }
+
+    /// Share of the income to transfer to the insurance account, rounded down
+    pub fn calc_reserve_amount(&self, income: u64) -> Result<u64, ProgramError> {
+        Decimal::from(income)
+            .try_mul(Rate::from_ratio(self.reserve_factor))?
+            .try_floor_u64()
+    }
+
+    /// Cumulative borrow rate compounding the debt since the liquidity was created
+    pub fn cumulative_borrow_rate(&self) -> Decimal {
+        Decimal::from_le_bytes(self.cumulative_borrow_rate)
+    }
+
+    /// Annual borrow rate multiplied by `RATIO_POWER`, rises linearly from the minimum to the
+    /// optimal rate up to the optimal utilization and steeply to the maximum rate beyond it
+    pub fn calc_borrow_rate(&self, utilization: u64) -> Result<u64, ProgramError> {
+        let utilization = utilization.min(RATIO_POWER);
+        let (rate_start, rate_end, utilization_offset, utilization_range) =
+            if utilization <= self.optimal_utilization {
+                (
+                    self.borrow_rate_min,
+                    self.borrow_rate_optimal,
+                    utilization,
+                    self.optimal_utilization,
+                )
+            } else {
+                (
+                    self.borrow_rate_optimal,
+                    self.borrow_rate_max,
+                    utilization - self.optimal_utilization,
+                    RATIO_POWER - self.optimal_utilization,
+                )
+            };
+
+        if utilization_range == 0 {
+            return Ok(rate_start);
+        }
+
+        let rate_range = rate_end
+            .checked_sub(rate_start)
+            .ok_or(LendingError::CalculationFailure)?;
+        let rate_increase = Decimal::from(rate_range)
+            .try_mul(utilization_offset)?
+            .try_div(utilization_range)?
+            .try_floor_u64()?;
+
+        let result = rate_start
+            .checked_add(rate_increase)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        Ok(result)
+    }
+
+    /// Compound the borrowed amount and the cumulative borrow rate by the interest of the slots
+    /// elapsed at the current utilization
+    pub fn accrue_interest(&mut self, token_account_amount: u64, slot: Slot) -> ProgramResult {
+        let slots_elapsed = slot.saturating_sub(self.interest_slot);
+        if slots_elapsed == 0 {
+            return Ok(());
+        }
+
+        let borrow_rate = self.calc_borrow_rate(self.calc_utilization(token_account_amount)?)?;
+        // Interest is simple within the elapsed slots and compounds with every accrual
+        let compounded_rate = Decimal::from_ratio(borrow_rate)
+            .try_mul(slots_elapsed)?
+            .try_div(SLOTS_PER_YEAR)?
+            .try_add(Decimal::one())?;
+
+        self.cumulative_borrow_rate = self
+            .cumulative_borrow_rate()
+            .try_mul(compounded_rate)?
+            .to_le_bytes();
+        let amount_borrowed = Decimal::from(self.amount_borrowed)
+            .try_mul(compounded_rate)?
+            .try_floor_u64()?;
+        if self.has_insurance() {
+            let reserve_amount =
+                self.calc_reserve_amount(amount_borrowed - self.amount_borrowed)?;
+            self.amount_reserve = self
+                .amount_reserve
+                .checked_add(reserve_amount)
+                .ok_or(LendingError::CalculationFailure)?;
+        }
+
+        self.amount_borrowed = amount_borrowed;
+        self.interest_slot = slot;
+
+        Ok(())
     }
 
     /// Borrow funds
@@ -73,57 +245,242 @@ impl Liquidity {
 
     /// Repay funds
     pub fn repay(&mut self, amount: u64) -> ProgramResult {
-        self.amount_borrowed = self
-            .amount_borrowed
-            .checked_sub(amount)
+        // Obligations round the interest up and the liquidity rounds it down,
+        // so the repaid debt can be a bit more than the borrowed amount
+        self.amount_borrowed = self.amount_borrowed.saturating_sub(amount);
+        // Reserve of the interest written off is forgiven
+        self.amount_reserve = self.amount_reserve.min(self.amount_borrowed);
+        Ok(())
+    }
+
+    /// Take the reserve out of the repaid amount before the repay, returns the amount to transfer
+    /// to the insurance account
+    pub fn take_reserve(&mut self, repay_amount: u64) -> u64 {
+        let amount = self.amount_reserve.min(repay_amount);
+        self.amount_reserve -= amount;
+        amount
+    }
+
+    /// Record the liquidity leaving through withdrawals and borrows, checked by the rate limit
+    pub fn record_outflow(&mut self, amount: u64, slot: Slot) -> ProgramResult {
+        // Roll the window over
+        if slot
+            >= self
+                .outflow_window_start
+                .saturating_add(self.outflow_window)
+        {
+            self.reset_outflow(slot);
+        }
+
+        let outflow_amount = self
+            .outflow_amount
+            .checked_add(amount)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        if self.outflow_limit != 0 && outflow_amount > self.outflow_limit {
+            msg!(
+                "Outflow {} exceeds the limit {} until slot {}",
+                outflow_amount,
+                self.outflow_limit,
+                self.outflow_window_start
+                    .saturating_add(self.outflow_window)
+            );
+            return Err(LendingError::OutflowLimitExceeded.into());
+        }
+
+        self.outflow_amount = outflow_amount;
+        Ok(())
+    }
+
+    /// Start a new outflow window
+    pub fn reset_outflow(&mut self, slot: Slot) {
+        self.outflow_window_start = slot;
+        self.outflow_amount = 0;
+    }
+
+    /// Queue the withdrawal of the liquidity amount, returns the queue offset of the ticket
+    pub fn request_withdraw(&mut self, amount: u64) -> Result<u64, ProgramError> {
+        let queue_offset = self.queue_amount_requested;
+        self.queue_amount_requested = queue_offset
+            .checked_add(amount)
+            .ok_or(LendingError::CalculationFailure)?;
+        Ok(queue_offset)
+    }
+
+    /// Pay out the filled withdrawal ticket
+    pub fn claim_withdraw(
+        &mut self,
+        queue_offset: u64,
+        amount: u64,
+        token_account_amount: u64,
+    ) -> ProgramResult {
+        let queue_end = queue_offset
+            .checked_add(amount)
+            .ok_or(LendingError::CalculationFailure)?;
+        if queue_end > self.calc_queue_amount_filled(token_account_amount)? {
+            return Err(LendingError::WithdrawalTicketNotFilled.into());
+        }
+
+        self.queue_amount_claimed = self
+            .queue_amount_claimed
+            .checked_add(amount)
             .ok_or(LendingError::CalculationFailure)?;
         Ok(())
     }
 
-    /// Deposit exchange amount
+    /// Amount of liquidity owed to the queued withdrawal tickets
+    pub fn calc_queue_amount(&self) -> Result<u64, ProgramError> {
+        let result = self
+            .queue_amount_requested
+            .checked_sub(self.queue_amount_claimed)
+            .ok_or(LendingError::CalculationFailure)?;
+
+        Ok(result)
+    }
+
+    /// Amount of liquidity ever requested by the filled withdrawal tickets.
+    /// The token account fills the queue first, so deposits and repayments
+    /// fill the tickets in the order of the requests.
+    pub fn calc_queue_amount_filled(&self, token_account_amount: u64) -> Result<u64, ProgramError> {
+        let result = self
+            .queue_amount_claimed
+            .checked_add(self.calc_queue_amount()?.min(token_account_amount))
+            .ok_or(LendingError::CalculationFailure)?;
+
+        Ok(result)
+    }
+
+    /// Amount of liquidity in the token account which isn't reserved for the withdrawal queue
+    pub fn calc_free_amount(&self, token_account_amount: u64) -> Result<u64, ProgramError> {
+        Ok(token_account_amount.saturating_sub(self.calc_queue_amount()?))
+    }
+
+    /// Total amount of liquidity in the token account and borrowed, except the amount
+    /// owed to the withdrawal queue
+    pub fn calc_total_amount(&self, token_account_amount: u64) -> Result<u64, ProgramError> {
+        let result = token_account_amount
+            .checked_add(self.amount_borrowed)
+            .ok_or(LendingError::CalculationFailure)?
+            // Queued withdrawals are paid before the providers bear the bad debt
+            .saturating_sub(self.calc_queue_amount()?)
+            // Reserve belongs to the insurance account
+            .saturating_sub(self.amount_reserve);
+
+        Ok(result)
+    }
+
+    /// Share of the total liquidity borrowed multiplied by `RATIO_POWER`, rounded up
+    pub fn calc_utilization(&self, token_account_amount: u64) -> Result<u64, ProgramError> {
+        let total_amount = self.calc_total_amount(token_account_amount)?;
+        if total_amount == 0 {
+            return Ok(0);
+        }
+
+        Decimal::from(self.amount_borrowed)
+            .try_div_ceil(total_amount)?
+            .try_ceil_ratio()
+    }
+
+    /// Check utilization to be within the maximum
+    pub fn check_utilization(&self, token_account_amount: u64) -> ProgramResult {
+        if self.calc_utilization(token_account_amount)? > self.max_utilization {
+            return Err(LendingError::UtilizationExceeded.into());
+        }
+
+        Ok(())
+    }
+
+    /// Amount of liquidity available to borrow within the maximum utilization, rounded down
+    pub fn calc_available_amount(&self, token_account_amount: u64) -> Result<u64, ProgramError> {
+        // total * max_utilization - borrowed, the total doesn't change on borrow
+        let max_amount_borrowed = Decimal::from(self.calc_total_amount(token_account_amount)?)
+            .try_mul(Rate::from_ratio(self.max_utilization))?
+            .try_floor_u64()?;
+
+        Ok(max_amount_borrowed
+            .saturating_sub(self.amount_borrowed)
+            .min(self.calc_free_amount(token_account_amount)?))
+    }
+
+    /// Amount of liquidity per pool token multiplied by `RATIO_POWER`
+    pub fn calc_exchange_rate(
+        &self,
+        token_account_amount: u64,
+        pool_mint_supply: u64,
+    ) -> Result<u64, ProgramError> {
+        self.calc_withdraw_exchange_amount(RATIO_POWER, token_account_amount, pool_mint_supply)
+    }
+
+    /// Amount of pool tokens to mint for the deposited liquidity, rounded down
     pub fn calc_deposit_exchange_amount(
         &self,
         amount: u64,
         token_account_amount: u64,
         pool_mint_supply: u64,
     ) -> Result<u64, ProgramError> {
-        let result = if pool_mint_supply == 0 || token_account_amount == 0 {
-            amount
-        } else {
-            let total_amount = token_account_amount
-                .checked_add(self.amount_borrowed)
-                .ok_or(LendingError::CalculationFailure)?;
-            (amount as u128)
-                .checked_mul(pool_mint_supply as u128)
-                .ok_or(LendingError::CalculationFailure)?
-                .checked_div(total_amount as u128)
-                .ok_or(LendingError::CalculationFailure)? as u64
-        };
+        let total_amount = self.calc_total_amount(token_account_amount)?;
+        if pool_mint_supply == 0 || total_amount == 0 {
+            return Ok(amount);
+        }
 
-        Ok(result)
+        Decimal::from(amount)
+            .try_mul(pool_mint_supply)?
+            .try_div(total_amount)?
+            .try_floor_u64()
     }
 
-    /// Withdraw exchange amount
+    /// Amount of liquidity to pay out for the burned pool tokens, rounded down
     pub fn calc_withdraw_exchange_amount(
         &self,
         amount: u64,
         token_account_amount: u64,
         pool_mint_supply: u64,
     ) -> Result<u64, ProgramError> {
-        let result = if pool_mint_supply == 0 || token_account_amount == 0 {
-            amount
-        } else {
-            let total_amount = token_account_amount
-                .checked_add(self.amount_borrowed)
-                .ok_or(LendingError::CalculationFailure)?;
-            (amount as u128)
-                .checked_mul(total_amount as u128)
-                .ok_or(LendingError::CalculationFailure)?
-                .checked_div(pool_mint_supply as u128)
-                .ok_or(LendingError::CalculationFailure)? as u64
-        };
+        let total_amount = self.calc_total_amount(token_account_amount)?;
+        if pool_mint_supply == 0 || total_amount == 0 {
+            return Ok(amount);
+        }
 
-        Ok(result)
+        Decimal::from(amount)
+            .try_mul(total_amount)?
+            .try_div(pool_mint_supply)?
+            .try_floor_u64()
+    }
+
+    /// Amount of liquidity to deposit for the pool tokens, rounded up
+    pub fn calc_deposit_amount_in(
+        &self,
+        pool_tokens_amount: u64,
+        token_account_amount: u64,
+        pool_mint_supply: u64,
+    ) -> Result<u64, ProgramError> {
+        let total_amount = self.calc_total_amount(token_account_amount)?;
+        if pool_mint_supply == 0 || total_amount == 0 {
+            return Ok(pool_tokens_amount);
+        }
+
+        Decimal::from(pool_tokens_amount)
+            .try_mul(total_amount)?
+            .try_div_ceil(pool_mint_supply)?
+            .try_ceil_u64()
+    }
+
+    /// Amount of pool tokens to burn for the liquidity, rounded up
+    pub fn calc_withdraw_amount_in(
+        &self,
+        liquidity_amount: u64,
+        token_account_amount: u64,
+        pool_mint_supply: u64,
+    ) -> Result<u64, ProgramError> {
+        let total_amount = self.calc_total_amount(token_account_amount)?;
+        if pool_mint_supply == 0 || total_amount == 0 {
+            return Ok(liquidity_amount);
+        }
+
+        Decimal::from(liquidity_amount)
+            .try_mul(pool_mint_supply)?
+            .try_div_ceil(total_amount)?
+            .try_ceil_u64()
     }
 }
 
@@ -137,23 +494,33 @@ pub struct InitLiquidityParams {
     pub token_account: Pubkey,
     /// Token that lenders will receive
     pub pool_mint: Pubkey,
+    /// Slot the interest starts accruing at
+    pub interest_slot: Slot,
 }
 
+assert_state_len!(
+    Liquidity,
+    1 + 1 + 1 + 5 + 32 * 4 + 8 + 32 + 8 * 10 + 8 * 4 + 24 + 8 + 8
+);
+
 impl Sealed for Liquidity {}
 impl Pack for Liquidity {
-    // 1 + 1 + 32 + 32 + 32 + 32 + 8
-    const LEN: usize = 138;
+    const LEN: usize = size_of::<Self>();
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut slice = dst;
-        self.serialize(&mut slice).unwrap()
+        self.write(dst)
     }
 
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
-        Self::try_from_slice(src).map_err(|_| {
-            msg!("Failed to deserialize");
-            ProgramError::InvalidAccountData
-        })
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::read(src)
+    }
+}
+
+unsafe impl ZeroCopy for Liquidity {
+    fn check_bit_pattern(data: &[u8]) -> ProgramResult {
+        // Status follows the version
+        LiquidityStatus::try_from(data[1]).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(())
     }
 }
 
@@ -1,25 +1,35 @@
 //! Program state definitions
 use super::*;
-use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use crate::error::LendingError;
 use solana_program::{
+    entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
+use std::mem::size_of;
 
 /// Lending Market
 #[repr(C)]
-#[derive(Debug, BorshDeserialize, BorshSerialize, BorshSchema, Default)]
+#[derive(Debug, Default)]
 pub struct Market {
     /// State version
     pub version: u8,
+    /// Padding for the alignment of the fields
+    pub padding: [u8; 7],
     /// Market owner
     pub owner: Pubkey,
     /// Number of liquidity tokens in the market
     pub liquidity_tokens: u64,
     /// Number of collateral tokens in the market
     pub collateral_tokens: u64,
+    /// Market guardian allowed to pause the operations, default if not set
+    pub guardian: Pubkey,
+    /// Bitmask of the paused operations, see `PAUSE_*`
+    pub paused: u64,
+    /// Unix timestamp of the last pause
+    pub paused_at: i64,
 }
 
 impl Market {
@@ -40,6 +50,37 @@ impl Market {
     pub fn increase_collateral_tokens(&mut self) {
         self.collateral_tokens += 1;
     }
+
+    /// Check that the signer is the guardian or the owner
+    pub fn check_guardian(&self, signer: &Pubkey) -> ProgramResult {
+        if *signer == self.owner || (self.guardian != Pubkey::default() && *signer == self.guardian)
+        {
+            Ok(())
+        } else {
+            msg!("Signer provided is neither the market guardian nor the owner");
+            Err(ProgramError::InvalidArgument)
+        }
+    }
+
+    /// Pause the operations
+    pub fn pause(&mut self, operations: u64, now: i64) {
+        self.paused |= operations;
+        self.paused_at = now;
+    }
+
+    /// Unpause the operations
+    pub fn unpause(&mut self, operations: u64) {
+        self.paused &= !operations;
+    }
+
+    /// Check that the operation isn't paused
+    pub fn check_not_paused(&self, operation: u64) -> ProgramResult {
+        if self.paused & operation != 0 {
+            return Err(LendingError::OperationPaused.into());
+        }
+
+        Ok(())
+    }
 }
 
 /// Initialize a market params
@@ -48,24 +89,23 @@ pub struct InitMarketParams {
     pub owner: Pubkey,
 }
 
+assert_state_len!(Market, 1 + 7 + 32 + 8 + 8 + 32 + 8 + 8);
+
 impl Sealed for Market {}
 impl Pack for Market {
-    // 1 + 32 + 8 + 8
-    const LEN: usize = 49;
+    const LEN: usize = size_of::<Self>();
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut slice = dst;
-        self.serialize(&mut slice).unwrap()
+        self.write(dst)
     }
 
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
-        Self::try_from_slice(src).map_err(|_| {
-            msg!("Failed to deserialize");
-            ProgramError::InvalidAccountData
-        })
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::read(src)
     }
 }
 
+unsafe impl ZeroCopy for Market {}
+
 impl IsInitialized for Market {
     fn is_initialized(&self) -> bool {
         self.version != UNINITIALIZED_VERSION
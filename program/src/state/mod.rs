@@ -1,25 +1,85 @@
 //! State types
 
+use crate::error::LendingError;
+use solana_program::{
+    clock::{DEFAULT_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT, SECONDS_PER_DAY},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::IsInitialized,
+};
+use std::mem::{align_of, size_of};
+
+/// Compile-time check that the state length is the sum of its field lengths,
+/// so the `#[repr(C)]` layout has no implicit padding
+macro_rules! assert_state_len {
+    ($state:ty, $len:expr) => {
+        const _: [(); $len] = [(); std::mem::size_of::<$state>()];
+    };
+}
+
+mod category;
 mod collateral;
+mod delegation;
 mod liquidity;
 mod market;
 mod obligation;
+mod pair;
+mod withdrawal_ticket;
 
+pub use category::*;
 pub use collateral::*;
+pub use delegation::*;
 pub use liquidity::*;
 pub use market::*;
 pub use obligation::*;
+pub use pair::*;
+pub use withdrawal_ticket::*;
 
 /// Accounts are created with data zeroed out, so uninitialized state instances
 /// will have the version set to 0.
 pub const UNINITIALIZED_VERSION: u8 = 0;
 
-/// Current version of the program and all new accounts created
-pub const PROGRAM_VERSION: u8 = 1;
+/// Current version of the program and all new accounts created.
+/// Version 1 accounts were serialized with borsh, version 2 accounts use the zero-copy layout.
+/// Version 1 accounts aren't migrated, the program is redeployed with the accounts created again.
+pub const PROGRAM_VERSION: u8 = 2;
 
 /// Ratio power
 pub const RATIO_POWER: u64 = 1_000_000_000;
 
+/// Category of the assets and obligations which don't belong to any category
+pub const NO_CATEGORY: u8 = 0;
+
+/// Maximum number of liquidity approved for the isolated collateral
+pub const MAX_ISOLATED_LIQUIDITIES: usize = 4;
+
+/// Delay in seconds between the insurance withdrawal request and the withdrawal
+pub const INSURANCE_WITHDRAW_TIMELOCK: i64 = 2 * 24 * 60 * 60;
+
+/// Approximate number of slots per year the annual borrow rates are spread over
+pub const SLOTS_PER_YEAR: u64 =
+    DEFAULT_TICKS_PER_SECOND / DEFAULT_TICKS_PER_SLOT * SECONDS_PER_DAY * 365;
+
+/// Lamports wrapped over the compounded debt on the native repay of the whole debt,
+/// returned to the owner on the close of the native account
+pub const REPAY_NATIVE_BUFFER: u64 = 1_000;
+
+/// Deposits of liquidity and collateral
+pub const PAUSE_DEPOSIT: u64 = 1 << 0;
+
+/// Withdrawals of liquidity and collateral
+pub const PAUSE_WITHDRAW: u64 = 1 << 1;
+
+/// Borrowing of liquidity
+pub const PAUSE_BORROW: u64 = 1 << 2;
+
+/// Liquidations and write-offs of the obligations
+pub const PAUSE_LIQUIDATE: u64 = 1 << 3;
+
+/// All the operations the guardian can pause
+pub const PAUSE_ALL: u64 = PAUSE_DEPOSIT | PAUSE_WITHDRAW | PAUSE_BORROW | PAUSE_LIQUIDATE;
+
 /// Convert the UI representation of a ratio (like 0.5) to the raw ratio
 pub fn ui_ratio_to_ratio(ui_ratio: f64) -> u64 {
     (ui_ratio * RATIO_POWER as f64).round() as u64
@@ -29,3 +89,104 @@ pub fn ui_ratio_to_ratio(ui_ratio: f64) -> u64 {
 pub fn ratio_to_ui_ratio(ratio: u64) -> f64 {
     ratio as f64 / RATIO_POWER as f64
 }
+
+/// State which is read and modified in place of the account data
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` without implicit padding, start with the `version`
+/// field, and any bit pattern accepted by `check_bit_pattern` must be a valid value of the state.
+pub unsafe trait ZeroCopy: Sized + Default + IsInitialized {
+    /// Check the bit pattern of the fields that aren't valid for any data, like enums
+    fn check_bit_pattern(_data: &[u8]) -> ProgramResult {
+        Ok(())
+    }
+
+    /// Borrow initialized state from the account data
+    fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        check_layout::<Self>(data)?;
+        check_version(data)?;
+        Self::check_bit_pattern(data)?;
+
+        // Safety: length, alignment and bit pattern of the data are checked above
+        let state = unsafe { &*(data.as_ptr() as *const Self) };
+        assert_initialized(state)?;
+
+        Ok(state)
+    }
+
+    /// Mutably borrow initialized state from the account data
+    fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        check_layout::<Self>(data)?;
+        check_version(data)?;
+        Self::check_bit_pattern(data)?;
+
+        // Safety: length, alignment and bit pattern of the data are checked above
+        let state = unsafe { &mut *(data.as_mut_ptr() as *mut Self) };
+        assert_initialized(state)?;
+
+        Ok(state)
+    }
+
+    /// Byte representation of the state
+    fn as_bytes(&self) -> &[u8] {
+        // Safety: the state has no padding bytes
+        unsafe { std::slice::from_raw_parts(self as *const Self as *const u8, size_of::<Self>()) }
+    }
+
+    /// Copy the state from the data of any alignment
+    fn read(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() != size_of::<Self>() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_version(data)?;
+        Self::check_bit_pattern(data)?;
+
+        let mut state = Self::default();
+        // Safety: the bit pattern of the data is checked above
+        unsafe {
+            std::slice::from_raw_parts_mut(&mut state as *mut Self as *mut u8, size_of::<Self>())
+                .copy_from_slice(data);
+        }
+
+        Ok(state)
+    }
+
+    /// Copy the state to the data
+    fn write(&self, data: &mut [u8]) {
+        data.copy_from_slice(self.as_bytes())
+    }
+}
+
+fn check_layout<T>(data: &[u8]) -> ProgramResult {
+    if data.len() != size_of::<T>() {
+        msg!("Account data length does not match the state");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if data.as_ptr().align_offset(align_of::<T>()) != 0 {
+        msg!("Account data is not aligned");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(())
+}
+
+fn check_version(data: &[u8]) -> ProgramResult {
+    // Accounts of another layout can't be read in place, even if the length matches
+    match data[0] {
+        UNINITIALIZED_VERSION | PROGRAM_VERSION => Ok(()),
+        version => {
+            msg!("Account state version {} is not supported", version);
+            Err(LendingError::UnsupportedStateVersion.into())
+        }
+    }
+}
+
+fn assert_initialized<T: IsInitialized>(state: &T) -> ProgramResult {
+    if state.is_initialized() {
+        Ok(())
+    } else {
+        Err(ProgramError::UninitializedAccount)
+    }
+}
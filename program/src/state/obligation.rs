@@ -1,22 +1,33 @@
 //! Program state definitions
-use crate::error::LendingError;
+use crate::{
+    error::LendingError,
+    math::{Decimal, Rate, TryAdd, TryDivCeil, TryMul},
+};
 
 use super::*;
-use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use solana_program::{
+    clock::Slot,
     entrypoint::ProgramResult,
-    msg,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
+use std::mem::size_of;
 
 /// Obligation
 #[repr(C)]
-#[derive(Debug, BorshDeserialize, BorshSerialize, BorshSchema, Default)]
+#[derive(Debug, Default)]
 pub struct Obligation {
     /// State version
     pub version: u8,
+    /// Created with the isolated collateral, the debt counts towards the collateral debt ceiling
+    pub isolated: bool,
+    /// Category the owner opted into, its risk parameters apply while the assets share it
+    pub category: u8,
+    /// Padding for the alignment of the fields
+    pub padding: [u8; 3],
+    /// Sub-account index among the owner obligations of the pair
+    pub index: u16,
     /// Market
     pub market: Pubkey,
     /// Obligation owner
@@ -29,6 +40,17 @@ pub struct Obligation {
     pub amount_liquidity_borrowed: u64,
     /// Amount of deposited collateral
     pub amount_collateral_deposited: u64,
+    /// Slot the obligation was recorded unhealthy at, zero while healthy
+    pub unhealthy_since: Slot,
+    /// One-of-one mint representing the ownership, default while not tokenized
+    pub position_mint: Pubkey,
+    /// Operator appointed by the owner, default while not appointed
+    pub operator: Pubkey,
+    /// Limit for the healthy ratio the obligation was recorded unhealthy against (multiplied by 10e9)
+    pub unhealthy_ratio: u64,
+    /// Cumulative borrow rate of the liquidity the debt was compounded to, little-endian
+    /// `Decimal` scaled value
+    pub cumulative_borrow_rate: [u8; 24],
 }
 
 impl Obligation {
@@ -39,10 +61,26 @@ impl Obligation {
         self.owner = params.owner;
         self.liquidity = params.liquidity;
         self.collateral = params.collateral;
+        self.isolated = params.isolated;
+        self.index = params.index;
         self.amount_liquidity_borrowed = 0;
         self.amount_collateral_deposited = 0;
     }
 
+    /// Ownership is represented by the position mint
+    pub fn is_tokenized(&self) -> bool {
+        self.position_mint != Pubkey::default()
+    }
+
+    /// Current owner or the operator appointed by the owner, the operator lapses once the position
+    /// token of the tokenized obligation moved to a new holder
+    pub fn is_owner_or_operator(&self, owner: &Pubkey, authority: &Pubkey) -> bool {
+        *owner == *authority
+            || (self.owner == *owner
+                && self.operator != Pubkey::default()
+                && self.operator == *authority)
+    }
+
     /// Increase amount of deposited collateral
     pub fn collateral_deposit(&mut self, amount: u64) -> ProgramResult {
         self.amount_collateral_deposited = self
@@ -63,6 +101,24 @@ impl Obligation {
         Ok(())
     }
 
+    /// Compound the borrowed liquidity to the cumulative borrow rate of the liquidity,
+    /// the interest is rounded up
+    pub fn accrue_interest(&mut self, cumulative_borrow_rate: Decimal) -> ProgramResult {
+        let previous_rate = Decimal::from_le_bytes(self.cumulative_borrow_rate);
+        if self.amount_liquidity_borrowed > 0
+            && previous_rate != Decimal::zero()
+            && cumulative_borrow_rate > previous_rate
+        {
+            self.amount_liquidity_borrowed = Decimal::from(self.amount_liquidity_borrowed)
+                .try_mul(cumulative_borrow_rate)?
+                .try_div_ceil(previous_rate)?
+                .try_ceil_u64()?;
+        }
+
+        self.cumulative_borrow_rate = cumulative_borrow_rate.to_le_bytes();
+        Ok(())
+    }
+
     /// Increase amount of borrowed liquidity
     pub fn liquidity_borrow(&mut self, amount: u64) -> ProgramResult {
         self.amount_liquidity_borrowed = self
@@ -83,67 +139,148 @@ impl Obligation {
         Ok(())
     }
 
+    /// Write off borrowed liquidity left without collateral, returns the amount written off
+    pub fn write_off_bad_debt(&mut self) -> Result<u64, ProgramError> {
+        if self.amount_collateral_deposited > 0 {
+            return Err(LendingError::ObligationNotInsolvent.into());
+        }
+
+        if self.amount_liquidity_borrowed == 0 {
+            return Err(LendingError::ObligationHasNoDebt.into());
+        }
+
+        let amount = self.amount_liquidity_borrowed;
+        self.amount_liquidity_borrowed = 0;
+
+        Ok(amount)
+    }
+
     /// Calculate obligation health ratio
     pub fn calc_health(&self) -> Result<u64, ProgramError> {
         // TODO: Add oracle interface here to calculate collateral and borrowed liquidity value.
         // For now we assume that collateral and liquidity tokens have 1:1 value ratio
-        let result = (self.amount_liquidity_borrowed as u128)
-            .checked_mul(RATIO_POWER as u128)
-            .ok_or(LendingError::CalculationFailure)?
-            .checked_div(self.amount_collateral_deposited as u128)
-            .ok_or(LendingError::CollateralHealthCheckFailed)? as u64;
+        if self.amount_liquidity_borrowed == 0 {
+            return Ok(0);
+        }
 
-        Ok(result)
+        if self.amount_collateral_deposited == 0 {
+            return Err(LendingError::CollateralHealthCheckFailed.into());
+        }
+
+        // Health is rounded up, so the obligation never looks healthier than it is
+        Decimal::from(self.amount_liquidity_borrowed)
+            .try_div_ceil(self.amount_collateral_deposited)?
+            .try_ceil_ratio()
+    }
+
+    /// Check health to be within the initial collateralization ratio, returns the health
+    pub fn check_health(&self, ratio_initial: u64) -> Result<u64, ProgramError> {
+        let health = self.calc_health()?;
+        if health > ratio_initial {
+            return Err(LendingError::CollateralHealthCheckFailed.into());
+        }
+
+        Ok(health)
+    }
+
+    /// Calculate obligation health ratio, `u64::MAX` for the debt left without collateral
+    pub fn calc_health_saturating(&self) -> Result<u64, ProgramError> {
+        if self.amount_liquidity_borrowed > 0 && self.amount_collateral_deposited == 0 {
+            return Ok(u64::MAX);
+        }
+
+        self.calc_health()
+    }
+
+    /// Check health to be beyond the healthy collateralization ratio, returns the health
+    pub fn check_unhealthy(&self, ratio_healthy: u64) -> Result<u64, ProgramError> {
+        let health = self.calc_health_saturating()?;
+        if health <= ratio_healthy {
+            return Err(LendingError::ObligationHealthy.into());
+        }
+
+        Ok(health)
+    }
+
+    /// Record the slot the obligation became unhealthy at, reset once it's healthy, returns the health
+    pub fn record_health(&mut self, ratio_healthy: u64, slot: Slot) -> Result<u64, ProgramError> {
+        let health = self.calc_health_saturating()?;
+        if health <= ratio_healthy {
+            self.unhealthy_since = 0;
+        } else {
+            if self.unhealthy_since == 0 {
+                self.unhealthy_since = slot;
+            }
+            self.unhealthy_ratio = ratio_healthy;
+        }
+
+        Ok(health)
+    }
+
+    /// Reset the recorded unhealthy slot once a deposit or repay leaves the obligation healthy
+    /// against the limit it was recorded with
+    pub fn reset_unhealthy(&mut self) -> ProgramResult {
+        if self.unhealthy_since != 0 && self.calc_health_saturating()? <= self.unhealthy_ratio {
+            self.unhealthy_since = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Amount of collateral paid out for the repaid liquidity with the liquidation bonus,
+    /// rounded down and limited by the deposited collateral
+    pub fn calc_liquidation_amount(
+        &self,
+        repay_amount: u64,
+        liquidation_bonus: u64,
+    ) -> Result<u64, ProgramError> {
+        // TODO: Value the collateral by the oracle price, same as the health
+        let amount = Decimal::from(repay_amount)
+            .try_mul(Rate::one().try_add(Rate::from_ratio(liquidation_bonus))?)?
+            .try_floor_u64()?;
+
+        Ok(amount.min(self.amount_collateral_deposited))
     }
 
     /// Calculation of available funds for withdrawal
     pub fn calc_withdrawal_limit(&self, ratio_initial: u64) -> Result<u64, ProgramError> {
         // deposited - borrowed / ratio_initial
-        let result = self
-            .amount_collateral_deposited
-            .checked_sub(
-                self.amount_liquidity_borrowed
-                    .checked_mul(RATIO_POWER)
-                    .ok_or(LendingError::CalculationFailure)?
-                    .checked_div(ratio_initial)
-                    .ok_or(LendingError::CalculationFailure)?,
-            )
-            .ok_or(LendingError::CalculationFailure)?;
+        // Required collateral is rounded up to keep the obligation healthy after withdrawal
+        let required_collateral = Decimal::from(self.amount_liquidity_borrowed)
+            .try_div_ceil(Rate::from_ratio(ratio_initial))?
+            .try_ceil_u64()?;
 
-        Ok(result)
+        Ok(self
+            .amount_collateral_deposited
+            .saturating_sub(required_collateral))
     }
 
     /// Calculation of available funds for borrowing
     pub fn calc_borrowing_limit(&self, ratio_initial: u64) -> Result<u64, ProgramError> {
         // deposited * ratio_initial - borrowed
-        let result = self
-            .amount_collateral_deposited
-            .checked_mul(ratio_initial)
-            .ok_or(LendingError::CalculationFailure)?
-            .checked_div(RATIO_POWER)
-            .ok_or(LendingError::CalculationFailure)?
-            .checked_sub(self.amount_liquidity_borrowed)
-            .ok_or(LendingError::CalculationFailure)?;
+        let borrowing_limit = Decimal::from(self.amount_collateral_deposited)
+            .try_mul(Rate::from_ratio(ratio_initial))?
+            .try_floor_u64()?;
 
-        Ok(result)
+        Ok(borrowing_limit.saturating_sub(self.amount_liquidity_borrowed))
     }
 }
 
+assert_state_len!(
+    Obligation,
+    1 + 1 + 1 + 3 + 2 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 32 + 32 + 8 + 24
+);
+
 impl Sealed for Obligation {}
 impl Pack for Obligation {
-    // 1 + 32 + 32 + 32 + 32 + 8 + 8
-    const LEN: usize = 145;
+    const LEN: usize = size_of::<Self>();
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut slice = dst;
-        self.serialize(&mut slice).unwrap()
+        self.write(dst)
     }
 
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
-        Self::try_from_slice(src).map_err(|_| {
-            msg!("Failed to deserialize");
-            ProgramError::InvalidAccountData
-        })
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::read(src)
     }
 }
 
@@ -157,6 +294,20 @@ pub struct InitObligationParams {
     pub liquidity: Pubkey,
     /// Collateral
     pub collateral: Pubkey,
+    /// Created with the isolated collateral
+    pub isolated: bool,
+    /// Sub-account index among the owner obligations of the pair
+    pub index: u16,
+}
+
+unsafe impl ZeroCopy for Obligation {
+    fn check_bit_pattern(data: &[u8]) -> ProgramResult {
+        // Isolation follows the version
+        if data[1] > 1 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
 }
 
 impl IsInitialized for Obligation {
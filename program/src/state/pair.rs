@@ -0,0 +1,113 @@
+//! Program state definitions
+use super::*;
+use crate::error::LendingError;
+use solana_program::{
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::mem::size_of;
+
+/// Risk parameters of the collateral backing the liquidity
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Pair {
+    /// State version
+    pub version: u8,
+    /// Borrowing of the liquidity against the collateral is enabled
+    pub borrow_enabled: bool,
+    /// Padding for the alignment of the fields
+    pub padding: [u8; 6],
+    /// Market
+    pub market: Pubkey,
+    /// Liquidity
+    pub liquidity: Pubkey,
+    /// Collateral
+    pub collateral: Pubkey,
+    /// Fractional initial collateralization ratio, loan to value (multiplied by 10e9)
+    pub ratio_initial: u64,
+    /// Fractional limit for the healthy collateralization ratio, liquidation threshold (multiplied by 10e9)
+    pub ratio_healthy: u64,
+}
+
+impl Pair {
+    /// Initialize a pair
+    pub fn init(&mut self, params: InitPairParams) {
+        self.version = PROGRAM_VERSION;
+        self.market = params.market;
+        self.liquidity = params.liquidity;
+        self.collateral = params.collateral;
+        self.update(params.config);
+    }
+
+    /// Update risk parameters
+    pub fn update(&mut self, config: PairConfig) {
+        self.borrow_enabled = config.borrow_enabled;
+        self.ratio_initial = config.ratio_initial;
+        self.ratio_healthy = config.ratio_healthy;
+    }
+
+    /// Check that borrowing is enabled
+    pub fn check_borrow_enabled(&self) -> ProgramResult {
+        if self.borrow_enabled {
+            Ok(())
+        } else {
+            Err(LendingError::PairBorrowDisabled.into())
+        }
+    }
+}
+
+/// Risk parameters of the pair
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PairConfig {
+    /// Borrowing of the liquidity against the collateral is enabled
+    pub borrow_enabled: bool,
+    /// Fractional initial collateralization ratio, loan to value (multiplied by 10e9)
+    pub ratio_initial: u64,
+    /// Fractional limit for the healthy collateralization ratio, liquidation threshold (multiplied by 10e9)
+    pub ratio_healthy: u64,
+}
+
+/// Initialize a pair params
+pub struct InitPairParams {
+    /// Market
+    pub market: Pubkey,
+    /// Liquidity
+    pub liquidity: Pubkey,
+    /// Collateral
+    pub collateral: Pubkey,
+    /// Risk parameters
+    pub config: PairConfig,
+}
+
+assert_state_len!(Pair, 1 + 1 + 6 + 32 + 32 + 32 + 8 + 8);
+
+impl Sealed for Pair {}
+impl Pack for Pair {
+    const LEN: usize = size_of::<Self>();
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        self.write(dst)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::read(src)
+    }
+}
+
+unsafe impl ZeroCopy for Pair {
+    fn check_bit_pattern(data: &[u8]) -> ProgramResult {
+        // Borrow enabled flag follows the version
+        if data[1] > 1 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+}
+
+impl IsInitialized for Pair {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
@@ -0,0 +1,77 @@
+//! Program state definitions
+use super::*;
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::mem::size_of;
+
+/// Liquidity withdrawal waiting in the queue of the liquidity
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct WithdrawalTicket {
+    /// State version
+    pub version: u8,
+    /// Padding for the alignment of the fields
+    pub padding: [u8; 7],
+    /// Liquidity
+    pub liquidity: Pubkey,
+    /// Owner receiving the liquidity
+    pub owner: Pubkey,
+    /// Amount of liquidity to pay out
+    pub amount: u64,
+    /// Amount of pool tokens burnt on the request
+    pub pool_tokens_amount: u64,
+    /// Amount of liquidity requested by the tickets queued before this one
+    pub queue_offset: u64,
+}
+
+impl WithdrawalTicket {
+    /// Initialize a withdrawal ticket
+    pub fn init(&mut self, params: InitWithdrawalTicketParams) {
+        self.version = PROGRAM_VERSION;
+        self.liquidity = params.liquidity;
+        self.owner = params.owner;
+        self.amount = params.amount;
+        self.pool_tokens_amount = params.pool_tokens_amount;
+        self.queue_offset = params.queue_offset;
+    }
+}
+
+/// Initialize a withdrawal ticket params
+pub struct InitWithdrawalTicketParams {
+    /// Liquidity
+    pub liquidity: Pubkey,
+    /// Owner receiving the liquidity
+    pub owner: Pubkey,
+    /// Amount of liquidity to pay out
+    pub amount: u64,
+    /// Amount of pool tokens burnt on the request
+    pub pool_tokens_amount: u64,
+    /// Amount of liquidity requested by the tickets queued before this one
+    pub queue_offset: u64,
+}
+
+assert_state_len!(WithdrawalTicket, 1 + 7 + 32 + 32 + 8 + 8 + 8);
+
+impl Sealed for WithdrawalTicket {}
+impl Pack for WithdrawalTicket {
+    const LEN: usize = size_of::<Self>();
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        self.write(dst)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::read(src)
+    }
+}
+
+unsafe impl ZeroCopy for WithdrawalTicket {}
+
+impl IsInitialized for WithdrawalTicket {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
@@ -0,0 +1,63 @@
+//! Swap program interface used to repay the debt with the collateral
+//!
+//! Any program implementing the interface can be plugged into `RepayWithCollateral`,
+//! the lending program only relies on the token amount received by the destination.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Instruction accepted by the swap program
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub enum SwapInstruction {
+    /// Swap tokens of the source account for the tokens of the destination account
+    ///
+    /// Accounts:
+    /// [W] Source account
+    /// [W] Destination account
+    /// [RS] User transfer authority
+    /// [R] Token program id
+    /// .. Swap program accounts
+    Swap {
+        /// Amount of tokens to swap
+        amount_in: u64,
+        /// Minimum amount of tokens to receive
+        minimum_amount_out: u64,
+    },
+}
+
+/// Create `Swap` instruction of the swap program
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    swap_program_id: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    source: &Pubkey,
+    destination: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_accounts: &[AccountMeta],
+) -> Result<Instruction, ProgramError> {
+    let init_data = SwapInstruction::Swap {
+        amount_in,
+        minimum_amount_out,
+    };
+    let data = init_data.try_to_vec()?;
+
+    let mut accounts = vec![
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new_readonly(*user_transfer_authority, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    accounts.extend_from_slice(swap_accounts);
+
+    Ok(Instruction {
+        program_id: *swap_program_id,
+        accounts,
+        data,
+    })
+}
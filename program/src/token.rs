@@ -0,0 +1,129 @@
+//! Token program helpers shared by SPL Token and Token-2022
+
+use crate::error::LendingError;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    program_pack::Pack, pubkey::Pubkey,
+};
+use spl_token::state::{Account, Mint};
+
+/// Token-2022 program id
+pub mod spl_token_2022 {
+    solana_program::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+/// Token-2022 account type of a mint, stored right after the padded base state
+const ACCOUNT_TYPE_MINT: u8 = 1;
+
+/// Token-2022 transfer fee config extension type
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+
+/// Token-2022 metadata pointer extension type
+const EXTENSION_METADATA_POINTER: u16 = 18;
+
+/// Token-2022 token metadata extension type
+const EXTENSION_TOKEN_METADATA: u16 = 19;
+
+/// Token-2022 mint extensions which don't change the accounting of the program.
+/// Transfer fees are handled by crediting the amount actually received.
+const SUPPORTED_MINT_EXTENSIONS: [u16; 3] = [
+    EXTENSION_TRANSFER_FEE_CONFIG,
+    EXTENSION_METADATA_POINTER,
+    EXTENSION_TOKEN_METADATA,
+];
+
+/// Check that the token program is SPL Token or Token-2022
+pub fn is_supported_token_program(token_program_id: &Pubkey) -> bool {
+    *token_program_id == spl_token::id() || *token_program_id == spl_token_2022::id()
+}
+
+/// Check that the token program is supported and owns the token account or mint
+pub fn assert_token_program(token_program: &AccountInfo, account: &AccountInfo) -> ProgramResult {
+    if !is_supported_token_program(token_program.key) {
+        msg!("Token program is not supported");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if account.owner != token_program.key {
+        msg!("Token account is not owned by the token program provided");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    Ok(())
+}
+
+/// Unpack base state of a token account, ignoring Token-2022 extensions
+pub fn unpack_token_account(data: &[u8]) -> Result<Account, ProgramError> {
+    Account::unpack(
+        data.get(..Account::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?,
+    )
+}
+
+/// Unpack base state of a mint, ignoring Token-2022 extensions
+pub fn unpack_mint(data: &[u8]) -> Result<Mint, ProgramError> {
+    Mint::unpack(
+        data.get(..Mint::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?,
+    )
+}
+
+/// Check that the mint has no extensions the program can't account for
+pub fn assert_supported_mint(mint_info: &AccountInfo) -> ProgramResult {
+    for extension_type in get_mint_extension_types(&mint_info.data.borrow())? {
+        if !SUPPORTED_MINT_EXTENSIONS.contains(&extension_type) {
+            msg!("Mint extension {} is not supported", extension_type);
+            return Err(LendingError::UnsupportedMintExtension.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the mint charges a transfer fee
+pub fn has_transfer_fee(mint_data: &[u8]) -> Result<bool, ProgramError> {
+    Ok(get_mint_extension_types(mint_data)?.contains(&EXTENSION_TRANSFER_FEE_CONFIG))
+}
+
+/// Length of a token account for the mint, including the extensions required by the mint
+pub fn get_token_account_len(mint_data: &[u8]) -> Result<usize, ProgramError> {
+    if has_transfer_fee(mint_data)? {
+        // Account type and the transfer fee amount extension (type, length, withheld amount)
+        Ok(Account::LEN + 1 + 2 + 2 + 8)
+    } else {
+        Ok(Account::LEN)
+    }
+}
+
+/// Read Token-2022 extension types of the mint
+fn get_mint_extension_types(data: &[u8]) -> Result<Vec<u16>, ProgramError> {
+    unpack_mint(data)?;
+
+    let mut extension_types = Vec::new();
+    if data.len() == Mint::LEN {
+        return Ok(extension_types);
+    }
+
+    // Token-2022 pads the mint to the account length and stores extensions after the account type
+    if data.get(Account::LEN) != Some(&ACCOUNT_TYPE_MINT) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut tlv = &data[Account::LEN + 1..];
+    while tlv.len() >= 4 {
+        let extension_type = u16::from_le_bytes([tlv[0], tlv[1]]);
+        let length = u16::from_le_bytes([tlv[2], tlv[3]]) as usize;
+
+        // Uninitialized extension, the rest of the data is padding
+        if extension_type == 0 {
+            break;
+        }
+
+        extension_types.push(extension_type);
+        tlv = tlv
+            .get(4 + length..)
+            .ok_or(ProgramError::InvalidAccountData)?;
+    }
+
+    Ok(extension_types)
+}
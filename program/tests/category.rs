@@ -0,0 +1,457 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus, NO_CATEGORY, RATIO_POWER},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const CATEGORY_ID: u8 = 1;
+const CATEGORY_RATIO_INITIAL: u64 = 90 * RATIO_POWER / 100;
+const CATEGORY_RATIO_HEALTHY: u64 = 95 * RATIO_POWER / 100;
+
+async fn setup() -> (
+    ProgramTestContext,
+    MarketInfo,
+    LiquidityInfo,
+    CollateralInfo,
+    PairInfo,
+    CategoryInfo,
+) {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let category_info = CategoryInfo::new(CATEGORY_ID, &market_info);
+    category_info
+        .create(
+            &mut context,
+            CATEGORY_RATIO_INITIAL,
+            CATEGORY_RATIO_HEALTHY,
+            "stablecoins",
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &source,
+            &destination,
+            999999,
+        )
+        .await;
+
+    (
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        category_info,
+    )
+}
+
+/// Obligation with the collateral deposited and the borrower liquidity account
+async fn prepare_borrower(
+    context: &mut ProgramTestContext,
+    market_info: &MarketInfo,
+    liquidity_info: &LiquidityInfo,
+    collateral_info: &CollateralInfo,
+    pair_info: &PairInfo,
+) -> (ObligationInfo, Keypair) {
+    let obligation_info = ObligationInfo::new(market_info);
+    obligation_info
+        .create(
+            context,
+            market_info,
+            liquidity_info,
+            collateral_info,
+            pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+
+    create_token_account(
+        context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            context,
+            market_info,
+            collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    (obligation_info, borrower_liquidity)
+}
+
+#[tokio::test]
+async fn success() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info, category_info) =
+        setup().await;
+
+    let category = category_info.get_data(&mut context).await;
+    assert_eq!(category.id, CATEGORY_ID);
+    assert_eq!(category.ratio_initial, CATEGORY_RATIO_INITIAL);
+
+    liquidity_info
+        .update_category(
+            &mut context,
+            CATEGORY_ID,
+            Some(&category_info),
+            &market_info,
+        )
+        .await
+        .unwrap();
+    collateral_info
+        .update_category(
+            &mut context,
+            CATEGORY_ID,
+            Some(&category_info),
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let (obligation_info, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+    )
+    .await;
+
+    obligation_info
+        .update_category(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            CATEGORY_ID,
+            Some(&category_info),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        obligation_info.get_data(&mut context).await.category,
+        CATEGORY_ID
+    );
+
+    // Borrow limit of the obligation follows the category
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+            Some(&category_info),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut context, &borrower_liquidity.pubkey()).await,
+        DEPOSIT_AMOUNT * 9 / 10
+    );
+}
+
+#[tokio::test]
+async fn success_category_removed() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info, category_info) =
+        setup().await;
+
+    liquidity_info
+        .update_category(
+            &mut context,
+            CATEGORY_ID,
+            Some(&category_info),
+            &market_info,
+        )
+        .await
+        .unwrap();
+    collateral_info
+        .update_category(
+            &mut context,
+            CATEGORY_ID,
+            Some(&category_info),
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let (obligation_info, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+    )
+    .await;
+
+    obligation_info
+        .update_category(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            CATEGORY_ID,
+            Some(&category_info),
+        )
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update_category(&mut context, NO_CATEGORY, None, &market_info)
+        .await
+        .unwrap();
+
+    // Pair risk parameters apply once the assets don't share the category
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+            Some(&category_info),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut context, &borrower_liquidity.pubkey()).await,
+        DEPOSIT_AMOUNT / 2
+    );
+}
+
+#[tokio::test]
+async fn fail_update_obligation_category_not_shared() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info, category_info) =
+        setup().await;
+
+    collateral_info
+        .update_category(
+            &mut context,
+            CATEGORY_ID,
+            Some(&category_info),
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let (obligation_info, _) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+    )
+    .await;
+
+    assert_eq!(
+        obligation_info
+            .update_category(
+                &mut context,
+                &market_info,
+                &liquidity_info,
+                &collateral_info,
+                &pair_info,
+                CATEGORY_ID,
+                Some(&category_info),
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidCategory as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_update_category_not_created() {
+    let (mut context, market_info, liquidity_info, collateral_info, _, _) = setup().await;
+
+    let missing_category_info = CategoryInfo::new(CATEGORY_ID + 1, &market_info);
+
+    assert_eq!(
+        liquidity_info
+            .update_category(
+                &mut context,
+                CATEGORY_ID + 1,
+                Some(&missing_category_info),
+                &market_info,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountOwner as u32)
+        )
+    );
+
+    assert_eq!(
+        collateral_info
+            .update_category(
+                &mut context,
+                CATEGORY_ID + 1,
+                Some(&missing_category_info),
+                &market_info,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountOwner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_update_category_id_mismatch() {
+    let (mut context, market_info, liquidity_info, collateral_info, _, category_info) =
+        setup().await;
+
+    assert_eq!(
+        liquidity_info
+            .update_category(
+                &mut context,
+                CATEGORY_ID + 1,
+                Some(&category_info),
+                &market_info,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidCategory as u32)
+        )
+    );
+
+    assert_eq!(
+        collateral_info
+            .update_category(
+                &mut context,
+                CATEGORY_ID + 1,
+                Some(&category_info),
+                &market_info,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidCategory as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_update_category_without_account() {
+    let (mut context, market_info, liquidity_info, _, _, _) = setup().await;
+
+    assert_eq!(
+        liquidity_info
+            .update_category(&mut context, CATEGORY_ID, None, &market_info)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+}
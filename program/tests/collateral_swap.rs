@@ -0,0 +1,323 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    find_obligation_authority, id, obligation_seed,
+    state::{CollateralStatus, LiquidityStatus, PairConfig, RATIO_POWER},
+};
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const BORROW_AMOUNT: u64 = 4000;
+const POOL_AMOUNT: u64 = 20000;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    new_collateral_info: CollateralInfo,
+    new_pair_info: PairInfo,
+    obligation_info: ObligationInfo,
+    swap_pool_info: SwapPoolInfo,
+    borrower_collateral: Keypair,
+    borrower_new_collateral: Keypair,
+}
+
+async fn setup(new_pair_config: PairConfig) -> Env {
+    let mut program_test = program_test();
+    swap::add_program(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    let new_collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    for collateral_info in [&collateral_info, &new_collateral_info].iter() {
+        collateral_info
+            .update(
+                &mut context,
+                CollateralStatus::Active,
+                collateral::RATIO_INITIAL,
+                collateral::RATIO_HEALTHY,
+                &market_info,
+            )
+            .await
+            .unwrap();
+    }
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let new_pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &new_collateral_info,
+            new_pair_config,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &source,
+            &destination,
+            999999,
+        )
+        .await;
+
+    // Swap pool paying out new collateral for the old one
+    let swap_pool_info = SwapPoolInfo::new();
+    swap_pool_info
+        .create(
+            &mut context,
+            &market_info,
+            &collateral_info.token_mint.pubkey(),
+            &new_collateral_info.token_mint.pubkey(),
+            POOL_AMOUNT,
+        )
+        .await
+        .unwrap();
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_new_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+
+    create_token_account(
+        &mut context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &borrower_new_collateral,
+        &new_collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            BORROW_AMOUNT,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        new_collateral_info,
+        new_pair_info,
+        obligation_info,
+        swap_pool_info,
+        borrower_collateral,
+        borrower_new_collateral,
+    }
+}
+
+impl Env {
+    async fn swap_collateral(&mut self, minimum_amount_out: u64) -> transport::Result<()> {
+        self.obligation_info
+            .swap_collateral(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.new_collateral_info,
+                &self.new_pair_info,
+                &self.swap_pool_info,
+                minimum_amount_out,
+                &self.borrower_collateral.pubkey(),
+                &self.borrower_new_collateral.pubkey(),
+            )
+            .await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup(pair::CONFIG).await;
+
+    env.swap_collateral(DEPOSIT_AMOUNT).await.unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(
+        obligation.collateral,
+        env.new_collateral_info.collateral_pubkey
+    );
+    assert_eq!(obligation.amount_collateral_deposited, DEPOSIT_AMOUNT);
+    assert_eq!(obligation.amount_liquidity_borrowed, BORROW_AMOUNT);
+
+    assert_eq!(
+        get_token_balance(
+            &mut env.context,
+            &env.collateral_info.token_account.pubkey()
+        )
+        .await,
+        0
+    );
+    assert_eq!(
+        get_token_balance(
+            &mut env.context,
+            &env.new_collateral_info.token_account.pubkey()
+        )
+        .await,
+        DEPOSIT_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn success_address_derived_after_swap() {
+    let mut env = setup(pair::CONFIG).await;
+
+    env.swap_collateral(DEPOSIT_AMOUNT).await.unwrap();
+
+    // Obligation address is derived from its current state after the collateral changed
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    let (obligation_authority, _) =
+        find_obligation_authority(&id(), &obligation.owner, &obligation.market);
+    assert_eq!(
+        Pubkey::create_with_seed(
+            &obligation_authority,
+            &obligation_seed(obligation.index),
+            &id()
+        )
+        .unwrap(),
+        env.obligation_info.obligation_pubkey
+    );
+}
+
+#[tokio::test]
+async fn fail_slippage_exceeded() {
+    let mut env = setup(pair::CONFIG).await;
+
+    assert_eq!(
+        env.swap_collateral(DEPOSIT_AMOUNT + 1)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::SlippageExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_new_collateral_health_check() {
+    // New collateral backs less debt than the obligation has
+    let mut env = setup(PairConfig {
+        borrow_enabled: true,
+        ratio_initial: 30 * RATIO_POWER / 100,
+        ratio_healthy: 40 * RATIO_POWER / 100,
+    })
+    .await;
+
+    assert_eq!(
+        env.swap_collateral(DEPOSIT_AMOUNT)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::CollateralHealthCheckFailed as u32)
+        )
+    );
+}
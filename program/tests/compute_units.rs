@@ -0,0 +1,830 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    id, instruction,
+    state::{CollateralStatus, LiquidityStatus, PairConfig, RATIO_POWER},
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::Instruction, signature::Keypair, signer::Signer, transaction::Transaction,
+    transport,
+};
+use utils::*;
+
+// Program test doesn't report the units consumed, so each flow runs under the budget
+// of its heaviest instruction and fails as soon as one of them goes over it.
+
+/// Compute units of deposits, withdrawals, borrows, repays and the health refresh
+const COMPUTE_MAX_UNITS: u64 = 40_000;
+
+/// Compute units of the liquidation, bonus calculation included
+const LIQUIDATION_MAX_UNITS: u64 = 50_000;
+
+/// Compute units of the collateral swap, repay with collateral and debt refinance
+const SWAP_MAX_UNITS: u64 = 80_000;
+
+/// Compute units of the native instructions wrapping and unwrapping SOL
+const NATIVE_MAX_UNITS: u64 = 60_000;
+
+const AMOUNT: u64 = 10000;
+
+const UNHEALTHY_CONFIG: PairConfig = PairConfig {
+    borrow_enabled: true,
+    ratio_initial: 30 * RATIO_POWER / 100,
+    ratio_healthy: 40 * RATIO_POWER / 100,
+};
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    pair_info: PairInfo,
+    provider_actor: ProviderActor,
+    provider_liquidity: Keypair,
+    provider_pool: Keypair,
+}
+
+async fn setup(max_units: u64) -> Env {
+    let mut program_test = program_test();
+    swap::add_program(&mut program_test);
+    program_test.set_bpf_compute_max_units(max_units);
+    let mut context = program_test.start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let provider_actor = ProviderActor::new();
+    let (provider_liquidity, provider_pool) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &liquidity_info.token_mint.pubkey(),
+        &provider_liquidity.pubkey(),
+        &market_info.owner,
+        AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        provider_actor,
+        provider_liquidity,
+        provider_pool,
+    }
+}
+
+impl Env {
+    async fn deposit(&mut self) {
+        self.liquidity_info
+            .deposit(
+                &mut self.context,
+                &self.market_info,
+                &self.provider_liquidity.pubkey(),
+                &self.provider_pool.pubkey(),
+                AMOUNT,
+                &self.provider_actor.owner,
+            )
+            .await
+            .unwrap();
+    }
+
+    /// Obligation with the collateral deposited and the borrower liquidity account
+    async fn prepare_borrower(&mut self) -> (ObligationInfo, Keypair, Keypair) {
+        let obligation_info = ObligationInfo::new(&self.market_info);
+        obligation_info
+            .create(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+            )
+            .await
+            .unwrap();
+
+        let borrower_collateral = Keypair::new();
+        let borrower_liquidity = Keypair::new();
+
+        create_token_account(
+            &mut self.context,
+            &borrower_collateral,
+            &self.collateral_info.token_mint.pubkey(),
+            &obligation_info.owner.pubkey(),
+        )
+        .await
+        .unwrap();
+
+        create_token_account(
+            &mut self.context,
+            &borrower_liquidity,
+            &self.liquidity_info.token_mint.pubkey(),
+            &obligation_info.owner.pubkey(),
+        )
+        .await
+        .unwrap();
+
+        mint_tokens(
+            &mut self.context,
+            &self.collateral_info.token_mint.pubkey(),
+            &borrower_collateral.pubkey(),
+            &self.market_info.owner,
+            AMOUNT,
+        )
+        .await
+        .unwrap();
+
+        obligation_info
+            .collateral_deposit(
+                &mut self.context,
+                &self.market_info,
+                &self.collateral_info,
+                AMOUNT,
+                &borrower_collateral.pubkey(),
+            )
+            .await
+            .unwrap();
+
+        (obligation_info, borrower_collateral, borrower_liquidity)
+    }
+
+    /// Borrower with the whole borrow limit taken out
+    async fn prepare_debt(&mut self) -> (ObligationInfo, Keypair, Keypair) {
+        self.deposit().await;
+        let (obligation_info, borrower_collateral, borrower_liquidity) =
+            self.prepare_borrower().await;
+
+        obligation_info
+            .liquidity_borrow(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+                u64::MAX,
+                &borrower_liquidity.pubkey(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        (obligation_info, borrower_collateral, borrower_liquidity)
+    }
+
+    /// Pool paying out the destination tokens for the source ones
+    async fn create_swap_pool(
+        &mut self,
+        source_mint: &Pubkey,
+        destination_mint: &Pubkey,
+    ) -> SwapPoolInfo {
+        let swap_pool_info = SwapPoolInfo::new();
+        swap_pool_info
+            .create(
+                &mut self.context,
+                &self.market_info,
+                source_mint,
+                destination_mint,
+                AMOUNT * 2,
+            )
+            .await
+            .unwrap();
+
+        swap_pool_info
+    }
+}
+
+#[tokio::test]
+async fn liquidity_deposit() {
+    let mut env = setup(COMPUTE_MAX_UNITS).await;
+
+    env.deposit().await;
+}
+
+#[tokio::test]
+async fn liquidity_withdraw() {
+    let mut env = setup(COMPUTE_MAX_UNITS).await;
+    env.deposit().await;
+
+    env.liquidity_info
+        .withdraw(
+            &mut env.context,
+            &env.market_info,
+            &env.provider_pool.pubkey(),
+            &env.provider_liquidity.pubkey(),
+            AMOUNT,
+            &env.provider_actor.owner,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn obligation_collateral_deposit() {
+    let mut env = setup(COMPUTE_MAX_UNITS).await;
+
+    env.prepare_borrower().await;
+}
+
+#[tokio::test]
+async fn obligation_collateral_withdraw() {
+    let mut env = setup(COMPUTE_MAX_UNITS).await;
+    let (obligation_info, borrower_collateral, _) = env.prepare_borrower().await;
+
+    obligation_info
+        .collateral_withdraw(
+            &mut env.context,
+            &env.market_info,
+            &env.collateral_info,
+            &env.pair_info,
+            AMOUNT,
+            &borrower_collateral.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn obligation_liquidity_borrow_and_repay() {
+    let mut env = setup(COMPUTE_MAX_UNITS).await;
+    env.deposit().await;
+    let (obligation_info, _, borrower_liquidity) = env.prepare_borrower().await;
+
+    obligation_info
+        .liquidity_borrow(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &env.collateral_info,
+            &env.pair_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_repay(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &env.collateral_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn refresh_obligation_health() {
+    let mut env = setup(COMPUTE_MAX_UNITS).await;
+    let (obligation_info, _, _) = env.prepare_debt().await;
+
+    env.pair_info
+        .update(&mut env.context, UNHEALTHY_CONFIG, &env.market_info)
+        .await
+        .unwrap();
+
+    obligation_info
+        .refresh_health(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &env.collateral_info,
+            &env.pair_info,
+            None,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn liquidate_obligation() {
+    let mut env = setup(LIQUIDATION_MAX_UNITS).await;
+    let (obligation_info, _, _) = env.prepare_debt().await;
+
+    env.pair_info
+        .update(&mut env.context, UNHEALTHY_CONFIG, &env.market_info)
+        .await
+        .unwrap();
+
+    obligation_info
+        .refresh_health(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &env.collateral_info,
+            &env.pair_info,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let liquidator = Keypair::new();
+    let liquidator_liquidity = Keypair::new();
+    let liquidator_collateral = Keypair::new();
+
+    create_token_account(
+        &mut env.context,
+        &liquidator_liquidity,
+        &env.liquidity_info.token_mint.pubkey(),
+        &liquidator.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut env.context,
+        &liquidator_collateral,
+        &env.collateral_info.token_mint.pubkey(),
+        &liquidator.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut env.context,
+        &env.liquidity_info.token_mint.pubkey(),
+        &liquidator_liquidity.pubkey(),
+        &env.market_info.owner,
+        AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .liquidate(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &env.collateral_info,
+            &env.pair_info,
+            AMOUNT / 10,
+            &liquidator_liquidity.pubkey(),
+            &liquidator_collateral.pubkey(),
+            &liquidator,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn repay_with_collateral() {
+    let mut env = setup(SWAP_MAX_UNITS).await;
+    let (obligation_info, borrower_collateral, borrower_liquidity) = env.prepare_debt().await;
+
+    let swap_pool_info = env
+        .create_swap_pool(
+            &env.collateral_info.token_mint.pubkey(),
+            &env.liquidity_info.token_mint.pubkey(),
+        )
+        .await;
+
+    obligation_info
+        .repay_with_collateral(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &env.collateral_info,
+            &env.pair_info,
+            &swap_pool_info,
+            AMOUNT / 10,
+            AMOUNT / 10,
+            &borrower_collateral.pubkey(),
+            &borrower_liquidity.pubkey(),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn swap_obligation_collateral() {
+    let mut env = setup(SWAP_MAX_UNITS).await;
+    let (obligation_info, borrower_collateral, _) = env.prepare_debt().await;
+
+    let new_collateral_info = env
+        .market_info
+        .create_collateral_token(&mut env.context)
+        .await
+        .unwrap();
+
+    new_collateral_info
+        .update(
+            &mut env.context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+
+    let new_pair_info = env
+        .market_info
+        .create_pair(
+            &mut env.context,
+            &env.liquidity_info,
+            &new_collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let swap_pool_info = env
+        .create_swap_pool(
+            &env.collateral_info.token_mint.pubkey(),
+            &new_collateral_info.token_mint.pubkey(),
+        )
+        .await;
+
+    let borrower_new_collateral = Keypair::new();
+    create_token_account(
+        &mut env.context,
+        &borrower_new_collateral,
+        &new_collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .swap_collateral(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &env.collateral_info,
+            &new_collateral_info,
+            &new_pair_info,
+            &swap_pool_info,
+            AMOUNT,
+            &borrower_collateral.pubkey(),
+            &borrower_new_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn refinance_obligation_debt() {
+    let mut env = setup(SWAP_MAX_UNITS).await;
+    let (obligation_info, _, borrower_liquidity) = env.prepare_debt().await;
+    let debt = obligation_info
+        .get_data(&mut env.context)
+        .await
+        .amount_liquidity_borrowed;
+
+    let new_liquidity_info = env
+        .market_info
+        .create_liquidity_token(&mut env.context)
+        .await
+        .unwrap();
+
+    new_liquidity_info
+        .update(&mut env.context, LiquidityStatus::Active, &env.market_info)
+        .await
+        .unwrap();
+
+    let (source, destination) = env
+        .provider_actor
+        .create_liquidity_accounts(&mut env.context, &new_liquidity_info)
+        .await
+        .unwrap();
+    env.provider_actor
+        .deposit(
+            &mut env.context,
+            &env.market_info,
+            &new_liquidity_info,
+            &source,
+            &destination,
+            AMOUNT,
+        )
+        .await;
+
+    let new_pair_info = env
+        .market_info
+        .create_pair(
+            &mut env.context,
+            &new_liquidity_info,
+            &env.collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let swap_pool_info = env
+        .create_swap_pool(
+            &new_liquidity_info.token_mint.pubkey(),
+            &env.liquidity_info.token_mint.pubkey(),
+        )
+        .await;
+
+    let borrower_new_liquidity = Keypair::new();
+    create_token_account(
+        &mut env.context,
+        &borrower_new_liquidity,
+        &new_liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .refinance_debt(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &new_liquidity_info,
+            &env.collateral_info,
+            &new_pair_info,
+            &swap_pool_info,
+            debt,
+            &borrower_new_liquidity.pubkey(),
+            &borrower_liquidity.pubkey(),
+        )
+        .await
+        .unwrap();
+}
+
+struct NativeEnv {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    pair_info: PairInfo,
+    obligation_info: ObligationInfo,
+}
+
+/// Wrapped SOL liquidity and collateral with the obligation created
+async fn setup_native() -> NativeEnv {
+    let mut program_test = program_test();
+    program_test.add_account(spl_token::native_mint::id(), native_mint_account());
+    program_test.set_bpf_compute_max_units(NATIVE_MAX_UNITS);
+    let mut context = program_test.start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = LiquidityInfo::new("liquidity0", &market_info);
+    liquidity_info
+        .create_with_mint(&mut context, &market_info, &spl_token::native_mint::id())
+        .await
+        .unwrap();
+
+    let collateral_info = CollateralInfo::new("collateral0", &market_info);
+    collateral_info
+        .create_with_mint(&mut context, &market_info, &spl_token::native_mint::id())
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    NativeEnv {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        obligation_info,
+    }
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signer: &Keypair,
+) -> transport::Result<()> {
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+impl NativeEnv {
+    /// Provider with the wrapped SOL liquidity deposited and its pool account
+    async fn deposit(&mut self) -> (Keypair, Keypair) {
+        let provider = Keypair::new();
+        let destination = Keypair::new();
+
+        fund(&mut self.context, &provider.pubkey(), AMOUNT * 2).await;
+        create_token_account(
+            &mut self.context,
+            &destination,
+            &self.liquidity_info.pool_mint.pubkey(),
+            &provider.pubkey(),
+        )
+        .await
+        .unwrap();
+
+        let deposit = instruction::liquidity_deposit_native(
+            &id(),
+            AMOUNT,
+            &self.liquidity_info.liquidity_pubkey,
+            &provider.pubkey(),
+            &destination.pubkey(),
+            &self.liquidity_info.token_account.pubkey(),
+            &self.liquidity_info.pool_mint.pubkey(),
+            &self.market_info.market.pubkey(),
+        )
+        .unwrap();
+        process(&mut self.context, deposit, &provider)
+            .await
+            .unwrap();
+
+        (provider, destination)
+    }
+
+    async fn collateral_deposit(&mut self) {
+        let deposit = instruction::obligation_collateral_deposit_native(
+            &id(),
+            AMOUNT,
+            &self.obligation_info.obligation_pubkey,
+            &self.collateral_info.collateral_pubkey,
+            &self.obligation_info.owner.pubkey(),
+            &self.collateral_info.token_account.pubkey(),
+            &self.market_info.market.pubkey(),
+        )
+        .unwrap();
+        process(&mut self.context, deposit, &self.obligation_info.owner)
+            .await
+            .unwrap();
+    }
+
+    async fn borrow(&mut self) {
+        let borrow = instruction::obligation_liquidity_borrow_native(
+            &id(),
+            u64::MAX,
+            &self.obligation_info.obligation_pubkey,
+            &self.liquidity_info.liquidity_pubkey,
+            &self.collateral_info.collateral_pubkey,
+            &self.pair_info.pair_pubkey,
+            &self.obligation_info.owner.pubkey(),
+            &self.liquidity_info.token_account.pubkey(),
+            &self.market_info.market.pubkey(),
+            None,
+            None,
+        )
+        .unwrap();
+        process(&mut self.context, borrow, &self.obligation_info.owner)
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn liquidity_deposit_and_withdraw_native() {
+    let mut env = setup_native().await;
+    let (provider, destination) = env.deposit().await;
+
+    let withdraw = instruction::liquidity_withdraw_native(
+        &id(),
+        u64::MAX,
+        &env.liquidity_info.liquidity_pubkey,
+        &destination.pubkey(),
+        &provider.pubkey(),
+        &env.liquidity_info.token_account.pubkey(),
+        &env.liquidity_info.pool_mint.pubkey(),
+        &env.market_info.market.pubkey(),
+    )
+    .unwrap();
+    process(&mut env.context, withdraw, &provider)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn obligation_collateral_deposit_and_withdraw_native() {
+    let mut env = setup_native().await;
+    env.collateral_deposit().await;
+
+    let withdraw = instruction::obligation_collateral_withdraw_native(
+        &id(),
+        u64::MAX,
+        &env.obligation_info.obligation_pubkey,
+        &env.liquidity_info.liquidity_pubkey,
+        &env.collateral_info.collateral_pubkey,
+        &env.pair_info.pair_pubkey,
+        &env.obligation_info.owner.pubkey(),
+        &env.collateral_info.token_account.pubkey(),
+        &env.market_info.market.pubkey(),
+        &env.liquidity_info.token_account.pubkey(),
+        None,
+        None,
+    )
+    .unwrap();
+    process(&mut env.context, withdraw, &env.obligation_info.owner)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn obligation_liquidity_borrow_and_repay_native() {
+    let mut env = setup_native().await;
+    env.deposit().await;
+    env.collateral_deposit().await;
+    env.borrow().await;
+
+    let repay = instruction::obligation_liquidity_repay_native(
+        &id(),
+        u64::MAX,
+        &env.obligation_info.obligation_pubkey,
+        &env.liquidity_info.liquidity_pubkey,
+        &env.collateral_info.collateral_pubkey,
+        &env.obligation_info.owner.pubkey(),
+        &env.liquidity_info.token_account.pubkey(),
+        &env.market_info.market.pubkey(),
+        None,
+    )
+    .unwrap();
+    process(&mut env.context, repay, &env.obligation_info.owner)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,283 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus, RATIO_POWER},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const ALLOWANCE: u64 = 2000;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    pair_info: PairInfo,
+    obligation_info: ObligationInfo,
+    delegation_info: DelegationInfo,
+    delegate_liquidity: Keypair,
+}
+
+async fn setup() -> Env {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &source,
+            &destination,
+            999999,
+        )
+        .await;
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    create_token_account(
+        &mut context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    let delegation_info = DelegationInfo::new(&obligation_info);
+    delegation_info
+        .create(&mut context, &obligation_info, ALLOWANCE)
+        .await
+        .unwrap();
+
+    // Delegate borrows to the own account
+    let delegate_liquidity = Keypair::new();
+    create_token_account(
+        &mut context,
+        &delegate_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &delegation_info.delegate.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        obligation_info,
+        delegation_info,
+        delegate_liquidity,
+    }
+}
+
+impl Env {
+    async fn borrow(&mut self, amount: u64, delegate: &Keypair) -> transport::Result<()> {
+        self.delegation_info
+            .borrow(
+                &mut self.context,
+                &self.market_info,
+                &self.obligation_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+                amount,
+                &self.delegate_liquidity.pubkey(),
+                delegate,
+            )
+            .await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+    let delegate = Keypair::from_bytes(&env.delegation_info.delegate.to_bytes()).unwrap();
+
+    env.borrow(500, &delegate).await.unwrap();
+
+    let delegation = env.delegation_info.get_data(&mut env.context).await;
+    assert_eq!(delegation.allowance, ALLOWANCE - 500);
+
+    // Borrow the rest of the allowance
+    env.borrow(u64::MAX, &delegate).await.unwrap();
+
+    let delegation = env.delegation_info.get_data(&mut env.context).await;
+    assert_eq!(delegation.allowance, 0);
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, ALLOWANCE);
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.delegate_liquidity.pubkey()).await,
+        ALLOWANCE
+    );
+}
+
+#[tokio::test]
+async fn success_allowance_beyond_borrowing_limit() {
+    let mut env = setup().await;
+    let delegate = Keypair::from_bytes(&env.delegation_info.delegate.to_bytes()).unwrap();
+
+    env.delegation_info
+        .update(&mut env.context, &env.obligation_info, DEPOSIT_AMOUNT)
+        .await
+        .unwrap();
+
+    // Borrow up to the borrowing limit of the obligation
+    env.borrow(u64::MAX, &delegate).await.unwrap();
+
+    let borrow_amount = DEPOSIT_AMOUNT * collateral::RATIO_INITIAL / RATIO_POWER;
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, borrow_amount);
+
+    let delegation = env.delegation_info.get_data(&mut env.context).await;
+    assert_eq!(delegation.allowance, DEPOSIT_AMOUNT - borrow_amount);
+}
+
+#[tokio::test]
+async fn fail_allowance_exceeded() {
+    let mut env = setup().await;
+    let delegate = Keypair::from_bytes(&env.delegation_info.delegate.to_bytes()).unwrap();
+
+    assert_eq!(
+        env.borrow(ALLOWANCE + 1, &delegate)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::DelegationAllowanceExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_revoked() {
+    let mut env = setup().await;
+    let delegate = Keypair::from_bytes(&env.delegation_info.delegate.to_bytes()).unwrap();
+
+    env.delegation_info
+        .update(&mut env.context, &env.obligation_info, 0)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.borrow(1, &delegate).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::DelegationAllowanceExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_wrong_delegate() {
+    let mut env = setup().await;
+
+    assert_eq!(
+        env.borrow(500, &Keypair::new()).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn fail_after_obligation_transfer() {
+    let mut env = setup().await;
+    let delegate = Keypair::from_bytes(&env.delegation_info.delegate.to_bytes()).unwrap();
+
+    // Delegation granted by the previous owner lapses
+    env.obligation_info
+        .transfer(&mut env.context, &Keypair::new().pubkey())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.borrow(500, &delegate).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{Liquidity, INSURANCE_WITHDRAW_TIMELOCK, RATIO_POWER},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+use utils::*;
+
+const RESERVE_FACTOR: u64 = RATIO_POWER / 10;
+
+async fn setup() -> (ProgramTestContext, MarketInfo, LiquidityInfo, Keypair) {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .create_insurance(&mut context, &market_info, RESERVE_FACTOR)
+        .await
+        .unwrap();
+
+    // Owner account for the insurance top up
+    let owner_account = Keypair::new();
+    create_token_account(
+        &mut context,
+        &owner_account,
+        &liquidity_info.token_mint.pubkey(),
+        &market_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &liquidity_info.token_mint.pubkey(),
+        &owner_account.pubkey(),
+        &market_info.owner,
+        10000,
+    )
+    .await
+    .unwrap();
+
+    (context, market_info, liquidity_info, owner_account)
+}
+
+#[tokio::test]
+async fn success() {
+    let (mut context, _market_info, liquidity_info, _) = setup().await;
+
+    let liquidity = liquidity_info.get_data(&mut context).await;
+
+    assert_eq!(
+        liquidity.insurance_account,
+        liquidity_info.insurance_account.pubkey()
+    );
+    assert_eq!(liquidity.reserve_factor, RESERVE_FACTOR);
+}
+
+#[tokio::test]
+async fn top_up() {
+    let (mut context, market_info, liquidity_info, owner_account) = setup().await;
+
+    liquidity_info
+        .insurance_top_up(&mut context, &market_info, &owner_account.pubkey(), 10000)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut context, &liquidity_info.insurance_account.pubkey()).await,
+        10000
+    );
+}
+
+#[test]
+fn success_withdraw_after_timelock() {
+    // Bank clock of the test validator doesn't move on the slot warp, so the timelock
+    // is checked against the liquidity state
+    let mut liquidity = Liquidity::default();
+    let now = 1_600_000_000;
+
+    liquidity.request_insurance_withdraw(10000, now).unwrap();
+    assert_eq!(
+        liquidity
+            .take_insurance_withdraw(now + INSURANCE_WITHDRAW_TIMELOCK - 1)
+            .unwrap_err(),
+        LendingError::InsuranceWithdrawLocked.into()
+    );
+
+    assert_eq!(
+        liquidity
+            .take_insurance_withdraw(now + INSURANCE_WITHDRAW_TIMELOCK)
+            .unwrap(),
+        10000
+    );
+    assert_eq!(liquidity.insurance_withdraw_amount, 0);
+    assert_eq!(liquidity.insurance_withdraw_unlock_at, 0);
+
+    // Withdrawal is taken only once
+    assert_eq!(
+        liquidity
+            .take_insurance_withdraw(now + INSURANCE_WITHDRAW_TIMELOCK)
+            .unwrap_err(),
+        LendingError::InsuranceWithdrawNotRequested.into()
+    );
+}
+
+#[tokio::test]
+async fn success_withdraw_request_capped_at_balance() {
+    let (mut context, market_info, liquidity_info, owner_account) = setup().await;
+
+    liquidity_info
+        .insurance_top_up(&mut context, &market_info, &owner_account.pubkey(), 10000)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .insurance_withdraw_request(&mut context, &market_info, 20000)
+        .await
+        .unwrap();
+
+    let liquidity = liquidity_info.get_data(&mut context).await;
+    assert_eq!(liquidity.insurance_withdraw_amount, 10000);
+}
+
+#[tokio::test]
+async fn fail_withdraw_locked() {
+    let (mut context, market_info, liquidity_info, owner_account) = setup().await;
+
+    liquidity_info
+        .insurance_top_up(&mut context, &market_info, &owner_account.pubkey(), 10000)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .insurance_withdraw_request(&mut context, &market_info, 10000)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        liquidity_info
+            .insurance_withdraw(&mut context, &market_info, &owner_account.pubkey())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InsuranceWithdrawLocked as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_withdraw_not_requested() {
+    let (mut context, market_info, liquidity_info, owner_account) = setup().await;
+
+    assert_eq!(
+        liquidity_info
+            .insurance_withdraw(&mut context, &market_info, &owner_account.pubkey())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InsuranceWithdrawNotRequested as u32)
+        )
+    );
+}
@@ -0,0 +1,368 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const DEBT_CEILING: u64 = 1000;
+
+async fn setup() -> (
+    ProgramTestContext,
+    MarketInfo,
+    LiquidityInfo,
+    CollateralInfo,
+    PairInfo,
+) {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &source,
+            &destination,
+            999999,
+        )
+        .await;
+
+    (
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+    )
+}
+
+/// Obligation with the collateral deposited and the borrower liquidity account
+async fn prepare_borrower(
+    context: &mut ProgramTestContext,
+    market_info: &MarketInfo,
+    liquidity_info: &LiquidityInfo,
+    collateral_info: &CollateralInfo,
+    pair_info: &PairInfo,
+) -> (ObligationInfo, Keypair) {
+    let obligation_info = ObligationInfo::new(market_info);
+    obligation_info
+        .create(
+            context,
+            market_info,
+            liquidity_info,
+            collateral_info,
+            pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+
+    create_token_account(
+        context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            context,
+            market_info,
+            collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    (obligation_info, borrower_liquidity)
+}
+
+#[tokio::test]
+async fn success() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    collateral_info
+        .update_isolation(
+            &mut context,
+            true,
+            DEBT_CEILING,
+            &[liquidity_info.liquidity_pubkey],
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let (obligation_info, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+    )
+    .await;
+    assert!(obligation_info.get_data(&mut context).await.isolated);
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            DEBT_CEILING,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        collateral_info.get_data(&mut context).await.isolated_debt,
+        DEBT_CEILING
+    );
+
+    obligation_info
+        .liquidity_repay(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        collateral_info.get_data(&mut context).await.isolated_debt,
+        0
+    );
+}
+
+#[tokio::test]
+async fn fail_create_obligation_not_approved() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    collateral_info
+        .update_isolation(&mut context, true, DEBT_CEILING, &[], &market_info)
+        .await
+        .unwrap();
+
+    let obligation_info = ObligationInfo::new(&market_info);
+
+    assert_eq!(
+        obligation_info
+            .create(
+                &mut context,
+                &market_info,
+                &liquidity_info,
+                &collateral_info,
+                &pair_info,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::IsolatedLiquidityNotApproved as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_borrow_debt_ceiling_exceeded() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    collateral_info
+        .update_isolation(
+            &mut context,
+            true,
+            DEBT_CEILING,
+            &[liquidity_info.liquidity_pubkey],
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let (obligation_info, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+    )
+    .await;
+
+    assert_eq!(
+        obligation_info
+            .liquidity_borrow(
+                &mut context,
+                &market_info,
+                &liquidity_info,
+                &collateral_info,
+                &pair_info,
+                DEBT_CEILING + 1,
+                &borrower_liquidity.pubkey(),
+                None,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::DebtCeilingExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn borrow_after_isolation_lifted() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    collateral_info
+        .update_isolation(
+            &mut context,
+            true,
+            DEBT_CEILING,
+            &[liquidity_info.liquidity_pubkey],
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let (obligation_info, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+    )
+    .await;
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            DEBT_CEILING,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    // Lifting the isolation clears the approved liquidity
+    collateral_info
+        .update_isolation(&mut context, false, 0, &[], &market_info)
+        .await
+        .unwrap();
+
+    // Existing isolated obligation isn't limited by the approved liquidity and the debt ceiling
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            DEBT_CEILING,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_repay(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    let obligation = obligation_info.get_data(&mut context).await;
+    assert!(obligation.isolated);
+    assert_eq!(obligation.amount_liquidity_borrowed, 0);
+}
@@ -0,0 +1,669 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus, PairConfig, RATIO_POWER, SLOTS_PER_YEAR},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const BORROW_AMOUNT: u64 = 5000;
+const BONUS_START: u64 = 2 * RATIO_POWER / 100;
+const BONUS_MAX: u64 = 10 * RATIO_POWER / 100;
+const BONUS_SLOTS: u64 = 100;
+const INSURANCE_AMOUNT: u64 = 2000;
+// Debt triples within a tenth of the year
+const BORROW_RATE: u64 = 20 * RATIO_POWER;
+
+// Healthy ratio below the obligation health of 0.5
+const UNHEALTHY_CONFIG: PairConfig = PairConfig {
+    borrow_enabled: true,
+    ratio_initial: 30 * RATIO_POWER / 100,
+    ratio_healthy: 40 * RATIO_POWER / 100,
+};
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    pair_info: PairInfo,
+    obligation_info: ObligationInfo,
+    borrower_collateral: Keypair,
+    borrower_liquidity: Keypair,
+    liquidator: Keypair,
+    liquidator_liquidity: Keypair,
+    liquidator_collateral: Keypair,
+}
+
+async fn setup() -> Env {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    collateral_info
+        .update_liquidation(
+            &mut context,
+            BONUS_START,
+            BONUS_MAX,
+            BONUS_SLOTS,
+            RATIO_POWER,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &source,
+            &destination,
+            999999,
+        )
+        .await;
+
+    // Borrow at the initial collateralization ratio
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+
+    create_token_account(
+        &mut context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            BORROW_AMOUNT,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    // Liquidator accounts
+    let liquidator = Keypair::new();
+    let liquidator_liquidity = Keypair::new();
+    let liquidator_collateral = Keypair::new();
+
+    create_token_account(
+        &mut context,
+        &liquidator_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &liquidator.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &liquidator_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &liquidator.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &liquidity_info.token_mint.pubkey(),
+        &liquidator_liquidity.pubkey(),
+        &market_info.owner,
+        BORROW_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        obligation_info,
+        borrower_collateral,
+        borrower_liquidity,
+        liquidator,
+        liquidator_liquidity,
+        liquidator_collateral,
+    }
+}
+
+impl Env {
+    async fn make_unhealthy(&mut self) {
+        self.pair_info
+            .update(&mut self.context, UNHEALTHY_CONFIG, &self.market_info)
+            .await
+            .unwrap();
+    }
+
+    async fn refresh_health(&mut self) -> transport::Result<()> {
+        self.obligation_info
+            .refresh_health(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+                None,
+            )
+            .await
+    }
+
+    async fn repay(&mut self, amount: u64) -> transport::Result<()> {
+        self.obligation_info
+            .liquidity_repay(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                amount,
+                &self.borrower_liquidity.pubkey(),
+            )
+            .await
+    }
+
+    async fn unhealthy_since(&mut self) -> u64 {
+        self.obligation_info
+            .get_data(&mut self.context)
+            .await
+            .unhealthy_since
+    }
+
+    async fn liquidate(&mut self, amount: u64) -> transport::Result<()> {
+        self.obligation_info
+            .liquidate(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+                amount,
+                &self.liquidator_liquidity.pubkey(),
+                &self.liquidator_collateral.pubkey(),
+                &self.liquidator,
+            )
+            .await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+
+    env.make_unhealthy().await;
+    env.refresh_health().await.unwrap();
+
+    let unhealthy_since = env
+        .obligation_info
+        .get_data(&mut env.context)
+        .await
+        .unhealthy_since;
+    assert_ne!(unhealthy_since, 0);
+
+    // Bonus rises halfway to the maximum
+    env.context
+        .warp_to_slot(unhealthy_since + BONUS_SLOTS / 2)
+        .unwrap();
+    env.liquidate(1000).await.unwrap();
+
+    let collateral_amount =
+        get_token_balance(&mut env.context, &env.liquidator_collateral.pubkey()).await;
+    assert!(collateral_amount > 1020 && collateral_amount < 1100);
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, BORROW_AMOUNT - 1000);
+    assert_eq!(
+        obligation.amount_collateral_deposited,
+        DEPOSIT_AMOUNT - collateral_amount
+    );
+    assert_eq!(obligation.unhealthy_since, unhealthy_since);
+}
+
+#[tokio::test]
+async fn success_bonus_max() {
+    let mut env = setup().await;
+
+    env.make_unhealthy().await;
+    env.refresh_health().await.unwrap();
+
+    let unhealthy_since = env
+        .obligation_info
+        .get_data(&mut env.context)
+        .await
+        .unhealthy_since;
+
+    env.context
+        .warp_to_slot(unhealthy_since + BONUS_SLOTS * 2)
+        .unwrap();
+    env.liquidate(u64::MAX).await.unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.liquidator_collateral.pubkey()).await,
+        BORROW_AMOUNT + BORROW_AMOUNT * BONUS_MAX / RATIO_POWER
+    );
+
+    // Obligation is healthy again without the debt
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, 0);
+    assert_eq!(obligation.unhealthy_since, 0);
+}
+
+#[tokio::test]
+async fn success_close_factor() {
+    let mut env = setup().await;
+
+    env.collateral_info
+        .update_liquidation(
+            &mut env.context,
+            BONUS_START,
+            BONUS_MAX,
+            BONUS_SLOTS,
+            RATIO_POWER / 2,
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+
+    env.make_unhealthy().await;
+    env.refresh_health().await.unwrap();
+    let unhealthy_since = env.unhealthy_since().await;
+
+    // Half of the debt is repaid at most
+    env.liquidate(u64::MAX).await.unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, BORROW_AMOUNT / 2);
+    assert_eq!(obligation.unhealthy_since, unhealthy_since);
+}
+
+#[tokio::test]
+async fn success_partial_liquidation_keeps_recorded_ratio() {
+    let mut env = setup().await;
+
+    env.make_unhealthy().await;
+    env.refresh_health().await.unwrap();
+    let unhealthy_since = env.unhealthy_since().await;
+
+    // Healthy ratio raised over the one the obligation was recorded with
+    env.pair_info
+        .update(
+            &mut env.context,
+            PairConfig {
+                ratio_healthy: 45 * RATIO_POWER / 100,
+                ..UNHEALTHY_CONFIG
+            },
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+
+    env.context
+        .warp_to_slot(unhealthy_since + BONUS_SLOTS * 2)
+        .unwrap();
+
+    // Health 0.449 is within the new healthy ratio but beyond the recorded one
+    env.liquidate(1000).await.unwrap();
+    assert_eq!(env.unhealthy_since().await, unhealthy_since);
+}
+
+#[tokio::test]
+async fn fail_close_factor_zero() {
+    let mut env = setup().await;
+
+    assert_eq!(
+        env.collateral_info
+            .update_liquidation(
+                &mut env.context,
+                BONUS_START,
+                BONUS_MAX,
+                BONUS_SLOTS,
+                0,
+                &env.market_info,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn success_refresh_healthy() {
+    let mut env = setup().await;
+
+    env.refresh_health().await.unwrap();
+
+    assert_eq!(
+        env.obligation_info
+            .get_data(&mut env.context)
+            .await
+            .unhealthy_since,
+        0
+    );
+}
+
+#[tokio::test]
+async fn fail_liquidate_healthy() {
+    let mut env = setup().await;
+
+    env.refresh_health().await.unwrap();
+
+    assert_eq!(
+        env.liquidate(1000).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ObligationHealthy as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_liquidation_not_started() {
+    let mut env = setup().await;
+
+    env.make_unhealthy().await;
+
+    assert_eq!(
+        env.liquidate(1000).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::LiquidationNotStarted as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_repay_resets_unhealthy() {
+    let mut env = setup().await;
+
+    env.make_unhealthy().await;
+    env.refresh_health().await.unwrap();
+    let unhealthy_since = env.unhealthy_since().await;
+    assert_ne!(unhealthy_since, 0);
+
+    // Health 0.45 is still beyond the healthy ratio
+    env.repay(500).await.unwrap();
+    assert_eq!(env.unhealthy_since().await, unhealthy_since);
+
+    // Health 0.35 is within the healthy ratio
+    env.repay(1000).await.unwrap();
+    assert_eq!(env.unhealthy_since().await, 0);
+
+    // Unhealthy again, the auction doesn't resume from the previous slot
+    env.pair_info
+        .update(
+            &mut env.context,
+            PairConfig {
+                ratio_healthy: 30 * RATIO_POWER / 100,
+                ..UNHEALTHY_CONFIG
+            },
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.liquidate(1000).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::LiquidationNotStarted as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_deposit_resets_unhealthy() {
+    let mut env = setup().await;
+
+    env.make_unhealthy().await;
+    env.refresh_health().await.unwrap();
+    assert_ne!(env.unhealthy_since().await, 0);
+
+    mint_tokens(
+        &mut env.context,
+        &env.collateral_info.token_mint.pubkey(),
+        &env.borrower_collateral.pubkey(),
+        &env.market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    // Health 0.25 is within the healthy ratio
+    env.obligation_info
+        .collateral_deposit(
+            &mut env.context,
+            &env.market_info,
+            &env.collateral_info,
+            DEPOSIT_AMOUNT,
+            &env.borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(env.unhealthy_since().await, 0);
+}
+
+#[tokio::test]
+async fn success_write_off_bad_debt_covered_by_insurance() {
+    let mut env = setup().await;
+
+    env.liquidity_info
+        .create_insurance(&mut env.context, &env.market_info, RATIO_POWER / 10)
+        .await
+        .unwrap();
+
+    let owner_account = Keypair::new();
+    create_token_account(
+        &mut env.context,
+        &owner_account,
+        &env.liquidity_info.token_mint.pubkey(),
+        &env.market_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+    mint_tokens(
+        &mut env.context,
+        &env.liquidity_info.token_mint.pubkey(),
+        &owner_account.pubkey(),
+        &env.market_info.owner,
+        INSURANCE_AMOUNT,
+    )
+    .await
+    .unwrap();
+    env.liquidity_info
+        .insurance_top_up(
+            &mut env.context,
+            &env.market_info,
+            &owner_account.pubkey(),
+            INSURANCE_AMOUNT,
+        )
+        .await
+        .unwrap();
+
+    // Debt outgrows the collateral
+    env.liquidity_info
+        .update_borrow_rate(
+            &mut env.context,
+            RATIO_POWER,
+            BORROW_RATE,
+            BORROW_RATE,
+            BORROW_RATE,
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+    let interest_slot = env
+        .liquidity_info
+        .get_data(&mut env.context)
+        .await
+        .interest_slot;
+    env.context
+        .warp_to_slot(interest_slot + SLOTS_PER_YEAR / 10)
+        .unwrap();
+    env.repay(1).await.unwrap();
+    env.refresh_health().await.unwrap();
+
+    let unhealthy_since = env.unhealthy_since().await;
+    assert_ne!(unhealthy_since, 0);
+    env.context
+        .warp_to_slot(unhealthy_since + BONUS_SLOTS * 2)
+        .unwrap();
+
+    // Liquidator takes the whole collateral leaving the debt behind
+    mint_tokens(
+        &mut env.context,
+        &env.liquidity_info.token_mint.pubkey(),
+        &env.liquidator_liquidity.pubkey(),
+        &env.market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+    env.liquidate(DEPOSIT_AMOUNT).await.unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_collateral_deposited, 0);
+    assert!(obligation.amount_liquidity_borrowed > INSURANCE_AMOUNT);
+
+    let insurance_amount = get_token_balance(
+        &mut env.context,
+        &env.liquidity_info.insurance_account.pubkey(),
+    )
+    .await;
+    let token_account_amount =
+        get_token_balance(&mut env.context, &env.liquidity_info.token_account.pubkey()).await;
+
+    env.obligation_info
+        .write_off_bad_debt(
+            &mut env.context,
+            &env.market_info,
+            &env.liquidity_info,
+            &env.collateral_info,
+            Some(&env.liquidity_info.insurance_account.pubkey()),
+        )
+        .await
+        .unwrap();
+
+    // Insurance is drained into the liquidity and the providers bear the rest
+    assert_eq!(
+        get_token_balance(
+            &mut env.context,
+            &env.liquidity_info.insurance_account.pubkey()
+        )
+        .await,
+        0
+    );
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.liquidity_info.token_account.pubkey()).await,
+        token_account_amount + insurance_amount
+    );
+    assert_eq!(
+        env.obligation_info
+            .get_data(&mut env.context)
+            .await
+            .amount_liquidity_borrowed,
+        0
+    );
+}
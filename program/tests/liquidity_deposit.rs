@@ -2,9 +2,10 @@
 
 mod utils;
 
-use everlend_lending::state::LiquidityStatus;
+use everlend_lending::{error::LendingError, state::LiquidityStatus};
+use solana_program::instruction::InstructionError;
 use solana_program_test::*;
-use solana_sdk::signer::Signer;
+use solana_sdk::{signer::Signer, transaction::TransactionError};
 use utils::*;
 
 async fn setup() -> (ProgramTestContext, MarketInfo, LiquidityInfo) {
@@ -114,4 +115,45 @@ async fn two_deposits() {
     );
 }
 
+#[tokio::test]
+async fn fail_slippage_exceeded() {
+    let (mut context, market_info, liquidity_info) = setup().await;
+    let provider_actor = ProviderActor::new();
+
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &liquidity_info.token_mint.pubkey(),
+        &source.pubkey(),
+        &market_info.owner,
+        10000,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        liquidity_info
+            .deposit_checked(
+                &mut context,
+                &market_info,
+                &source.pubkey(),
+                &destination.pubkey(),
+                10000,
+                10001,
+                &provider_actor.owner,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::SlippageExceeded as u32)
+        )
+    );
+}
+
 // TODO: need to add more fail tests
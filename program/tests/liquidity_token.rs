@@ -2,8 +2,13 @@
 
 mod utils;
 
-use everlend_lending::state::LiquidityStatus;
+use everlend_lending::{id, instruction, state::LiquidityStatus};
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
 use solana_program_test::*;
+use solana_sdk::{
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
 use utils::*;
 
 async fn setup() -> (ProgramTestContext, MarketInfo) {
@@ -69,4 +74,45 @@ async fn update_token() {
     );
 }
 
+#[tokio::test]
+async fn fail_unsupported_token_program() {
+    let (mut context, market_info) = setup().await;
+
+    let liquidity_info = LiquidityInfo::new("liquidity0", &market_info);
+    create_mint(
+        &mut context,
+        &liquidity_info.token_mint,
+        &market_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::create_liquidity_token(
+            &id(),
+            &liquidity_info.liquidity_pubkey,
+            &liquidity_info.token_mint.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &liquidity_info.pool_mint.pubkey(),
+            &market_info.market.pubkey(),
+            &market_info.owner.pubkey(),
+            &Pubkey::new_unique(),
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &market_info.owner],
+        context.last_blockhash,
+    );
+
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+}
+
 // TODO: need to add more fail tests
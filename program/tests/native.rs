@@ -0,0 +1,533 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    find_native_account_address, id, instruction,
+    state::{CollateralStatus, LiquidityStatus, RATIO_POWER, SLOTS_PER_YEAR},
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    transport,
+};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 100_000_000;
+
+async fn setup() -> (
+    ProgramTestContext,
+    MarketInfo,
+    LiquidityInfo,
+    CollateralInfo,
+    PairInfo,
+) {
+    let mut program_test = program_test();
+    program_test.add_account(spl_token::native_mint::id(), native_mint_account());
+    let mut context = program_test.start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    // Liquidity and collateral of wrapped SOL
+    let liquidity_info = LiquidityInfo::new("liquidity0", &market_info);
+    liquidity_info
+        .create_with_mint(&mut context, &market_info, &spl_token::native_mint::id())
+        .await
+        .unwrap();
+
+    let collateral_info = CollateralInfo::new("collateral0", &market_info);
+    collateral_info
+        .create_with_mint(&mut context, &market_info, &spl_token::native_mint::id())
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    (
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+    )
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signer: &Keypair,
+) -> transport::Result<()> {
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+async fn get_balance(context: &mut ProgramTestContext, pubkey: &Pubkey) -> u64 {
+    context.banks_client.get_balance(*pubkey).await.unwrap()
+}
+
+/// Temporary native account is closed and its lamports are returned to the owner
+async fn assert_native_account_closed(
+    context: &mut ProgramTestContext,
+    owner: &Pubkey,
+    market_info: &MarketInfo,
+) {
+    let (native_account, _) =
+        find_native_account_address(&id(), owner, &market_info.market.pubkey());
+
+    assert!(context
+        .banks_client
+        .get_account(native_account)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+/// Deposit wrapped SOL liquidity from a new provider, returns the provider and pool account
+async fn deposit_liquidity(
+    context: &mut ProgramTestContext,
+    market_info: &MarketInfo,
+    liquidity_info: &LiquidityInfo,
+    amount: u64,
+) -> (Keypair, Keypair) {
+    let provider = Keypair::new();
+    let destination = Keypair::new();
+
+    fund(context, &provider.pubkey(), amount * 2).await;
+    create_token_account(
+        context,
+        &destination,
+        &liquidity_info.pool_mint.pubkey(),
+        &provider.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    process(
+        context,
+        instruction::liquidity_deposit_native(
+            &id(),
+            amount,
+            &liquidity_info.liquidity_pubkey,
+            &provider.pubkey(),
+            &destination.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &liquidity_info.pool_mint.pubkey(),
+            &market_info.market.pubkey(),
+        )
+        .unwrap(),
+        &provider,
+    )
+    .await
+    .unwrap();
+
+    (provider, destination)
+}
+
+async fn deposit_collateral(
+    context: &mut ProgramTestContext,
+    market_info: &MarketInfo,
+    collateral_info: &CollateralInfo,
+    obligation_info: &ObligationInfo,
+    amount: u64,
+) {
+    process(
+        context,
+        instruction::obligation_collateral_deposit_native(
+            &id(),
+            amount,
+            &obligation_info.obligation_pubkey,
+            &collateral_info.collateral_pubkey,
+            &obligation_info.owner.pubkey(),
+            &collateral_info.token_account.pubkey(),
+            &market_info.market.pubkey(),
+        )
+        .unwrap(),
+        &obligation_info.owner,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn liquidity_deposit_and_withdraw() {
+    let (mut context, market_info, liquidity_info, _, _) = setup().await;
+
+    let (provider, destination) =
+        deposit_liquidity(&mut context, &market_info, &liquidity_info, DEPOSIT_AMOUNT).await;
+
+    assert_eq!(
+        get_token_balance(&mut context, &destination.pubkey()).await,
+        DEPOSIT_AMOUNT
+    );
+    assert_eq!(
+        get_token_balance(&mut context, &liquidity_info.token_account.pubkey()).await,
+        DEPOSIT_AMOUNT
+    );
+    assert_eq!(
+        get_balance(&mut context, &provider.pubkey()).await,
+        DEPOSIT_AMOUNT
+    );
+    assert_native_account_closed(&mut context, &provider.pubkey(), &market_info).await;
+
+    // Withdraw the whole pool token balance
+    process(
+        &mut context,
+        instruction::liquidity_withdraw_native(
+            &id(),
+            u64::MAX,
+            &liquidity_info.liquidity_pubkey,
+            &destination.pubkey(),
+            &provider.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &liquidity_info.pool_mint.pubkey(),
+            &market_info.market.pubkey(),
+        )
+        .unwrap(),
+        &provider,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut context, &destination.pubkey()).await,
+        0
+    );
+    assert_eq!(
+        get_balance(&mut context, &provider.pubkey()).await,
+        DEPOSIT_AMOUNT * 2
+    );
+    assert_native_account_closed(&mut context, &provider.pubkey(), &market_info).await;
+}
+
+#[tokio::test]
+async fn collateral_deposit_and_withdraw() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    let owner_balance = get_balance(&mut context, &obligation_info.owner.pubkey()).await;
+
+    deposit_collateral(
+        &mut context,
+        &market_info,
+        &collateral_info,
+        &obligation_info,
+        DEPOSIT_AMOUNT,
+    )
+    .await;
+
+    assert_eq!(
+        obligation_info
+            .get_data(&mut context)
+            .await
+            .amount_collateral_deposited,
+        DEPOSIT_AMOUNT
+    );
+    assert_eq!(
+        get_token_balance(&mut context, &collateral_info.token_account.pubkey()).await,
+        DEPOSIT_AMOUNT
+    );
+    assert_eq!(
+        get_balance(&mut context, &obligation_info.owner.pubkey()).await,
+        owner_balance - DEPOSIT_AMOUNT
+    );
+    assert_native_account_closed(&mut context, &obligation_info.owner.pubkey(), &market_info).await;
+
+    // Withdraw all collateral
+    process(
+        &mut context,
+        instruction::obligation_collateral_withdraw_native(
+            &id(),
+            u64::MAX,
+            &obligation_info.obligation_pubkey,
+            &liquidity_info.liquidity_pubkey,
+            &collateral_info.collateral_pubkey,
+            &pair_info.pair_pubkey,
+            &obligation_info.owner.pubkey(),
+            &collateral_info.token_account.pubkey(),
+            &market_info.market.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            None,
+            None,
+        )
+        .unwrap(),
+        &obligation_info.owner,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        obligation_info
+            .get_data(&mut context)
+            .await
+            .amount_collateral_deposited,
+        0
+    );
+    assert_eq!(
+        get_balance(&mut context, &obligation_info.owner.pubkey()).await,
+        owner_balance
+    );
+    assert_native_account_closed(&mut context, &obligation_info.owner.pubkey(), &market_info).await;
+}
+
+#[tokio::test]
+async fn liquidity_borrow_and_repay() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    deposit_liquidity(&mut context, &market_info, &liquidity_info, DEPOSIT_AMOUNT).await;
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    deposit_collateral(
+        &mut context,
+        &market_info,
+        &collateral_info,
+        &obligation_info,
+        DEPOSIT_AMOUNT,
+    )
+    .await;
+
+    let owner_balance = get_balance(&mut context, &obligation_info.owner.pubkey()).await;
+
+    // Borrow up to the limit
+    process(
+        &mut context,
+        instruction::obligation_liquidity_borrow_native(
+            &id(),
+            u64::MAX,
+            &obligation_info.obligation_pubkey,
+            &liquidity_info.liquidity_pubkey,
+            &collateral_info.collateral_pubkey,
+            &pair_info.pair_pubkey,
+            &obligation_info.owner.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &market_info.market.pubkey(),
+            None,
+            None,
+        )
+        .unwrap(),
+        &obligation_info.owner,
+    )
+    .await
+    .unwrap();
+
+    let borrow_amount = DEPOSIT_AMOUNT * collateral::RATIO_INITIAL / RATIO_POWER;
+    assert_eq!(
+        obligation_info
+            .get_data(&mut context)
+            .await
+            .amount_liquidity_borrowed,
+        borrow_amount
+    );
+    assert_eq!(
+        get_balance(&mut context, &obligation_info.owner.pubkey()).await,
+        owner_balance + borrow_amount
+    );
+    assert_native_account_closed(&mut context, &obligation_info.owner.pubkey(), &market_info).await;
+
+    // Repay the whole debt, only the debt is wrapped
+    process(
+        &mut context,
+        instruction::obligation_liquidity_repay_native(
+            &id(),
+            u64::MAX,
+            &obligation_info.obligation_pubkey,
+            &liquidity_info.liquidity_pubkey,
+            &collateral_info.collateral_pubkey,
+            &obligation_info.owner.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &market_info.market.pubkey(),
+            None,
+        )
+        .unwrap(),
+        &obligation_info.owner,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        obligation_info
+            .get_data(&mut context)
+            .await
+            .amount_liquidity_borrowed,
+        0
+    );
+    assert_eq!(
+        get_token_balance(&mut context, &liquidity_info.token_account.pubkey()).await,
+        DEPOSIT_AMOUNT
+    );
+    assert_eq!(
+        get_balance(&mut context, &obligation_info.owner.pubkey()).await,
+        owner_balance
+    );
+    assert_native_account_closed(&mut context, &obligation_info.owner.pubkey(), &market_info).await;
+}
+
+#[tokio::test]
+async fn liquidity_repay_all_with_interest() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    deposit_liquidity(&mut context, &market_info, &liquidity_info, DEPOSIT_AMOUNT).await;
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    deposit_collateral(
+        &mut context,
+        &market_info,
+        &collateral_info,
+        &obligation_info,
+        DEPOSIT_AMOUNT,
+    )
+    .await;
+
+    // Flat 10% annual borrow rate
+    liquidity_info
+        .update_borrow_rate(
+            &mut context,
+            RATIO_POWER,
+            RATIO_POWER / 10,
+            RATIO_POWER / 10,
+            RATIO_POWER / 10,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    process(
+        &mut context,
+        instruction::obligation_liquidity_borrow_native(
+            &id(),
+            DEPOSIT_AMOUNT / 2,
+            &obligation_info.obligation_pubkey,
+            &liquidity_info.liquidity_pubkey,
+            &collateral_info.collateral_pubkey,
+            &pair_info.pair_pubkey,
+            &obligation_info.owner.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &market_info.market.pubkey(),
+            None,
+            None,
+        )
+        .unwrap(),
+        &obligation_info.owner,
+    )
+    .await
+    .unwrap();
+
+    // Debt grows over the borrowed amount
+    let interest_slot = liquidity_info.get_data(&mut context).await.interest_slot;
+    context
+        .warp_to_slot(interest_slot + SLOTS_PER_YEAR / 100)
+        .unwrap();
+
+    fund(
+        &mut context,
+        &obligation_info.owner.pubkey(),
+        DEPOSIT_AMOUNT,
+    )
+    .await;
+    let owner_balance = get_balance(&mut context, &obligation_info.owner.pubkey()).await;
+
+    // Repay the whole compounded debt
+    process(
+        &mut context,
+        instruction::obligation_liquidity_repay_native(
+            &id(),
+            u64::MAX,
+            &obligation_info.obligation_pubkey,
+            &liquidity_info.liquidity_pubkey,
+            &collateral_info.collateral_pubkey,
+            &obligation_info.owner.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &market_info.market.pubkey(),
+            None,
+        )
+        .unwrap(),
+        &obligation_info.owner,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        obligation_info
+            .get_data(&mut context)
+            .await
+            .amount_liquidity_borrowed,
+        0
+    );
+
+    // Owner pays the compounded debt only, the buffer returns on the close
+    let repay_amount = get_token_balance(&mut context, &liquidity_info.token_account.pubkey())
+        .await
+        - DEPOSIT_AMOUNT / 2;
+    assert!(repay_amount > DEPOSIT_AMOUNT / 2);
+    assert_eq!(
+        get_balance(&mut context, &obligation_info.owner.pubkey()).await,
+        owner_balance - repay_amount
+    );
+    assert_native_account_closed(&mut context, &obligation_info.owner.pubkey(), &market_info).await;
+}
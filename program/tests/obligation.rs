@@ -2,9 +2,13 @@
 
 mod utils;
 
-use everlend_lending::state::{CollateralStatus, LiquidityStatus, PROGRAM_VERSION, RATIO_POWER};
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus, PROGRAM_VERSION, RATIO_POWER},
+};
+use solana_program::instruction::InstructionError;
 use solana_program_test::*;
-use solana_sdk::{signature::Keypair, signer::Signer};
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
 use utils::*;
 
 async fn setup() -> (
@@ -12,6 +16,7 @@ async fn setup() -> (
     MarketInfo,
     LiquidityInfo,
     CollateralInfo,
+    PairInfo,
 ) {
     let mut context = program_test().start_with_context().await;
 
@@ -44,7 +49,23 @@ async fn setup() -> (
         .await
         .unwrap();
 
-    (context, market_info, liquidity_info, collateral_info)
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    (
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+    )
 }
 
 async fn prepare_borrower(
@@ -52,11 +73,18 @@ async fn prepare_borrower(
     market_info: &MarketInfo,
     liquidity_info: &LiquidityInfo,
     collateral_info: &CollateralInfo,
+    pair_info: &PairInfo,
     mint_amount: u64,
 ) -> (ObligationInfo, Keypair, Keypair) {
-    let obligation_info = ObligationInfo::new(market_info, liquidity_info, collateral_info);
+    let obligation_info = ObligationInfo::new(market_info);
     obligation_info
-        .create(context, &market_info, &liquidity_info, &collateral_info)
+        .create(
+            context,
+            market_info,
+            liquidity_info,
+            collateral_info,
+            pair_info,
+        )
         .await
         .unwrap();
 
@@ -114,15 +142,16 @@ async fn prepare_borrower(
 
 #[tokio::test]
 async fn success() {
-    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
 
-    let obligation_info = ObligationInfo::new(&market_info, &liquidity_info, &collateral_info);
+    let obligation_info = ObligationInfo::new(&market_info);
     obligation_info
         .create(
             &mut context,
             &market_info,
             &liquidity_info,
             &collateral_info,
+            &pair_info,
         )
         .await
         .unwrap();
@@ -133,14 +162,51 @@ async fn success() {
     assert_eq!(obligation.version, PROGRAM_VERSION);
 }
 
+#[tokio::test]
+async fn success_sub_accounts() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    let sub_account_info = ObligationInfo::new_sub_account(
+        &market_info,
+        Keypair::from_bytes(&obligation_info.owner.to_bytes()).unwrap(),
+        1,
+    );
+    assert_ne!(
+        obligation_info.obligation_pubkey,
+        sub_account_info.obligation_pubkey
+    );
+
+    for obligation_info in [&obligation_info, &sub_account_info].iter() {
+        obligation_info
+            .create(
+                &mut context,
+                &market_info,
+                &liquidity_info,
+                &collateral_info,
+                &pair_info,
+            )
+            .await
+            .unwrap();
+    }
+
+    let obligation = obligation_info.get_data(&mut context).await;
+    let sub_account = sub_account_info.get_data(&mut context).await;
+
+    assert_eq!(obligation.index, 0);
+    assert_eq!(sub_account.index, 1);
+    assert_eq!(sub_account.owner, obligation.owner);
+}
+
 #[tokio::test]
 async fn collateral_deposit() {
-    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
     let (obligation_info, borrower_collateral, _) = prepare_borrower(
         &mut context,
         &market_info,
         &liquidity_info,
         &collateral_info,
+        &pair_info,
         99999,
     )
     .await;
@@ -168,12 +234,13 @@ async fn collateral_deposit() {
 
 #[tokio::test]
 async fn collateral_withdraw() {
-    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
     let (obligation_info, borrower_collateral, _) = prepare_borrower(
         &mut context,
         &market_info,
         &liquidity_info,
         &collateral_info,
+        &pair_info,
         10000,
     )
     .await;
@@ -195,8 +262,10 @@ async fn collateral_withdraw() {
             &mut context,
             &market_info,
             &collateral_info,
+            &pair_info,
             WITHDRAW_AMOUNT,
             &borrower_collateral.pubkey(),
+            None,
         )
         .await
         .unwrap();
@@ -209,7 +278,7 @@ async fn collateral_withdraw() {
 
 #[tokio::test]
 async fn liquidity_borrow() {
-    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
 
     const DEPOSIT_AMOUNT: u64 = 10000;
     let (obligation_info, borrower_collateral, borrower_liquidity) = prepare_borrower(
@@ -217,6 +286,7 @@ async fn liquidity_borrow() {
         &market_info,
         &liquidity_info,
         &collateral_info,
+        &pair_info,
         DEPOSIT_AMOUNT,
     )
     .await;
@@ -241,8 +311,10 @@ async fn liquidity_borrow() {
             &market_info,
             &liquidity_info,
             &collateral_info,
+            &pair_info,
             borrow_ammount,
             &borrower_liquidity.pubkey(),
+            None,
         )
         .await
         .unwrap();
@@ -268,7 +340,7 @@ async fn liquidity_borrow() {
 
 #[tokio::test]
 async fn liquidity_repay() {
-    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
 
     const DEPOSIT_AMOUNT: u64 = 10000;
     let (obligation_info, borrower_collateral, borrower_liquidity) = prepare_borrower(
@@ -276,6 +348,7 @@ async fn liquidity_repay() {
         &market_info,
         &liquidity_info,
         &collateral_info,
+        &pair_info,
         DEPOSIT_AMOUNT,
     )
     .await;
@@ -300,8 +373,10 @@ async fn liquidity_repay() {
             &market_info,
             &liquidity_info,
             &collateral_info,
+            &pair_info,
             borrow_ammount,
             &borrower_liquidity.pubkey(),
+            None,
         )
         .await
         .unwrap();
@@ -321,6 +396,7 @@ async fn liquidity_repay() {
             &mut context,
             &market_info,
             &liquidity_info,
+            &collateral_info,
             borrow_ammount,
             &borrower_liquidity.pubkey(),
         )
@@ -346,4 +422,185 @@ async fn liquidity_repay() {
     );
 }
 
+#[tokio::test]
+async fn liquidity_borrow_and_repay_all() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    const DEPOSIT_AMOUNT: u64 = 10000;
+    let (obligation_info, borrower_collateral, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+        DEPOSIT_AMOUNT,
+    )
+    .await;
+
+    // Deposit collateral
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    // Borrow up to the limit
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let borrow_ammount = DEPOSIT_AMOUNT * collateral::RATIO_INITIAL / RATIO_POWER;
+    assert_eq!(
+        get_token_balance(&mut context, &borrower_liquidity.pubkey()).await,
+        borrow_ammount
+    );
+
+    // Repay the whole debt
+    obligation_info
+        .liquidity_repay(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        obligation_info
+            .get_data(&mut context)
+            .await
+            .amount_liquidity_borrowed,
+        0
+    );
+
+    // Withdraw all collateral
+    obligation_info
+        .collateral_withdraw(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            &pair_info,
+            u64::MAX,
+            &borrower_collateral.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut context, &borrower_collateral.pubkey()).await,
+        DEPOSIT_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn fail_write_off_solvent_obligation() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    const DEPOSIT_AMOUNT: u64 = 10000;
+    let (obligation_info, borrower_collateral, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+        DEPOSIT_AMOUNT,
+    )
+    .await;
+
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        obligation_info
+            .write_off_bad_debt(
+                &mut context,
+                &market_info,
+                &liquidity_info,
+                &collateral_info,
+                None,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ObligationNotInsolvent as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_write_off_obligation_without_debt() {
+    let (mut context, market_info, liquidity_info, collateral_info, pair_info) = setup().await;
+
+    let (obligation_info, _, _) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+        0,
+    )
+    .await;
+
+    assert_eq!(
+        obligation_info
+            .write_off_bad_debt(
+                &mut context,
+                &market_info,
+                &liquidity_info,
+                &collateral_info,
+                None,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ObligationHasNoDebt as u32)
+        )
+    );
+}
+
 // TODO: need to add more fail tests
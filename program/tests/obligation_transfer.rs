@@ -0,0 +1,407 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{error::LendingError, id, instruction, state::CollateralStatus};
+use solana_program::{
+    instruction::InstructionError, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    signature::Keypair, signer::Signer, transaction::Transaction, transaction::TransactionError,
+    transport,
+};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    pair_info: PairInfo,
+    obligation_info: ObligationInfo,
+}
+
+async fn setup() -> Env {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        obligation_info,
+    }
+}
+
+impl Env {
+    /// Move the position token from the owner to a new holder, returns the holder and its account
+    async fn move_position_token(
+        &mut self,
+        position_mint: &Pubkey,
+        position_token_account: &Pubkey,
+    ) -> (Keypair, Keypair) {
+        let holder = Keypair::new();
+        let holder_token_account = Keypair::new();
+        create_token_account(
+            &mut self.context,
+            &holder_token_account,
+            position_mint,
+            &holder.pubkey(),
+        )
+        .await
+        .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[spl_token::instruction::transfer(
+                &spl_token::id(),
+                position_token_account,
+                &holder_token_account.pubkey(),
+                &self.obligation_info.owner.pubkey(),
+                &[],
+                1,
+            )
+            .unwrap()],
+            Some(&self.context.payer.pubkey()),
+            &[&self.context.payer, &self.obligation_info.owner],
+            self.context.last_blockhash,
+        );
+        self.context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap();
+
+        (holder, holder_token_account)
+    }
+
+    /// Deposit collateral of the owner into the obligation
+    async fn deposit_collateral(&mut self) {
+        let source = Keypair::new();
+        create_token_account(
+            &mut self.context,
+            &source,
+            &self.collateral_info.token_mint.pubkey(),
+            &self.obligation_info.owner.pubkey(),
+        )
+        .await
+        .unwrap();
+
+        mint_tokens(
+            &mut self.context,
+            &self.collateral_info.token_mint.pubkey(),
+            &source.pubkey(),
+            &self.market_info.owner,
+            DEPOSIT_AMOUNT,
+        )
+        .await
+        .unwrap();
+
+        self.obligation_info
+            .collateral_deposit(
+                &mut self.context,
+                &self.market_info,
+                &self.collateral_info,
+                DEPOSIT_AMOUNT,
+                &source.pubkey(),
+            )
+            .await
+            .unwrap();
+    }
+
+    /// Withdraw the whole collateral of the tokenized obligation by the authority
+    async fn withdraw_tokenized(
+        &mut self,
+        authority: &Keypair,
+        position_token_account: &Pubkey,
+    ) -> transport::Result<()> {
+        let destination = Keypair::new();
+        create_token_account(
+            &mut self.context,
+            &destination,
+            &self.collateral_info.token_mint.pubkey(),
+            &authority.pubkey(),
+        )
+        .await
+        .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::obligation_collateral_withdraw(
+                &id(),
+                u64::MAX,
+                &self.obligation_info.obligation_pubkey,
+                &self.liquidity_info.liquidity_pubkey,
+                &self.collateral_info.collateral_pubkey,
+                &self.pair_info.pair_pubkey,
+                &destination.pubkey(),
+                &self.collateral_info.token_account.pubkey(),
+                &self.collateral_info.token_mint.pubkey(),
+                &self.market_info.market.pubkey(),
+                &authority.pubkey(),
+                &spl_token::id(),
+                &self.liquidity_info.token_account.pubkey(),
+                Some(position_token_account),
+                None,
+            )
+            .unwrap()],
+            Some(&self.context.payer.pubkey()),
+            &[&self.context.payer, authority],
+            self.context.last_blockhash,
+        );
+
+        self.context.banks_client.process_transaction(tx).await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+    let new_owner = Keypair::new();
+
+    env.obligation_info
+        .transfer(&mut env.context, &new_owner.pubkey())
+        .await
+        .unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.owner, new_owner.pubkey());
+
+    // Previous owner lost the obligation
+    assert_eq!(
+        env.obligation_info
+            .transfer(&mut env.context, &Keypair::new().pubkey())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn success_tokenize_and_claim() {
+    let mut env = setup().await;
+    let position_mint = Keypair::new();
+    let position_token_account = Keypair::new();
+
+    env.obligation_info
+        .tokenize(
+            &mut env.context,
+            &env.market_info,
+            &position_mint,
+            &position_token_account,
+        )
+        .await
+        .unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.position_mint, position_mint.pubkey());
+
+    let mint_account = get_account(&mut env.context, &position_mint.pubkey()).await;
+    let mint = spl_token::state::Mint::unpack(&mint_account.data).unwrap();
+    assert_eq!(mint.supply, 1);
+    assert_eq!(mint.mint_authority, COption::None);
+
+    // Position token moves to the new holder
+    let (holder, holder_token_account) = env
+        .move_position_token(&position_mint.pubkey(), &position_token_account.pubkey())
+        .await;
+
+    env.obligation_info
+        .claim(&mut env.context, &holder_token_account.pubkey(), &holder)
+        .await
+        .unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.owner, holder.pubkey());
+}
+
+#[tokio::test]
+async fn success_ownership_follows_position_token() {
+    let mut env = setup().await;
+    let position_mint = Keypair::new();
+    let position_token_account = Keypair::new();
+
+    env.deposit_collateral().await;
+    env.obligation_info
+        .tokenize(
+            &mut env.context,
+            &env.market_info,
+            &position_mint,
+            &position_token_account,
+        )
+        .await
+        .unwrap();
+
+    let (holder, holder_token_account) = env
+        .move_position_token(&position_mint.pubkey(), &position_token_account.pubkey())
+        .await;
+
+    // Previous owner can't act on the obligation before the holder claims it
+    let previous_owner = Keypair::from_bytes(&env.obligation_info.owner.to_bytes()).unwrap();
+    assert_eq!(
+        env.withdraw_tokenized(&previous_owner, &holder_token_account.pubkey())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    assert_eq!(
+        env.withdraw_tokenized(&previous_owner, &position_token_account.pubkey())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // Holder of the position token acts without claiming
+    env.withdraw_tokenized(&holder, &holder_token_account.pubkey())
+        .await
+        .unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_collateral_deposited, 0);
+}
+
+#[tokio::test]
+async fn fail_transfer_tokenized() {
+    let mut env = setup().await;
+
+    env.obligation_info
+        .tokenize(
+            &mut env.context,
+            &env.market_info,
+            &Keypair::new(),
+            &Keypair::new(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.obligation_info
+            .transfer(&mut env.context, &Keypair::new().pubkey())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ObligationTokenized as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_transfer_to_default_owner() {
+    let mut env = setup().await;
+
+    assert_eq!(
+        env.obligation_info
+            .transfer(&mut env.context, &Pubkey::default())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn fail_claim_without_position_token() {
+    let mut env = setup().await;
+    let position_mint = Keypair::new();
+
+    env.obligation_info
+        .tokenize(
+            &mut env.context,
+            &env.market_info,
+            &position_mint,
+            &Keypair::new(),
+        )
+        .await
+        .unwrap();
+
+    let claimer = Keypair::new();
+    let claimer_token_account = Keypair::new();
+    create_token_account(
+        &mut env.context,
+        &claimer_token_account,
+        &position_mint.pubkey(),
+        &claimer.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        env.obligation_info
+            .claim(&mut env.context, &claimer_token_account.pubkey(), &claimer)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn fail_claim_not_tokenized() {
+    let mut env = setup().await;
+    let owner = Keypair::from_bytes(&env.obligation_info.owner.to_bytes()).unwrap();
+
+    assert_eq!(
+        env.obligation_info
+            .claim(&mut env.context, &Keypair::new().pubkey(), &owner)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ObligationNotTokenized as u32)
+        )
+    );
+}
@@ -0,0 +1,420 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    id, instruction,
+    state::{CollateralStatus, LiquidityStatus},
+};
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_program_test::*;
+use solana_sdk::{
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+    transport,
+};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const BORROW_AMOUNT: u64 = 4000;
+const POOL_AMOUNT: u64 = 10000;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    pair_info: PairInfo,
+    obligation_info: ObligationInfo,
+    swap_pool_info: SwapPoolInfo,
+    // Same obligation signed by the operator
+    operator_info: ObligationInfo,
+    operator_collateral: Keypair,
+    operator_liquidity: Keypair,
+}
+
+async fn setup() -> Env {
+    let mut program_test = program_test();
+    swap::add_program(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &source,
+            &destination,
+            999999,
+        )
+        .await;
+
+    // Swap pool paying out liquidity for collateral
+    let swap_pool_info = SwapPoolInfo::new();
+    swap_pool_info
+        .create(
+            &mut context,
+            &market_info,
+            &collateral_info.token_mint.pubkey(),
+            &liquidity_info.token_mint.pubkey(),
+            POOL_AMOUNT,
+        )
+        .await
+        .unwrap();
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+
+    create_token_account(
+        &mut context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            BORROW_AMOUNT,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let operator_info = ObligationInfo {
+        obligation_pubkey: obligation_info.obligation_pubkey,
+        owner: Keypair::new(),
+        index: obligation_info.index,
+    };
+    obligation_info
+        .set_operator(&mut context, &operator_info.owner.pubkey())
+        .await
+        .unwrap();
+
+    // Swap accounts of the operator
+    let operator_collateral = Keypair::new();
+    let operator_liquidity = Keypair::new();
+
+    create_token_account(
+        &mut context,
+        &operator_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &operator_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &operator_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &operator_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        obligation_info,
+        swap_pool_info,
+        operator_info,
+        operator_collateral,
+        operator_liquidity,
+    }
+}
+
+impl Env {
+    /// Operator swapping the collateral through the pool into the swap destination
+    async fn repay_with_collateral(
+        &mut self,
+        collateral_amount: u64,
+        swap_destination: &Pubkey,
+    ) -> transport::Result<()> {
+        self.operator_info
+            .repay_with_collateral(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+                &self.swap_pool_info,
+                collateral_amount,
+                0,
+                &self.operator_collateral.pubkey(),
+                swap_destination,
+            )
+            .await
+    }
+
+    /// Operator swapping the collateral without paying anything out
+    async fn repay_with_noop_swap(&mut self, collateral_amount: u64) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::repay_with_collateral(
+                &id(),
+                collateral_amount,
+                0,
+                &self.obligation_info.obligation_pubkey,
+                &self.liquidity_info.liquidity_pubkey,
+                &self.collateral_info.collateral_pubkey,
+                &self.pair_info.pair_pubkey,
+                &self.operator_collateral.pubkey(),
+                &self.liquidity_info.token_account.pubkey(),
+                &self.collateral_info.token_account.pubkey(),
+                &self.collateral_info.token_mint.pubkey(),
+                &self.liquidity_info.token_account.pubkey(),
+                &self.liquidity_info.token_mint.pubkey(),
+                &self.market_info.market.pubkey(),
+                &self.operator_info.owner.pubkey(),
+                &spl_token::id(),
+                &swap::noop_id(),
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap()],
+            Some(&self.context.payer.pubkey()),
+            &[&self.context.payer, &self.operator_info.owner],
+            self.context.last_blockhash,
+        );
+
+        self.context.banks_client.process_transaction(tx).await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.operator, env.operator_info.owner.pubkey());
+
+    // Operator can step down by itself
+    let operator = Keypair::from_bytes(&env.operator_info.owner.to_bytes()).unwrap();
+    env.obligation_info
+        .clear_operator(&mut env.context, &operator)
+        .await
+        .unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.operator, Default::default());
+}
+
+#[tokio::test]
+async fn success_repay_with_collateral_by_operator() {
+    let mut env = setup().await;
+
+    let liquidity_token_account = env.liquidity_info.token_account.pubkey();
+    env.repay_with_collateral(3000, &liquidity_token_account)
+        .await
+        .unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, BORROW_AMOUNT - 3000);
+    assert_eq!(
+        obligation.amount_collateral_deposited,
+        DEPOSIT_AMOUNT - 3000
+    );
+
+    // Nothing stays with the operator
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.operator_collateral.pubkey()).await,
+        0
+    );
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.operator_liquidity.pubkey()).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn fail_repay_with_collateral_to_operator_account() {
+    let mut env = setup().await;
+
+    let operator_liquidity = env.operator_liquidity.pubkey();
+    assert_eq!(
+        env.repay_with_collateral(3000, &operator_liquidity)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn fail_repay_with_collateral_over_debt_by_operator() {
+    let mut env = setup().await;
+
+    let liquidity_token_account = env.liquidity_info.token_account.pubkey();
+    assert_eq!(
+        env.repay_with_collateral(BORROW_AMOUNT + 1, &liquidity_token_account)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::SwapAmountExceedsDebt as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_repay_with_noop_swap_by_operator() {
+    let mut env = setup().await;
+
+    // Swap has to pay out at par
+    assert_eq!(
+        env.repay_with_noop_swap(3000).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::SlippageExceeded as u32)
+        )
+    );
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, BORROW_AMOUNT);
+    assert_eq!(obligation.amount_collateral_deposited, DEPOSIT_AMOUNT);
+}
+
+#[tokio::test]
+async fn fail_withdraw_by_operator() {
+    let mut env = setup().await;
+
+    assert_eq!(
+        env.operator_info
+            .collateral_withdraw(
+                &mut env.context,
+                &env.market_info,
+                &env.collateral_info,
+                &env.pair_info,
+                1000,
+                &env.operator_collateral.pubkey(),
+                None,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn fail_after_obligation_transfer() {
+    let mut env = setup().await;
+
+    // Operator appointed by the previous owner lapses
+    env.obligation_info
+        .transfer(&mut env.context, &Keypair::new().pubkey())
+        .await
+        .unwrap();
+
+    let operator = Keypair::from_bytes(&env.operator_info.owner.to_bytes()).unwrap();
+    assert_eq!(
+        env.obligation_info
+            .clear_operator(&mut env.context, &operator)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{error::LendingError, state::LiquidityStatus};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const OUTFLOW_LIMIT: u64 = 3000;
+const OUTFLOW_WINDOW: u64 = 1000;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    provider_actor: ProviderActor,
+    provider_liquidity: Keypair,
+    provider_pool: Keypair,
+}
+
+async fn setup() -> Env {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update_outflow_limit(&mut context, OUTFLOW_LIMIT, OUTFLOW_WINDOW, &market_info)
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (provider_liquidity, provider_pool) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &provider_liquidity,
+            &provider_pool,
+            DEPOSIT_AMOUNT,
+        )
+        .await;
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        provider_actor,
+        provider_liquidity,
+        provider_pool,
+    }
+}
+
+impl Env {
+    async fn withdraw(&mut self, amount: u64) -> transport::Result<()> {
+        self.liquidity_info
+            .withdraw(
+                &mut self.context,
+                &self.market_info,
+                &self.provider_pool.pubkey(),
+                &self.provider_liquidity.pubkey(),
+                amount,
+                &self.provider_actor.owner,
+            )
+            .await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+
+    env.withdraw(OUTFLOW_LIMIT).await.unwrap();
+    assert_eq!(
+        env.liquidity_info
+            .get_data(&mut env.context)
+            .await
+            .outflow_amount,
+        OUTFLOW_LIMIT
+    );
+
+    // Outflows are allowed again once the window rolls over
+    env.context.warp_to_slot(OUTFLOW_WINDOW * 2).unwrap();
+    env.withdraw(OUTFLOW_LIMIT - 1).await.unwrap();
+}
+
+#[tokio::test]
+async fn success_reset() {
+    let mut env = setup().await;
+
+    env.withdraw(OUTFLOW_LIMIT).await.unwrap();
+
+    env.liquidity_info
+        .reset_outflow(&mut env.context, &env.market_info)
+        .await
+        .unwrap();
+
+    env.withdraw(OUTFLOW_LIMIT - 1).await.unwrap();
+}
+
+#[tokio::test]
+async fn fail_withdraw_outflow_limit_exceeded() {
+    let mut env = setup().await;
+
+    env.withdraw(OUTFLOW_LIMIT).await.unwrap();
+
+    assert_eq!(
+        env.withdraw(1).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::OutflowLimitExceeded as u32)
+        )
+    );
+}
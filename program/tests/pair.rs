@@ -0,0 +1,293 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus, PairConfig, PROGRAM_VERSION},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+
+async fn setup() -> (
+    ProgramTestContext,
+    MarketInfo,
+    LiquidityInfo,
+    CollateralInfo,
+) {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &source,
+            &destination,
+            999999,
+        )
+        .await;
+
+    (context, market_info, liquidity_info, collateral_info)
+}
+
+/// Obligation with the collateral deposited and the borrower liquidity account
+async fn prepare_borrower(
+    context: &mut ProgramTestContext,
+    market_info: &MarketInfo,
+    liquidity_info: &LiquidityInfo,
+    collateral_info: &CollateralInfo,
+    pair_info: &PairInfo,
+) -> (ObligationInfo, Keypair) {
+    let obligation_info = ObligationInfo::new(market_info);
+    obligation_info
+        .create(
+            context,
+            market_info,
+            liquidity_info,
+            collateral_info,
+            pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+
+    create_token_account(
+        context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            context,
+            market_info,
+            collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    (obligation_info, borrower_liquidity)
+}
+
+#[tokio::test]
+async fn success() {
+    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let pair = pair_info.get_data(&mut context).await;
+    assert_eq!(pair.version, PROGRAM_VERSION);
+    assert_eq!(pair.liquidity, liquidity_info.liquidity_pubkey);
+    assert_eq!(pair.collateral, collateral_info.collateral_pubkey);
+    assert_eq!(pair.ratio_initial, pair::CONFIG.ratio_initial);
+
+    // Borrow limit of the obligation follows the pair
+    let (obligation_info, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+    )
+    .await;
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            u64::MAX,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut context, &borrower_liquidity.pubkey()).await,
+        DEPOSIT_AMOUNT / 2
+    );
+}
+
+#[tokio::test]
+async fn success_update() {
+    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let config = PairConfig {
+        borrow_enabled: false,
+        ..pair::CONFIG
+    };
+    pair_info
+        .update(&mut context, config, &market_info)
+        .await
+        .unwrap();
+
+    assert!(!pair_info.get_data(&mut context).await.borrow_enabled);
+}
+
+#[tokio::test]
+async fn fail_create_obligation_without_pair() {
+    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+
+    // Pair account is not created
+    let pair_info = PairInfo::new(&market_info, &liquidity_info, &collateral_info);
+    let obligation_info = ObligationInfo::new(&market_info);
+
+    assert_eq!(
+        obligation_info
+            .create(
+                &mut context,
+                &market_info,
+                &liquidity_info,
+                &collateral_info,
+                &pair_info,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAccountOwner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_borrow_disabled() {
+    let (mut context, market_info, liquidity_info, collateral_info) = setup().await;
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let (obligation_info, borrower_liquidity) = prepare_borrower(
+        &mut context,
+        &market_info,
+        &liquidity_info,
+        &collateral_info,
+        &pair_info,
+    )
+    .await;
+
+    let config = PairConfig {
+        borrow_enabled: false,
+        ..pair::CONFIG
+    };
+    pair_info
+        .update(&mut context, config, &market_info)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        obligation_info
+            .liquidity_borrow(
+                &mut context,
+                &market_info,
+                &liquidity_info,
+                &collateral_info,
+                &pair_info,
+                1,
+                &borrower_liquidity.pubkey(),
+                None,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::PairBorrowDisabled as u32)
+        )
+    );
+}
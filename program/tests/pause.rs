@@ -0,0 +1,194 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{LiquidityStatus, PAUSE_BORROW, PAUSE_DEPOSIT, PAUSE_WITHDRAW},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    guardian: Keypair,
+    provider_actor: ProviderActor,
+    provider_liquidity: Keypair,
+    provider_pool: Keypair,
+}
+
+async fn setup() -> Env {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    let guardian = Keypair::new();
+    market_info
+        .update_guardian(&mut context, &guardian.pubkey())
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (provider_liquidity, provider_pool) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &provider_liquidity,
+            &provider_pool,
+            DEPOSIT_AMOUNT,
+        )
+        .await;
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        guardian,
+        provider_actor,
+        provider_liquidity,
+        provider_pool,
+    }
+}
+
+impl Env {
+    async fn deposit(&mut self) -> transport::Result<()> {
+        self.liquidity_info
+            .deposit(
+                &mut self.context,
+                &self.market_info,
+                &self.provider_liquidity.pubkey(),
+                &self.provider_pool.pubkey(),
+                DEPOSIT_AMOUNT,
+                &self.provider_actor.owner,
+            )
+            .await
+    }
+
+    async fn withdraw(&mut self) -> transport::Result<()> {
+        self.liquidity_info
+            .withdraw(
+                &mut self.context,
+                &self.market_info,
+                &self.provider_pool.pubkey(),
+                &self.provider_liquidity.pubkey(),
+                DEPOSIT_AMOUNT,
+                &self.provider_actor.owner,
+            )
+            .await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+
+    env.market_info
+        .pause(
+            &mut env.context,
+            PAUSE_DEPOSIT | PAUSE_BORROW,
+            &env.guardian,
+        )
+        .await
+        .unwrap();
+
+    let market = env.market_info.get_data(&mut env.context).await;
+    assert_eq!(market.guardian, env.guardian.pubkey());
+    assert_eq!(market.paused, PAUSE_DEPOSIT | PAUSE_BORROW);
+
+    assert_eq!(
+        env.deposit().await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::OperationPaused as u32)
+        )
+    );
+
+    // Other operations aren't paused
+    env.withdraw().await.unwrap();
+
+    env.market_info
+        .unpause(&mut env.context, PAUSE_DEPOSIT, &env.market_info.owner)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.market_info.get_data(&mut env.context).await.paused,
+        PAUSE_BORROW
+    );
+    env.deposit().await.unwrap();
+}
+
+#[tokio::test]
+async fn fail_withdraw_paused() {
+    let mut env = setup().await;
+
+    env.market_info
+        .pause(&mut env.context, PAUSE_WITHDRAW, &env.guardian)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.withdraw().await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::OperationPaused as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_unpause_by_guardian() {
+    let mut env = setup().await;
+
+    env.market_info
+        .pause(&mut env.context, PAUSE_DEPOSIT, &env.guardian)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.market_info
+            .unpause(&mut env.context, PAUSE_DEPOSIT, &env.guardian)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn fail_pause_by_stranger() {
+    let mut env = setup().await;
+    let stranger = Keypair::new();
+
+    assert_eq!(
+        env.market_info
+            .pause(&mut env.context, PAUSE_DEPOSIT, &stranger)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
@@ -0,0 +1,321 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const BORROW_AMOUNT: u64 = 4000;
+const POOL_AMOUNT: u64 = 20000;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    new_liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    new_pair_info: PairInfo,
+    obligation_info: ObligationInfo,
+    swap_pool_info: SwapPoolInfo,
+    borrower_liquidity: Keypair,
+    borrower_new_liquidity: Keypair,
+}
+
+async fn setup() -> Env {
+    let mut program_test = program_test();
+    swap::add_program(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let new_liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    for liquidity_info in [&liquidity_info, &new_liquidity_info].iter() {
+        liquidity_info
+            .update(&mut context, LiquidityStatus::Active, &market_info)
+            .await
+            .unwrap();
+
+        let (source, destination) = provider_actor
+            .create_liquidity_accounts(&mut context, liquidity_info)
+            .await
+            .unwrap();
+        provider_actor
+            .deposit(
+                &mut context,
+                &market_info,
+                liquidity_info,
+                &source,
+                &destination,
+                999999,
+            )
+            .await;
+    }
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    let new_pair_info = market_info
+        .create_pair(
+            &mut context,
+            &new_liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    // Swap pool paying out old liquidity for the new one
+    let swap_pool_info = SwapPoolInfo::new();
+    swap_pool_info
+        .create(
+            &mut context,
+            &market_info,
+            &new_liquidity_info.token_mint.pubkey(),
+            &liquidity_info.token_mint.pubkey(),
+            POOL_AMOUNT,
+        )
+        .await
+        .unwrap();
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+    let borrower_new_liquidity = Keypair::new();
+
+    create_token_account(
+        &mut context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &borrower_new_liquidity,
+        &new_liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            BORROW_AMOUNT,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        new_liquidity_info,
+        collateral_info,
+        new_pair_info,
+        obligation_info,
+        swap_pool_info,
+        borrower_liquidity,
+        borrower_new_liquidity,
+    }
+}
+
+impl Env {
+    async fn refinance_debt(&mut self, amount: u64) -> transport::Result<()> {
+        self.obligation_info
+            .refinance_debt(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.new_liquidity_info,
+                &self.collateral_info,
+                &self.new_pair_info,
+                &self.swap_pool_info,
+                amount,
+                &self.borrower_new_liquidity.pubkey(),
+                &self.borrower_liquidity.pubkey(),
+            )
+            .await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+
+    env.refinance_debt(BORROW_AMOUNT).await.unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(
+        obligation.liquidity,
+        env.new_liquidity_info.liquidity_pubkey
+    );
+    assert_eq!(obligation.amount_liquidity_borrowed, BORROW_AMOUNT);
+
+    assert_eq!(
+        env.liquidity_info
+            .get_data(&mut env.context)
+            .await
+            .amount_borrowed,
+        0
+    );
+    assert_eq!(
+        env.new_liquidity_info
+            .get_data(&mut env.context)
+            .await
+            .amount_borrowed,
+        BORROW_AMOUNT
+    );
+
+    // Borrowed liquidity is left untouched
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.borrower_liquidity.pubkey()).await,
+        BORROW_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn fail_swap_not_covering_debt() {
+    let mut env = setup().await;
+
+    assert_eq!(
+        env.refinance_debt(BORROW_AMOUNT - 1)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::SlippageExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_health_check() {
+    let mut env = setup().await;
+
+    // Borrowing over the initial ratio of the new pair
+    assert_eq!(
+        env.refinance_debt(DEPOSIT_AMOUNT / 2 + 1)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::CollateralHealthCheckFailed as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_new_liquidity_inactive() {
+    let mut env = setup().await;
+
+    env.new_liquidity_info
+        .update(
+            &mut env.context,
+            LiquidityStatus::Inactive,
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.refinance_debt(BORROW_AMOUNT)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
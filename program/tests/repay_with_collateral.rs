@@ -0,0 +1,276 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const DEPOSIT_AMOUNT: u64 = 10000;
+const BORROW_AMOUNT: u64 = 4000;
+const POOL_AMOUNT: u64 = 10000;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    pair_info: PairInfo,
+    obligation_info: ObligationInfo,
+    swap_pool_info: SwapPoolInfo,
+    borrower_collateral: Keypair,
+    borrower_liquidity: Keypair,
+}
+
+async fn setup() -> Env {
+    let mut program_test = program_test();
+    swap::add_program(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (source, destination) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &source,
+            &destination,
+            999999,
+        )
+        .await;
+
+    // Swap pool paying out liquidity for collateral
+    let swap_pool_info = SwapPoolInfo::new();
+    swap_pool_info
+        .create(
+            &mut context,
+            &market_info,
+            &collateral_info.token_mint.pubkey(),
+            &liquidity_info.token_mint.pubkey(),
+            POOL_AMOUNT,
+        )
+        .await
+        .unwrap();
+
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+
+    create_token_account(
+        &mut context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            DEPOSIT_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            BORROW_AMOUNT,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        obligation_info,
+        swap_pool_info,
+        borrower_collateral,
+        borrower_liquidity,
+    }
+}
+
+impl Env {
+    async fn repay_with_collateral(
+        &mut self,
+        collateral_amount: u64,
+        minimum_amount_out: u64,
+    ) -> transport::Result<()> {
+        self.obligation_info
+            .repay_with_collateral(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+                &self.swap_pool_info,
+                collateral_amount,
+                minimum_amount_out,
+                &self.borrower_collateral.pubkey(),
+                &self.borrower_liquidity.pubkey(),
+            )
+            .await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+
+    env.repay_with_collateral(3000, 3000).await.unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, BORROW_AMOUNT - 3000);
+    assert_eq!(
+        obligation.amount_collateral_deposited,
+        DEPOSIT_AMOUNT - 3000
+    );
+
+    // Swapped liquidity went to the repay
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.borrower_liquidity.pubkey()).await,
+        BORROW_AMOUNT
+    );
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.borrower_collateral.pubkey()).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn success_repay_whole_debt() {
+    let mut env = setup().await;
+
+    env.repay_with_collateral(5000, 5000).await.unwrap();
+
+    let obligation = env.obligation_info.get_data(&mut env.context).await;
+    assert_eq!(obligation.amount_liquidity_borrowed, 0);
+    assert_eq!(
+        obligation.amount_collateral_deposited,
+        DEPOSIT_AMOUNT - 5000
+    );
+
+    // Liquidity left over the debt stays with the borrower
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.borrower_liquidity.pubkey()).await,
+        BORROW_AMOUNT + 1000
+    );
+}
+
+#[tokio::test]
+async fn fail_slippage_exceeded() {
+    let mut env = setup().await;
+
+    assert_eq!(
+        env.repay_with_collateral(3000, 3001)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::SlippageExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_withdraw_more_than_deposited() {
+    let mut env = setup().await;
+
+    assert!(env
+        .repay_with_collateral(DEPOSIT_AMOUNT + 1, 0)
+        .await
+        .is_err());
+}
@@ -0,0 +1,199 @@
+//! Property tests of the rounding policy, run with a deterministic generator
+//! so failures are reproducible
+
+use everlend_lending::state::{Liquidity, Obligation, RATIO_POWER};
+
+const ITERATIONS: usize = 10_000;
+
+/// Xorshift generator
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn range(&mut self, min: u64, max: u64) -> u64 {
+        min + self.next() % (max - min + 1)
+    }
+}
+
+/// Liquidity with the token account and pool mint it is exchanged against
+struct Pool {
+    liquidity: Liquidity,
+    token_account_amount: u64,
+    pool_mint_supply: u64,
+}
+
+impl Pool {
+    fn random(rng: &mut Rng) -> Self {
+        Self {
+            liquidity: Liquidity {
+                amount_borrowed: rng.range(0, 1_000_000_000_000),
+                ..Liquidity::default()
+            },
+            token_account_amount: rng.range(0, 1_000_000_000_000),
+            pool_mint_supply: rng.range(1, 1_000_000_000_000),
+        }
+    }
+
+    fn total_amount(&self) -> u128 {
+        (self.token_account_amount + self.liquidity.amount_borrowed) as u128
+    }
+
+    fn deposit(&mut self, amount: u64) -> u64 {
+        let pool_tokens_amount = self
+            .liquidity
+            .calc_deposit_exchange_amount(amount, self.token_account_amount, self.pool_mint_supply)
+            .unwrap();
+        self.token_account_amount += amount;
+        self.pool_mint_supply += pool_tokens_amount;
+
+        pool_tokens_amount
+    }
+
+    fn withdraw(&mut self, pool_tokens_amount: u64) -> Option<u64> {
+        let amount = self
+            .liquidity
+            .calc_withdraw_exchange_amount(
+                pool_tokens_amount,
+                self.token_account_amount,
+                self.pool_mint_supply,
+            )
+            .unwrap();
+        // Borrowed liquidity can't be withdrawn
+        if amount > self.token_account_amount {
+            return None;
+        }
+        self.token_account_amount -= amount;
+        self.pool_mint_supply -= pool_tokens_amount;
+
+        Some(amount)
+    }
+}
+
+#[test]
+fn deposit_withdraw_round_trip() {
+    let mut rng = Rng(0x5eed);
+
+    for _ in 0..ITERATIONS {
+        let mut pool = Pool::random(&mut rng);
+        let amount = rng.range(1, 1_000_000_000_000);
+
+        let pool_tokens_amount = pool.deposit(amount);
+        if let Some(withdrawn) = pool.withdraw(pool_tokens_amount) {
+            assert!(withdrawn <= amount);
+        }
+    }
+}
+
+#[test]
+fn repeated_dust_round_trips() {
+    let mut rng = Rng(0xd057);
+
+    for _ in 0..ITERATIONS / 100 {
+        let mut pool = Pool::random(&mut rng);
+        pool.liquidity.amount_borrowed = 0;
+        pool.token_account_amount = rng.range(1, 1_000_000_000_000);
+
+        const BALANCE: u64 = 1_000_000;
+        let mut balance = BALANCE;
+        for _ in 0..100 {
+            let (total_amount, pool_mint_supply) = (pool.total_amount(), pool.pool_mint_supply);
+
+            let amount = rng.range(1, 100).min(balance);
+            balance -= amount;
+            let pool_tokens_amount = pool.deposit(amount);
+            balance += pool.withdraw(pool_tokens_amount).unwrap();
+
+            // Value of the pool token never decreases
+            assert!(
+                pool.total_amount() * pool_mint_supply as u128
+                    >= total_amount * pool.pool_mint_supply as u128
+            );
+        }
+
+        assert!(balance <= BALANCE);
+    }
+}
+
+#[test]
+fn exact_out_round_trip() {
+    let mut rng = Rng(0xe4ac);
+
+    for _ in 0..ITERATIONS {
+        let pool = Pool::random(&mut rng);
+        let liquidity = &pool.liquidity;
+        let amount = rng.range(1, 1_000_000_000_000);
+
+        // Deposit pays at least the value of the pool tokens
+        let amount_in = liquidity
+            .calc_deposit_amount_in(amount, pool.token_account_amount, pool.pool_mint_supply)
+            .unwrap();
+        assert!(
+            amount_in as u128 * pool.pool_mint_supply as u128
+                >= amount as u128 * pool.total_amount()
+        );
+        assert!(
+            liquidity
+                .calc_deposit_exchange_amount(
+                    amount_in,
+                    pool.token_account_amount,
+                    pool.pool_mint_supply
+                )
+                .unwrap()
+                >= amount
+        );
+
+        // Withdraw burns at least the value of the liquidity
+        let pool_tokens_in = liquidity
+            .calc_withdraw_amount_in(amount, pool.token_account_amount, pool.pool_mint_supply)
+            .unwrap();
+        assert!(
+            pool_tokens_in as u128 * pool.total_amount()
+                >= amount as u128 * pool.pool_mint_supply as u128
+        );
+        assert!(
+            liquidity
+                .calc_withdraw_exchange_amount(
+                    pool_tokens_in,
+                    pool.token_account_amount,
+                    pool.pool_mint_supply
+                )
+                .unwrap()
+                >= amount
+        );
+    }
+}
+
+#[test]
+fn limits_keep_obligation_healthy() {
+    let mut rng = Rng(0x0b11);
+
+    for _ in 0..ITERATIONS {
+        let ratio_initial = rng.range(1, RATIO_POWER);
+        let mut obligation = Obligation {
+            amount_collateral_deposited: rng.range(1, u64::MAX / 2),
+            ..Obligation::default()
+        };
+
+        // Borrow a part of the limit first
+        let borrowing_limit = obligation.calc_borrowing_limit(ratio_initial).unwrap();
+        obligation.amount_liquidity_borrowed = rng.range(0, borrowing_limit);
+
+        let mut borrowed = Obligation { ..obligation };
+        borrowed.amount_liquidity_borrowed += borrowed.calc_borrowing_limit(ratio_initial).unwrap();
+        assert!(borrowed.calc_health().unwrap() <= ratio_initial);
+
+        let withdrawal_limit = obligation.calc_withdrawal_limit(ratio_initial).unwrap();
+        obligation.amount_collateral_deposited -= withdrawal_limit;
+        if obligation.amount_collateral_deposited > 0 {
+            assert!(obligation.calc_health().unwrap() <= ratio_initial);
+        } else {
+            assert_eq!(obligation.amount_liquidity_borrowed, 0);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    id, instruction,
+    state::LiquidityStatus,
+    token::{
+        assert_supported_mint, get_token_account_len, has_transfer_fee, spl_token_2022,
+        unpack_mint, unpack_token_account,
+    },
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::InstructionError,
+    program_error::ProgramError, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use utils::{token_2022::*, *};
+
+/// Token-2022 non-transferable extension type
+const EXTENSION_NON_TRANSFERABLE: u16 = 9;
+
+/// Token-2022 accounts packed by the spl-token-2022 crate (1.0.0), the extension layout
+/// the program parses is checked against them
+const MINT_TRANSFER_FEE: &[u8] = include_bytes!("fixtures/token_2022_mint_transfer_fee.bin");
+const MINT_METADATA_POINTER: &[u8] =
+    include_bytes!("fixtures/token_2022_mint_metadata_pointer.bin");
+const MINT_PERMANENT_DELEGATE: &[u8] =
+    include_bytes!("fixtures/token_2022_mint_permanent_delegate.bin");
+const ACCOUNT_TRANSFER_FEE: &[u8] = include_bytes!("fixtures/token_2022_account_transfer_fee.bin");
+
+fn assert_supported(data: &[u8]) -> ProgramResult {
+    let key = Pubkey::new_unique();
+    let owner = spl_token_2022::id();
+    let mut lamports = 0;
+    let mut data = data.to_vec();
+
+    assert_supported_mint(&AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    ))
+}
+
+/// Token-2022 mint with a single extension without data
+fn mint_with_extension(mint_authority: &Pubkey, extension_type: u16) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN + 1 + 4];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(*mint_authority),
+        supply: 0,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data[..spl_token::state::Mint::LEN]);
+
+    // Account type of a mint followed by the extension type and zero length
+    data[spl_token::state::Account::LEN] = 1;
+    data[spl_token::state::Account::LEN + 1..spl_token::state::Account::LEN + 3]
+        .copy_from_slice(&extension_type.to_le_bytes());
+
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token_2022::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[tokio::test]
+async fn fail_unsupported_mint_extension() {
+    let market_info = MarketInfo::new();
+    let liquidity_info = LiquidityInfo::new("liquidity0", &market_info);
+
+    let mut program_test = program_test();
+    program_test.add_account(
+        liquidity_info.token_mint.pubkey(),
+        mint_with_extension(&market_info.owner.pubkey(), EXTENSION_NON_TRANSFERABLE),
+    );
+
+    let mut context = program_test.start_with_context().await;
+    market_info.init(&mut context).await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::create_liquidity_token(
+            &id(),
+            &liquidity_info.liquidity_pubkey,
+            &liquidity_info.token_mint.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &liquidity_info.pool_mint.pubkey(),
+            &market_info.market.pubkey(),
+            &market_info.owner.pubkey(),
+            &spl_token_2022::id(),
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &market_info.owner],
+        context.last_blockhash,
+    );
+
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::UnsupportedMintExtension as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn deposit_with_transfer_fee() {
+    const TRANSFER_FEE_BASIS_POINTS: u16 = 100;
+    const DEPOSIT_AMOUNT: u64 = 10000;
+    const RECEIVED_AMOUNT: u64 = DEPOSIT_AMOUNT - DEPOSIT_AMOUNT / 100;
+
+    let mut context = program_test_with_token_2022().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info =
+        create_transfer_fee_liquidity(&mut context, &market_info, TRANSFER_FEE_BASIS_POINTS)
+            .await
+            .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    let provider = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+
+    create_token_2022_account(
+        &mut context,
+        &source,
+        &liquidity_info.token_mint.pubkey(),
+        &provider.pubkey(),
+        TRANSFER_FEE_ACCOUNT_LEN,
+    )
+    .await
+    .unwrap();
+
+    create_token_2022_account(
+        &mut context,
+        &destination,
+        &liquidity_info.pool_mint.pubkey(),
+        &provider.pubkey(),
+        spl_token::state::Account::LEN,
+    )
+    .await
+    .unwrap();
+
+    mint_token_2022(
+        &mut context,
+        &liquidity_info.token_mint.pubkey(),
+        &source.pubkey(),
+        &market_info.owner,
+        DEPOSIT_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::liquidity_deposit(
+            &id(),
+            DEPOSIT_AMOUNT,
+            &liquidity_info.liquidity_pubkey,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &liquidity_info.token_mint.pubkey(),
+            &liquidity_info.pool_mint.pubkey(),
+            &market_info.market.pubkey(),
+            &provider.pubkey(),
+            &spl_token_2022::id(),
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &provider],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Only the amount received after the transfer fee is credited
+    assert_eq!(
+        get_token_balance(&mut context, &liquidity_info.token_account.pubkey()).await,
+        RECEIVED_AMOUNT
+    );
+    assert_eq!(
+        get_token_balance(&mut context, &destination.pubkey()).await,
+        RECEIVED_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn fail_deposit_exact_out_with_transfer_fee() {
+    let mut context = program_test_with_token_2022().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = create_transfer_fee_liquidity(&mut context, &market_info, 100)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    let provider = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+
+    create_token_2022_account(
+        &mut context,
+        &source,
+        &liquidity_info.token_mint.pubkey(),
+        &provider.pubkey(),
+        TRANSFER_FEE_ACCOUNT_LEN,
+    )
+    .await
+    .unwrap();
+
+    create_token_2022_account(
+        &mut context,
+        &destination,
+        &liquidity_info.pool_mint.pubkey(),
+        &provider.pubkey(),
+        spl_token::state::Account::LEN,
+    )
+    .await
+    .unwrap();
+
+    mint_token_2022(
+        &mut context,
+        &liquidity_info.token_mint.pubkey(),
+        &source.pubkey(),
+        &market_info.owner,
+        20000,
+    )
+    .await
+    .unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::liquidity_deposit_exact_out(
+            &id(),
+            10000,
+            20000,
+            &liquidity_info.liquidity_pubkey,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &liquidity_info.token_account.pubkey(),
+            &liquidity_info.token_mint.pubkey(),
+            &liquidity_info.pool_mint.pubkey(),
+            &market_info.market.pubkey(),
+            &provider.pubkey(),
+            &spl_token_2022::id(),
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &provider],
+        context.last_blockhash,
+    );
+
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::UnsupportedMintExtension as u32)
+        )
+    );
+}
+
+#[test]
+fn extension_layout() {
+    assert_eq!(unpack_mint(MINT_TRANSFER_FEE).unwrap().decimals, 6);
+    assert!(has_transfer_fee(MINT_TRANSFER_FEE).unwrap());
+    assert_supported(MINT_TRANSFER_FEE).unwrap();
+
+    // Token account of the transfer fee mint carries the withheld amount extension
+    assert_eq!(
+        get_token_account_len(MINT_TRANSFER_FEE).unwrap(),
+        ACCOUNT_TRANSFER_FEE.len()
+    );
+    assert_eq!(
+        unpack_token_account(ACCOUNT_TRANSFER_FEE).unwrap().amount,
+        1000
+    );
+
+    assert!(!has_transfer_fee(MINT_METADATA_POINTER).unwrap());
+    assert_supported(MINT_METADATA_POINTER).unwrap();
+    assert_eq!(
+        get_token_account_len(MINT_METADATA_POINTER).unwrap(),
+        spl_token::state::Account::LEN
+    );
+
+    assert_eq!(
+        assert_supported(MINT_PERMANENT_DELEGATE).unwrap_err(),
+        ProgramError::from(LendingError::UnsupportedMintExtension)
+    );
+}
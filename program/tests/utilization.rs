@@ -0,0 +1,443 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus, RATIO_POWER, SLOTS_PER_YEAR},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const LIQUIDITY_AMOUNT: u64 = 10000;
+const COLLATERAL_AMOUNT: u64 = 2 * LIQUIDITY_AMOUNT;
+const MAX_UTILIZATION: u64 = 80 * RATIO_POWER / 100;
+const AVAILABLE_AMOUNT: u64 = LIQUIDITY_AMOUNT * 8 / 10;
+const OPTIMAL_UTILIZATION: u64 = RATIO_POWER / 2;
+const BORROW_RATE_OPTIMAL: u64 = RATIO_POWER / 10;
+const BORROW_RATE_MAX: u64 = 21 * RATIO_POWER / 10;
+const RESERVE_FACTOR: u64 = RATIO_POWER / 10;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    pair_info: PairInfo,
+    provider_actor: ProviderActor,
+    provider_liquidity: Keypair,
+    provider_pool: Keypair,
+}
+
+async fn setup() -> Env {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update_utilization(&mut context, MAX_UTILIZATION, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (provider_liquidity, provider_pool) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &provider_liquidity,
+            &provider_pool,
+            LIQUIDITY_AMOUNT,
+        )
+        .await;
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        pair_info,
+        provider_actor,
+        provider_liquidity,
+        provider_pool,
+    }
+}
+
+impl Env {
+    /// Obligation with the collateral deposited and the borrower liquidity account
+    async fn prepare_borrower(&mut self) -> (ObligationInfo, Keypair) {
+        let obligation_info = ObligationInfo::new(&self.market_info);
+        obligation_info
+            .create(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+            )
+            .await
+            .unwrap();
+
+        let borrower_collateral = Keypair::new();
+        let borrower_liquidity = Keypair::new();
+
+        create_token_account(
+            &mut self.context,
+            &borrower_collateral,
+            &self.collateral_info.token_mint.pubkey(),
+            &obligation_info.owner.pubkey(),
+        )
+        .await
+        .unwrap();
+
+        create_token_account(
+            &mut self.context,
+            &borrower_liquidity,
+            &self.liquidity_info.token_mint.pubkey(),
+            &obligation_info.owner.pubkey(),
+        )
+        .await
+        .unwrap();
+
+        mint_tokens(
+            &mut self.context,
+            &self.collateral_info.token_mint.pubkey(),
+            &borrower_collateral.pubkey(),
+            &self.market_info.owner,
+            COLLATERAL_AMOUNT,
+        )
+        .await
+        .unwrap();
+
+        obligation_info
+            .collateral_deposit(
+                &mut self.context,
+                &self.market_info,
+                &self.collateral_info,
+                COLLATERAL_AMOUNT,
+                &borrower_collateral.pubkey(),
+            )
+            .await
+            .unwrap();
+
+        (obligation_info, borrower_liquidity)
+    }
+
+    async fn borrow(
+        &mut self,
+        obligation_info: &ObligationInfo,
+        amount: u64,
+        destination: &Keypair,
+    ) -> transport::Result<()> {
+        obligation_info
+            .liquidity_borrow(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                &self.pair_info,
+                amount,
+                &destination.pubkey(),
+                None,
+            )
+            .await
+    }
+
+    /// Repay the whole debt after the slots elapsed since the last accrual, returns the amount repaid
+    async fn repay_after(
+        &mut self,
+        obligation_info: &ObligationInfo,
+        slots: u64,
+        source: &Keypair,
+    ) -> u64 {
+        let interest_slot = self
+            .liquidity_info
+            .get_data(&mut self.context)
+            .await
+            .interest_slot;
+        self.context.warp_to_slot(interest_slot + slots).unwrap();
+
+        // Interest is paid on top of the borrowed liquidity
+        mint_tokens(
+            &mut self.context,
+            &self.liquidity_info.token_mint.pubkey(),
+            &source.pubkey(),
+            &self.market_info.owner,
+            LIQUIDITY_AMOUNT,
+        )
+        .await
+        .unwrap();
+        let balance = get_token_balance(&mut self.context, &source.pubkey()).await;
+
+        obligation_info
+            .liquidity_repay(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                u64::MAX,
+                &source.pubkey(),
+            )
+            .await
+            .unwrap();
+
+        balance - get_token_balance(&mut self.context, &source.pubkey()).await
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+    let (obligation_info, borrower_liquidity) = env.prepare_borrower().await;
+
+    // Borrowing all is limited by the maximum utilization, not the obligation limit
+    env.borrow(&obligation_info, u64::MAX, &borrower_liquidity)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut env.context, &borrower_liquidity.pubkey()).await,
+        AVAILABLE_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn fail_borrow_utilization_exceeded() {
+    let mut env = setup().await;
+    let (obligation_info, borrower_liquidity) = env.prepare_borrower().await;
+
+    assert_eq!(
+        env.borrow(&obligation_info, AVAILABLE_AMOUNT + 1, &borrower_liquidity)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::UtilizationExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_withdraw_utilization_exceeded() {
+    let mut env = setup().await;
+    let (obligation_info, borrower_liquidity) = env.prepare_borrower().await;
+
+    env.borrow(&obligation_info, AVAILABLE_AMOUNT, &borrower_liquidity)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.liquidity_info
+            .withdraw(
+                &mut env.context,
+                &env.market_info,
+                &env.provider_pool.pubkey(),
+                &env.provider_liquidity.pubkey(),
+                1,
+                &env.provider_actor.owner,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::UtilizationExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_interest_below_optimal_utilization() {
+    let mut env = setup().await;
+    let (obligation_info, borrower_liquidity) = env.prepare_borrower().await;
+
+    env.liquidity_info
+        .update_borrow_rate(
+            &mut env.context,
+            OPTIMAL_UTILIZATION,
+            0,
+            BORROW_RATE_OPTIMAL,
+            BORROW_RATE_MAX,
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+
+    // 40% utilization is charged 8% a year
+    env.borrow(&obligation_info, 4000, &borrower_liquidity)
+        .await
+        .unwrap();
+    let repay_amount = env
+        .repay_after(&obligation_info, SLOTS_PER_YEAR / 100, &borrower_liquidity)
+        .await;
+
+    // 4000 * 0.08 / 100 = 3.2, the interest is rounded up for the obligation
+    assert_eq!(repay_amount, 4004);
+    assert_eq!(
+        obligation_info
+            .get_data(&mut env.context)
+            .await
+            .amount_liquidity_borrowed,
+        0
+    );
+
+    // Lenders receive the interest
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.liquidity_info.token_account.pubkey()).await,
+        LIQUIDITY_AMOUNT + 4
+    );
+    assert_eq!(
+        env.liquidity_info
+            .get_data(&mut env.context)
+            .await
+            .amount_borrowed,
+        0
+    );
+}
+
+#[tokio::test]
+async fn success_interest_spikes_beyond_optimal_utilization() {
+    let mut env = setup().await;
+    let (obligation_info, borrower_liquidity) = env.prepare_borrower().await;
+
+    env.liquidity_info
+        .update_borrow_rate(
+            &mut env.context,
+            OPTIMAL_UTILIZATION,
+            0,
+            BORROW_RATE_OPTIMAL,
+            BORROW_RATE_MAX,
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+
+    // 80% utilization is charged 10% + 200% * 0.6 = 130% a year
+    env.borrow(&obligation_info, AVAILABLE_AMOUNT, &borrower_liquidity)
+        .await
+        .unwrap();
+    let repay_amount = env
+        .repay_after(&obligation_info, SLOTS_PER_YEAR / 100, &borrower_liquidity)
+        .await;
+
+    // 8000 * 1.3 / 100 = 104
+    assert_eq!(repay_amount, AVAILABLE_AMOUNT + 104);
+}
+
+#[tokio::test]
+async fn success_reserve_paid_to_insurance() {
+    let mut env = setup().await;
+    let (obligation_info, borrower_liquidity) = env.prepare_borrower().await;
+
+    env.liquidity_info
+        .create_insurance(&mut env.context, &env.market_info, RESERVE_FACTOR)
+        .await
+        .unwrap();
+
+    env.liquidity_info
+        .update_borrow_rate(
+            &mut env.context,
+            OPTIMAL_UTILIZATION,
+            0,
+            BORROW_RATE_OPTIMAL,
+            BORROW_RATE_MAX,
+            &env.market_info,
+        )
+        .await
+        .unwrap();
+
+    env.borrow(&obligation_info, AVAILABLE_AMOUNT, &borrower_liquidity)
+        .await
+        .unwrap();
+    let repay_amount = env
+        .repay_after(&obligation_info, SLOTS_PER_YEAR / 100, &borrower_liquidity)
+        .await;
+    assert_eq!(repay_amount, AVAILABLE_AMOUNT + 104);
+
+    // 10% of the interest 104 goes to the insurance, the rest to the lenders
+    assert_eq!(
+        get_token_balance(
+            &mut env.context,
+            &env.liquidity_info.insurance_account.pubkey()
+        )
+        .await,
+        10
+    );
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.liquidity_info.token_account.pubkey()).await,
+        LIQUIDITY_AMOUNT + 94
+    );
+    assert_eq!(
+        env.liquidity_info
+            .get_data(&mut env.context)
+            .await
+            .amount_reserve,
+        0
+    );
+}
+
+#[tokio::test]
+async fn fail_borrow_rate_decreasing() {
+    let mut env = setup().await;
+
+    assert_eq!(
+        env.liquidity_info
+            .update_borrow_rate(
+                &mut env.context,
+                OPTIMAL_UTILIZATION,
+                0,
+                BORROW_RATE_MAX,
+                BORROW_RATE_OPTIMAL,
+                &env.market_info,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
@@ -0,0 +1,98 @@
+use super::{get_account, market::MarketInfo};
+use everlend_lending::{find_program_address, id, instruction, state::Category};
+use solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{signature::Signer, transaction::Transaction, transport};
+
+#[derive(Debug)]
+pub struct CategoryInfo {
+    pub category_pubkey: Pubkey,
+    pub id: u8,
+}
+
+impl CategoryInfo {
+    pub fn new(id: u8, market_info: &MarketInfo) -> Self {
+        let (market_authority, _) =
+            find_program_address(&everlend_lending::id(), &market_info.market.pubkey());
+        let seed = format!("category{}", id);
+
+        Self {
+            category_pubkey: Pubkey::create_with_seed(
+                &market_authority,
+                &seed,
+                &everlend_lending::id(),
+            )
+            .unwrap(),
+            id,
+        }
+    }
+
+    pub async fn get_data(&self, context: &mut ProgramTestContext) -> Category {
+        let category_account = get_account(context, &self.category_pubkey).await;
+        Category::unpack_unchecked(&category_account.data).unwrap()
+    }
+
+    pub async fn create(
+        &self,
+        context: &mut ProgramTestContext,
+        ratio_initial: u64,
+        ratio_healthy: u64,
+        name: &str,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let mut name_bytes = [0; 32];
+        name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                // Transfer a few lamports to cover fee for create account
+                system_instruction::transfer(
+                    &context.payer.pubkey(),
+                    &market_info.owner.pubkey(),
+                    999999999,
+                ),
+                instruction::create_category(
+                    &id(),
+                    self.id,
+                    ratio_initial,
+                    ratio_healthy,
+                    name_bytes,
+                    &self.category_pubkey,
+                    &market_info.market.pubkey(),
+                    &market_info.owner.pubkey(),
+                )
+                .unwrap(),
+            ],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update(
+        &self,
+        context: &mut ProgramTestContext,
+        ratio_initial: u64,
+        ratio_healthy: u64,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_category(
+                &id(),
+                ratio_initial,
+                ratio_healthy,
+                &self.category_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+}
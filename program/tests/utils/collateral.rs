@@ -1,4 +1,4 @@
-use super::{get_account, market::MarketInfo};
+use super::{category::CategoryInfo, get_account, market::MarketInfo};
 use everlend_lending::{
     find_program_address, id, instruction,
     state::{Collateral, CollateralStatus, RATIO_POWER},
@@ -42,6 +42,16 @@ impl CollateralInfo {
         &self,
         context: &mut ProgramTestContext,
         market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        self.create_with_mint(context, market_info, &self.token_mint.pubkey())
+            .await
+    }
+
+    pub async fn create_with_mint(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        token_mint: &Pubkey,
     ) -> transport::Result<()> {
         let rent = context.banks_client.get_rent().await.unwrap();
 
@@ -65,10 +75,11 @@ impl CollateralInfo {
                     RATIO_INITIAL,
                     RATIO_HEALTHY,
                     &self.collateral_pubkey,
-                    &self.token_mint.pubkey(),
+                    token_mint,
                     &self.token_account.pubkey(),
                     &market_info.market.pubkey(),
                     &market_info.owner.pubkey(),
+                    &spl_token::id(),
                 )
                 .unwrap(),
             ],
@@ -106,4 +117,85 @@ impl CollateralInfo {
 
         context.banks_client.process_transaction(tx).await
     }
+
+    pub async fn update_isolation(
+        &self,
+        context: &mut ProgramTestContext,
+        isolated: bool,
+        debt_ceiling: u64,
+        liquidities: &[Pubkey],
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_collateral_isolation(
+                &id(),
+                isolated,
+                debt_ceiling,
+                &self.collateral_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+                liquidities,
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update_liquidation(
+        &self,
+        context: &mut ProgramTestContext,
+        bonus_start: u64,
+        bonus_max: u64,
+        bonus_slots: u64,
+        close_factor: u64,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_collateral_liquidation(
+                &id(),
+                bonus_start,
+                bonus_max,
+                bonus_slots,
+                close_factor,
+                &self.collateral_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update_category(
+        &self,
+        context: &mut ProgramTestContext,
+        category_id: u8,
+        category_info: Option<&CategoryInfo>,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_collateral_category(
+                &id(),
+                category_id,
+                &self.collateral_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+                category_info.map(|category_info| &category_info.category_pubkey),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
 }
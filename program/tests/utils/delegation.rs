@@ -0,0 +1,129 @@
+use super::{
+    collateral::CollateralInfo, get_account, liquidity::LiquidityInfo, market::MarketInfo,
+    obligation::ObligationInfo, pair::PairInfo,
+};
+use everlend_lending::{find_delegation_authority, id, instruction, state::Delegation};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    transport,
+};
+
+#[derive(Debug)]
+pub struct DelegationInfo {
+    pub delegation_pubkey: Pubkey,
+    pub delegate: Keypair,
+}
+
+impl DelegationInfo {
+    pub fn new(obligation_info: &ObligationInfo) -> Self {
+        let delegate = Keypair::new();
+        let (delegation_authority, _) = find_delegation_authority(
+            &everlend_lending::id(),
+            &obligation_info.obligation_pubkey,
+            &delegate.pubkey(),
+        );
+
+        Self {
+            delegation_pubkey: Pubkey::create_with_seed(&delegation_authority, "delegation", &id())
+                .unwrap(),
+            delegate,
+        }
+    }
+
+    pub async fn get_data(&self, context: &mut ProgramTestContext) -> Delegation {
+        let delegation_account = get_account(context, &self.delegation_pubkey).await;
+        Delegation::unpack_unchecked(&delegation_account.data).unwrap()
+    }
+
+    pub async fn create(
+        &self,
+        context: &mut ProgramTestContext,
+        obligation_info: &ObligationInfo,
+        allowance: u64,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::create_delegation(
+                &id(),
+                allowance,
+                &self.delegation_pubkey,
+                &obligation_info.obligation_pubkey,
+                &self.delegate.pubkey(),
+                &obligation_info.owner.pubkey(),
+                None,
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &obligation_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update(
+        &self,
+        context: &mut ProgramTestContext,
+        obligation_info: &ObligationInfo,
+        allowance: u64,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_delegation(
+                &id(),
+                allowance,
+                &self.delegation_pubkey,
+                &obligation_info.obligation_pubkey,
+                &obligation_info.owner.pubkey(),
+                None,
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &obligation_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn borrow(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        obligation_info: &ObligationInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        pair_info: &PairInfo,
+        amount: u64,
+        destination: &Pubkey,
+        delegate: &Keypair,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::obligation_liquidity_borrow_delegated(
+                &id(),
+                amount,
+                &obligation_info.obligation_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &pair_info.pair_pubkey,
+                destination,
+                &liquidity_info.token_account.pubkey(),
+                &liquidity_info.token_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &delegate.pubkey(),
+                &self.delegation_pubkey,
+                &spl_token::id(),
+                None,
+                None,
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, delegate],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+}
@@ -1,4 +1,4 @@
-use super::{get_account, market::MarketInfo};
+use super::{category::CategoryInfo, get_account, market::MarketInfo};
 use everlend_lending::{
     find_program_address, id, instruction,
     state::{Liquidity, LiquidityStatus},
@@ -17,6 +17,7 @@ pub struct LiquidityInfo {
     pub token_mint: Keypair,
     pub token_account: Keypair,
     pub pool_mint: Keypair,
+    pub insurance_account: Keypair,
 }
 
 impl LiquidityInfo {
@@ -29,6 +30,7 @@ impl LiquidityInfo {
             token_mint: Keypair::new(),
             token_account: Keypair::new(),
             pool_mint: Keypair::new(),
+            insurance_account: Keypair::new(),
         }
     }
 
@@ -37,10 +39,26 @@ impl LiquidityInfo {
         Liquidity::unpack_unchecked(&liquidity_account.data).unwrap()
     }
 
+    /// Insurance account receiving the reserve on the repay, if the liquidity has insurance
+    pub async fn get_insurance_account(&self, context: &mut ProgramTestContext) -> Option<Pubkey> {
+        let liquidity = self.get_data(context).await;
+        Some(liquidity.insurance_account).filter(|_| liquidity.has_insurance())
+    }
+
     pub async fn create(
         &self,
         context: &mut ProgramTestContext,
         market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        self.create_with_mint(context, market_info, &self.token_mint.pubkey())
+            .await
+    }
+
+    pub async fn create_with_mint(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        token_mint: &Pubkey,
     ) -> transport::Result<()> {
         let rent = context.banks_client.get_rent().await.unwrap();
 
@@ -69,11 +87,12 @@ impl LiquidityInfo {
                 instruction::create_liquidity_token(
                     &id(),
                     &self.liquidity_pubkey,
-                    &self.token_mint.pubkey(),
+                    token_mint,
                     &self.token_account.pubkey(),
                     &self.pool_mint.pubkey(),
                     &market_info.market.pubkey(),
                     &market_info.owner.pubkey(),
+                    &spl_token::id(),
                 )
                 .unwrap(),
             ],
@@ -130,9 +149,46 @@ impl LiquidityInfo {
                 source,
                 destination,
                 &self.token_account.pubkey(),
+                &self.token_mint.pubkey(),
+                &self.pool_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &provider.pubkey(),
+                &spl_token::id(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &provider],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn deposit_checked(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        source: &Pubkey,
+        destination: &Pubkey,
+        amount: u64,
+        min_pool_tokens_out: u64,
+        provider: &Keypair,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::liquidity_deposit_checked(
+                &id(),
+                amount,
+                min_pool_tokens_out,
+                &self.liquidity_pubkey,
+                source,
+                destination,
+                &self.token_account.pubkey(),
+                &self.token_mint.pubkey(),
                 &self.pool_mint.pubkey(),
                 &market_info.market.pubkey(),
                 &provider.pubkey(),
+                &spl_token::id(),
             )
             .unwrap()],
             Some(&context.payer.pubkey()),
@@ -160,9 +216,11 @@ impl LiquidityInfo {
                 source,
                 destination,
                 &self.token_account.pubkey(),
+                &self.token_mint.pubkey(),
                 &self.pool_mint.pubkey(),
                 &market_info.market.pubkey(),
                 &provider.pubkey(),
+                &spl_token::id(),
             )
             .unwrap()],
             Some(&context.payer.pubkey()),
@@ -172,4 +230,244 @@ impl LiquidityInfo {
 
         context.banks_client.process_transaction(tx).await
     }
+
+    pub async fn create_insurance(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        reserve_factor: u64,
+    ) -> transport::Result<()> {
+        let rent = context.banks_client.get_rent().await.unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::create_account(
+                    &context.payer.pubkey(),
+                    &self.insurance_account.pubkey(),
+                    rent.minimum_balance(spl_token::state::Account::LEN),
+                    spl_token::state::Account::LEN as u64,
+                    &spl_token::id(),
+                ),
+                instruction::create_liquidity_insurance(
+                    &id(),
+                    reserve_factor,
+                    &self.liquidity_pubkey,
+                    &self.insurance_account.pubkey(),
+                    &self.token_mint.pubkey(),
+                    &market_info.market.pubkey(),
+                    &market_info.owner.pubkey(),
+                    &spl_token::id(),
+                )
+                .unwrap(),
+            ],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.insurance_account, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn insurance_top_up(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        source: &Pubkey,
+        amount: u64,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::insurance_top_up(
+                &id(),
+                amount,
+                &self.liquidity_pubkey,
+                source,
+                &self.insurance_account.pubkey(),
+                &self.token_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+                &spl_token::id(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn insurance_withdraw_request(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        amount: u64,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::insurance_withdraw_request(
+                &id(),
+                amount,
+                &self.liquidity_pubkey,
+                &self.insurance_account.pubkey(),
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn insurance_withdraw(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        destination: &Pubkey,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::insurance_withdraw(
+                &id(),
+                &self.liquidity_pubkey,
+                &self.insurance_account.pubkey(),
+                destination,
+                &self.token_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+                &spl_token::id(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update_category(
+        &self,
+        context: &mut ProgramTestContext,
+        category_id: u8,
+        category_info: Option<&CategoryInfo>,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_liquidity_category(
+                &id(),
+                category_id,
+                &self.liquidity_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+                category_info.map(|category_info| &category_info.category_pubkey),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update_utilization(
+        &self,
+        context: &mut ProgramTestContext,
+        max_utilization: u64,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_liquidity_utilization(
+                &id(),
+                max_utilization,
+                &self.liquidity_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_borrow_rate(
+        &self,
+        context: &mut ProgramTestContext,
+        optimal_utilization: u64,
+        borrow_rate_min: u64,
+        borrow_rate_optimal: u64,
+        borrow_rate_max: u64,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_liquidity_borrow_rate(
+                &id(),
+                optimal_utilization,
+                borrow_rate_min,
+                borrow_rate_optimal,
+                borrow_rate_max,
+                &self.liquidity_pubkey,
+                &self.token_account.pubkey(),
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update_outflow_limit(
+        &self,
+        context: &mut ProgramTestContext,
+        outflow_limit: u64,
+        outflow_window: u64,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_liquidity_outflow_limit(
+                &id(),
+                outflow_limit,
+                outflow_window,
+                &self.liquidity_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn reset_outflow(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::reset_liquidity_outflow(
+                &id(),
+                &self.liquidity_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
 }
@@ -1,7 +1,12 @@
-use super::{collateral::CollateralInfo, get_account, liquidity::LiquidityInfo};
+use super::{collateral::CollateralInfo, get_account, liquidity::LiquidityInfo, pair::PairInfo};
 use crate::utils::create_mint;
-use everlend_lending::{id, instruction, state::Market};
-use solana_program::{borsh::get_packed_len, program_pack::Pack, system_instruction};
+use everlend_lending::{
+    id, instruction,
+    state::{Market, PairConfig},
+};
+use solana_program::{
+    borsh::get_packed_len, program_pack::Pack, pubkey::Pubkey, system_instruction,
+};
 use solana_program_test::ProgramTestContext;
 use solana_sdk::{
     signature::{Keypair, Signer},
@@ -83,4 +88,85 @@ impl MarketInfo {
 
         Ok(collateral_info)
     }
+
+    pub async fn create_pair(
+        &self,
+        context: &mut ProgramTestContext,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        config: PairConfig,
+    ) -> transport::Result<PairInfo> {
+        let pair_info = PairInfo::new(self, liquidity_info, collateral_info);
+
+        pair_info
+            .create(context, self, liquidity_info, collateral_info, config)
+            .await?;
+
+        Ok(pair_info)
+    }
+
+    pub async fn update_guardian(
+        &self,
+        context: &mut ProgramTestContext,
+        guardian: &Pubkey,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_market_guardian(
+                &id(),
+                &self.market.pubkey(),
+                &self.owner.pubkey(),
+                guardian,
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn pause(
+        &self,
+        context: &mut ProgramTestContext,
+        operations: u64,
+        guardian: &Keypair,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::pause_market(
+                &id(),
+                operations,
+                &self.market.pubkey(),
+                &guardian.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, guardian],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn unpause(
+        &self,
+        context: &mut ProgramTestContext,
+        operations: u64,
+        market_owner: &Keypair,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::unpause_market(
+                &id(),
+                operations,
+                &self.market.pubkey(),
+                &market_owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, market_owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
 }
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 use everlend_lending::{id, processor};
-use solana_program::{program_pack::Pack, system_instruction};
+use solana_program::{program_option::COption, program_pack::Pack, system_instruction};
 use solana_program_test::ProgramTestContext;
 use solana_program_test::*;
 use solana_sdk::{
@@ -9,17 +9,28 @@ use solana_sdk::{
     transport,
 };
 
+pub mod category;
 pub mod collateral;
+pub mod delegation;
 pub mod liquidity;
 pub mod market;
 pub mod obligation;
+pub mod pair;
 pub mod provider;
+pub mod swap;
+pub mod token_2022;
+pub mod withdrawal_ticket;
 
+pub use category::CategoryInfo;
 pub use collateral::CollateralInfo;
+pub use delegation::DelegationInfo;
 pub use liquidity::LiquidityInfo;
 pub use market::MarketInfo;
 pub use obligation::ObligationInfo;
+pub use pair::PairInfo;
 pub use provider::ProviderActor;
+pub use swap::SwapPoolInfo;
+pub use withdrawal_ticket::WithdrawalTicketInfo;
 
 pub fn program_test() -> ProgramTest {
     ProgramTest::new(
@@ -136,3 +147,39 @@ pub async fn mint_tokens(
 
     context.banks_client.process_transaction(tx).await
 }
+
+/// Native mint isn't loaded by the program test
+pub fn native_mint_account() -> Account {
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::None,
+        supply: 0,
+        decimals: spl_token::native_mint::DECIMALS,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+pub async fn fund(context: &mut ProgramTestContext, account: &Pubkey, lamports: u64) {
+    let tx = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            account,
+            lamports,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await.unwrap();
+}
@@ -1,8 +1,14 @@
 use super::{
-    collateral::CollateralInfo, get_account, liquidity::LiquidityInfo, market::MarketInfo,
+    category::CategoryInfo,
+    collateral::CollateralInfo,
+    get_account,
+    liquidity::LiquidityInfo,
+    market::MarketInfo,
+    pair::PairInfo,
+    swap::{self, SwapPoolInfo},
 };
-use everlend_lending::state::Obligation;
-use everlend_lending::{find_obligation_authority, id, instruction};
+use everlend_lending::state::{Liquidity, Obligation};
+use everlend_lending::{find_obligation_authority, id, instruction, obligation_seed};
 use solana_program::program_pack::Pack;
 use solana_program::pubkey::Pubkey;
 use solana_program::system_instruction;
@@ -17,27 +23,30 @@ use solana_sdk::{
 pub struct ObligationInfo {
     pub obligation_pubkey: Pubkey,
     pub owner: Keypair,
+    pub index: u16,
 }
 
 impl ObligationInfo {
-    pub fn new(
-        market_info: &MarketInfo,
-        liquidity_info: &LiquidityInfo,
-        collateral_info: &CollateralInfo,
-    ) -> Self {
-        let owner = Keypair::new();
+    pub fn new(market_info: &MarketInfo) -> Self {
+        Self::new_sub_account(market_info, Keypair::new(), 0)
+    }
+
+    pub fn new_sub_account(market_info: &MarketInfo, owner: Keypair, index: u16) -> Self {
         let (obligation_authority, _) = find_obligation_authority(
             &everlend_lending::id(),
             &owner.pubkey(),
             &market_info.market.pubkey(),
-            &liquidity_info.liquidity_pubkey,
-            &collateral_info.collateral_pubkey,
         );
 
         Self {
-            obligation_pubkey: Pubkey::create_with_seed(&obligation_authority, "obligation", &id())
-                .unwrap(),
+            obligation_pubkey: Pubkey::create_with_seed(
+                &obligation_authority,
+                &obligation_seed(index),
+                &id(),
+            )
+            .unwrap(),
             owner,
+            index,
         }
     }
 
@@ -52,6 +61,7 @@ impl ObligationInfo {
         market_info: &MarketInfo,
         liquidity_info: &LiquidityInfo,
         collateral_info: &CollateralInfo,
+        pair_info: &PairInfo,
     ) -> transport::Result<()> {
         let tx = Transaction::new_signed_with_payer(
             &[
@@ -63,9 +73,11 @@ impl ObligationInfo {
                 ),
                 instruction::create_obligation(
                     &id(),
+                    self.index,
                     &self.obligation_pubkey,
                     &liquidity_info.liquidity_pubkey,
                     &collateral_info.collateral_pubkey,
+                    &pair_info.pair_pubkey,
                     &market_info.market.pubkey(),
                     &self.owner.pubkey(),
                 )
@@ -95,8 +107,10 @@ impl ObligationInfo {
                 &collateral_info.collateral_pubkey,
                 source,
                 &collateral_info.token_account.pubkey(),
+                &collateral_info.token_mint.pubkey(),
                 &market_info.market.pubkey(),
                 &self.owner.pubkey(),
+                &spl_token::id(),
             )
             .unwrap()],
             Some(&context.payer.pubkey()),
@@ -112,19 +126,33 @@ impl ObligationInfo {
         context: &mut ProgramTestContext,
         market_info: &MarketInfo,
         collateral_info: &CollateralInfo,
+        pair_info: &PairInfo,
         amount: u64,
         destination: &Pubkey,
+        category_info: Option<&CategoryInfo>,
     ) -> transport::Result<()> {
+        let liquidity = self.get_data(context).await.liquidity;
+        let liquidity_token_account =
+            Liquidity::unpack_unchecked(&get_account(context, &liquidity).await.data)
+                .unwrap()
+                .token_account;
         let tx = Transaction::new_signed_with_payer(
             &[instruction::obligation_collateral_withdraw(
                 &id(),
                 amount,
                 &self.obligation_pubkey,
+                &liquidity,
                 &collateral_info.collateral_pubkey,
+                &pair_info.pair_pubkey,
                 destination,
                 &collateral_info.token_account.pubkey(),
+                &collateral_info.token_mint.pubkey(),
                 &market_info.market.pubkey(),
                 &self.owner.pubkey(),
+                &spl_token::id(),
+                &liquidity_token_account,
+                None,
+                category_info.map(|category_info| &category_info.category_pubkey),
             )
             .unwrap()],
             Some(&context.payer.pubkey()),
@@ -141,8 +169,10 @@ impl ObligationInfo {
         market_info: &MarketInfo,
         liquidity_info: &LiquidityInfo,
         collateral_info: &CollateralInfo,
+        pair_info: &PairInfo,
         amount: u64,
         destination: &Pubkey,
+        category_info: Option<&CategoryInfo>,
     ) -> transport::Result<()> {
         let tx = Transaction::new_signed_with_payer(
             &[instruction::obligation_liquidity_borrow(
@@ -151,10 +181,15 @@ impl ObligationInfo {
                 &self.obligation_pubkey,
                 &liquidity_info.liquidity_pubkey,
                 &collateral_info.collateral_pubkey,
+                &pair_info.pair_pubkey,
                 destination,
                 &liquidity_info.token_account.pubkey(),
+                &liquidity_info.token_mint.pubkey(),
                 &market_info.market.pubkey(),
                 &self.owner.pubkey(),
+                &spl_token::id(),
+                None,
+                category_info.map(|category_info| &category_info.category_pubkey),
             )
             .unwrap()],
             Some(&context.payer.pubkey()),
@@ -170,19 +205,415 @@ impl ObligationInfo {
         context: &mut ProgramTestContext,
         market_info: &MarketInfo,
         liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
         amount: u64,
         source: &Pubkey,
     ) -> transport::Result<()> {
+        let insurance_account = liquidity_info.get_insurance_account(context).await;
         let tx = Transaction::new_signed_with_payer(
             &[instruction::obligation_liquidity_repay(
                 &id(),
                 amount,
                 &self.obligation_pubkey,
                 &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                source,
+                &liquidity_info.token_account.pubkey(),
+                &liquidity_info.token_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &self.owner.pubkey(),
+                &spl_token::id(),
+                insurance_account.as_ref(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn write_off_bad_debt(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        insurance_account: Option<&Pubkey>,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::write_off_bad_debt(
+                &id(),
+                &self.obligation_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &liquidity_info.token_account.pubkey(),
+                &liquidity_info.token_mint.pubkey(),
+                &liquidity_info.pool_mint.pubkey(),
+                &market_info.market.pubkey(),
+                insurance_account,
+                &spl_token::id(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update_category(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        pair_info: &PairInfo,
+        category: u8,
+        category_info: Option<&CategoryInfo>,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_obligation_category(
+                &id(),
+                category,
+                &self.obligation_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &pair_info.pair_pubkey,
+                &market_info.market.pubkey(),
+                &self.owner.pubkey(),
+                None,
+                category_info.map(|category_info| &category_info.category_pubkey),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn refresh_health(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        pair_info: &PairInfo,
+        category_info: Option<&CategoryInfo>,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::refresh_obligation_health(
+                &id(),
+                &self.obligation_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &pair_info.pair_pubkey,
+                &market_info.market.pubkey(),
+                &liquidity_info.token_account.pubkey(),
+                category_info.map(|category_info| &category_info.category_pubkey),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn liquidate(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        pair_info: &PairInfo,
+        amount: u64,
+        source: &Pubkey,
+        destination: &Pubkey,
+        liquidator: &Keypair,
+    ) -> transport::Result<()> {
+        let insurance_account = liquidity_info.get_insurance_account(context).await;
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::liquidate_obligation(
+                &id(),
+                amount,
+                &self.obligation_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &pair_info.pair_pubkey,
                 source,
+                destination,
+                &liquidity_info.token_account.pubkey(),
+                &liquidity_info.token_mint.pubkey(),
+                &collateral_info.token_account.pubkey(),
+                &collateral_info.token_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &liquidator.pubkey(),
+                &spl_token::id(),
+                None,
+                insurance_account.as_ref(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, liquidator],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn repay_with_collateral(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        pair_info: &PairInfo,
+        swap_pool_info: &SwapPoolInfo,
+        collateral_amount: u64,
+        minimum_amount_out: u64,
+        swap_source: &Pubkey,
+        swap_destination: &Pubkey,
+    ) -> transport::Result<()> {
+        let insurance_account = liquidity_info.get_insurance_account(context).await;
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::repay_with_collateral(
+                &id(),
+                collateral_amount,
+                minimum_amount_out,
+                &self.obligation_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &pair_info.pair_pubkey,
+                swap_source,
+                swap_destination,
+                &collateral_info.token_account.pubkey(),
+                &collateral_info.token_mint.pubkey(),
+                &liquidity_info.token_account.pubkey(),
+                &liquidity_info.token_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &self.owner.pubkey(),
+                &spl_token::id(),
+                &swap::id(),
+                None,
+                None,
+                insurance_account.as_ref(),
+                &swap_pool_info.accounts(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn swap_collateral(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        new_collateral_info: &CollateralInfo,
+        new_pair_info: &PairInfo,
+        swap_pool_info: &SwapPoolInfo,
+        minimum_amount_out: u64,
+        swap_source: &Pubkey,
+        swap_destination: &Pubkey,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::swap_obligation_collateral(
+                &id(),
+                minimum_amount_out,
+                &self.obligation_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &new_collateral_info.collateral_pubkey,
+                &new_pair_info.pair_pubkey,
+                swap_source,
+                swap_destination,
+                &collateral_info.token_account.pubkey(),
+                &collateral_info.token_mint.pubkey(),
+                &new_collateral_info.token_account.pubkey(),
+                &new_collateral_info.token_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &self.owner.pubkey(),
+                &spl_token::id(),
+                &swap::id(),
+                &liquidity_info.token_account.pubkey(),
+                None,
+                None,
+                &swap_pool_info.accounts(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn refinance_debt(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        new_liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        new_pair_info: &PairInfo,
+        swap_pool_info: &SwapPoolInfo,
+        amount: u64,
+        swap_source: &Pubkey,
+        swap_destination: &Pubkey,
+    ) -> transport::Result<()> {
+        let insurance_account = liquidity_info.get_insurance_account(context).await;
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::refinance_obligation_debt(
+                &id(),
+                amount,
+                &self.obligation_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &new_liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &new_pair_info.pair_pubkey,
+                swap_source,
+                swap_destination,
                 &liquidity_info.token_account.pubkey(),
+                &liquidity_info.token_mint.pubkey(),
+                &new_liquidity_info.token_account.pubkey(),
+                &new_liquidity_info.token_mint.pubkey(),
                 &market_info.market.pubkey(),
                 &self.owner.pubkey(),
+                &spl_token::id(),
+                &swap::id(),
+                None,
+                None,
+                insurance_account.as_ref(),
+                &swap_pool_info.accounts(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn transfer(
+        &self,
+        context: &mut ProgramTestContext,
+        new_owner: &Pubkey,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::transfer_obligation(
+                &id(),
+                &self.obligation_pubkey,
+                &self.owner.pubkey(),
+                new_owner,
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn tokenize(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        position_mint: &Keypair,
+        position_token_account: &Keypair,
+    ) -> transport::Result<()> {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::create_account(
+                    &context.payer.pubkey(),
+                    &position_mint.pubkey(),
+                    rent.minimum_balance(spl_token::state::Mint::LEN),
+                    spl_token::state::Mint::LEN as u64,
+                    &spl_token::id(),
+                ),
+                system_instruction::create_account(
+                    &context.payer.pubkey(),
+                    &position_token_account.pubkey(),
+                    rent.minimum_balance(spl_token::state::Account::LEN),
+                    spl_token::state::Account::LEN as u64,
+                    &spl_token::id(),
+                ),
+                instruction::tokenize_obligation(
+                    &id(),
+                    &self.obligation_pubkey,
+                    &position_mint.pubkey(),
+                    &position_token_account.pubkey(),
+                    &market_info.market.pubkey(),
+                    &self.owner.pubkey(),
+                    &spl_token::id(),
+                )
+                .unwrap(),
+            ],
+            Some(&context.payer.pubkey()),
+            &[
+                &context.payer,
+                position_mint,
+                position_token_account,
+                &self.owner,
+            ],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn claim(
+        &self,
+        context: &mut ProgramTestContext,
+        position_token_account: &Pubkey,
+        holder: &Keypair,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::claim_obligation(
+                &id(),
+                &self.obligation_pubkey,
+                position_token_account,
+                &holder.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, holder],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn set_operator(
+        &self,
+        context: &mut ProgramTestContext,
+        operator: &Pubkey,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::set_obligation_operator(
+                &id(),
+                &self.obligation_pubkey,
+                &self.owner.pubkey(),
+                operator,
+                None,
             )
             .unwrap()],
             Some(&context.payer.pubkey()),
@@ -192,4 +623,25 @@ impl ObligationInfo {
 
         context.banks_client.process_transaction(tx).await
     }
+
+    pub async fn clear_operator(
+        &self,
+        context: &mut ProgramTestContext,
+        authority: &Keypair,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::clear_obligation_operator(
+                &id(),
+                &self.obligation_pubkey,
+                &authority.pubkey(),
+                None,
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, authority],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
 }
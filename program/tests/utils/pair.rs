@@ -0,0 +1,98 @@
+use super::{
+    collateral::{self, CollateralInfo},
+    get_account,
+    liquidity::LiquidityInfo,
+    market::MarketInfo,
+};
+use everlend_lending::{
+    find_pair_authority, id, instruction,
+    state::{Pair, PairConfig},
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{signature::Signer, transaction::Transaction, transport};
+
+pub const CONFIG: PairConfig = PairConfig {
+    borrow_enabled: true,
+    ratio_initial: collateral::RATIO_INITIAL,
+    ratio_healthy: collateral::RATIO_HEALTHY,
+};
+
+#[derive(Debug)]
+pub struct PairInfo {
+    pub pair_pubkey: Pubkey,
+}
+
+impl PairInfo {
+    pub fn new(
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+    ) -> Self {
+        let (pair_authority, _) = find_pair_authority(
+            &everlend_lending::id(),
+            &market_info.market.pubkey(),
+            &liquidity_info.liquidity_pubkey,
+            &collateral_info.collateral_pubkey,
+        );
+
+        Self {
+            pair_pubkey: Pubkey::create_with_seed(&pair_authority, "pair", &id()).unwrap(),
+        }
+    }
+
+    pub async fn get_data(&self, context: &mut ProgramTestContext) -> Pair {
+        let pair_account = get_account(context, &self.pair_pubkey).await;
+        Pair::unpack_unchecked(&pair_account.data).unwrap()
+    }
+
+    pub async fn create(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        collateral_info: &CollateralInfo,
+        config: PairConfig,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::create_pair(
+                &id(),
+                config,
+                &self.pair_pubkey,
+                &liquidity_info.liquidity_pubkey,
+                &collateral_info.collateral_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn update(
+        &self,
+        context: &mut ProgramTestContext,
+        config: PairConfig,
+        market_info: &MarketInfo,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_pair(
+                &id(),
+                config,
+                &self.pair_pubkey,
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &market_info.owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+}
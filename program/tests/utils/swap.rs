@@ -0,0 +1,147 @@
+use super::{create_token_account, mint_tokens, MarketInfo};
+use everlend_lending::swap::SwapInstruction;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    borsh::try_from_slice_unchecked,
+    entrypoint::ProgramResult,
+    instruction::AccountMeta,
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport,
+};
+
+/// Mock swap program exchanging the tokens 1:1 from the pool
+pub fn id() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+/// Mock swap program leaving the tokens where they are
+pub fn noop_id() -> Pubkey {
+    Pubkey::new_from_array([8; 32])
+}
+
+pub fn add_program(program_test: &mut ProgramTest) {
+    program_test.add_program("mock_swap", id(), processor!(process_instruction));
+    program_test.add_program("mock_noop_swap", noop_id(), processor!(process_noop));
+}
+
+fn find_pool_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pool"], program_id)
+}
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let SwapInstruction::Swap { amount_in, .. } = try_from_slice_unchecked(input)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let source_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let pool_source_info = next_account_info(account_info_iter)?;
+    let pool_destination_info = next_account_info(account_info_iter)?;
+    let pool_authority_info = next_account_info(account_info_iter)?;
+
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program_info.key,
+            source_info.key,
+            pool_source_info.key,
+            user_transfer_authority_info.key,
+            &[],
+            amount_in,
+        )?,
+        &[
+            source_info.clone(),
+            pool_source_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+
+    // Minimum amount out is left to the caller to check
+    let (_, bump_seed) = find_pool_authority(program_id);
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program_info.key,
+            pool_destination_info.key,
+            destination_info.key,
+            pool_authority_info.key,
+            &[],
+            amount_in,
+        )?,
+        &[
+            pool_destination_info.clone(),
+            destination_info.clone(),
+            pool_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&[b"pool", &[bump_seed]]],
+    )
+}
+
+fn process_noop(_program_id: &Pubkey, _accounts: &[AccountInfo], _input: &[u8]) -> ProgramResult {
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct SwapPoolInfo {
+    pub pool_source: Keypair,
+    pub pool_destination: Keypair,
+}
+
+impl SwapPoolInfo {
+    pub fn new() -> Self {
+        Self {
+            pool_source: Keypair::new(),
+            pool_destination: Keypair::new(),
+        }
+    }
+
+    /// Pool receiving the source tokens and paying out the destination tokens
+    pub async fn create(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        source_mint: &Pubkey,
+        destination_mint: &Pubkey,
+        amount: u64,
+    ) -> transport::Result<()> {
+        let (pool_authority, _) = find_pool_authority(&id());
+
+        create_token_account(context, &self.pool_source, source_mint, &pool_authority).await?;
+        create_token_account(
+            context,
+            &self.pool_destination,
+            destination_mint,
+            &pool_authority,
+        )
+        .await?;
+
+        mint_tokens(
+            context,
+            destination_mint,
+            &self.pool_destination.pubkey(),
+            &market_info.owner,
+            amount,
+        )
+        .await
+    }
+
+    pub fn accounts(&self) -> Vec<AccountMeta> {
+        let (pool_authority, _) = find_pool_authority(&id());
+
+        vec![
+            AccountMeta::new(self.pool_source.pubkey(), false),
+            AccountMeta::new(self.pool_destination.pubkey(), false),
+            AccountMeta::new_readonly(pool_authority, false),
+        ]
+    }
+}
@@ -0,0 +1,231 @@
+use super::{liquidity::LiquidityInfo, market::MarketInfo};
+use everlend_lending::{id, instruction, token::spl_token_2022};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    transport,
+};
+
+/// Length of a mint with the transfer fee config extension
+pub const TRANSFER_FEE_MINT_LEN: usize = spl_token::state::Account::LEN + 1 + 4 + 108;
+
+/// Length of a token account with the transfer fee amount extension
+pub const TRANSFER_FEE_ACCOUNT_LEN: usize = spl_token::state::Account::LEN + 1 + 4 + 8;
+
+/// Program test with the Token-2022 program loaded from `tests/fixtures/spl_token_2022.so`,
+/// dumped with `solana program dump TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb`
+pub fn program_test_with_token_2022() -> ProgramTest {
+    let mut program_test = super::program_test();
+    program_test.add_program("spl_token_2022", spl_token_2022::id(), None);
+    program_test
+}
+
+/// Instructions of SPL Token are compatible with Token-2022, only the program id differs
+fn token_2022_instruction(mut ix: Instruction) -> Instruction {
+    ix.program_id = spl_token_2022::id();
+    ix
+}
+
+fn initialize_transfer_fee_config(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) -> Instruction {
+    // TransferFeeExtension, InitializeTransferFeeConfig
+    let mut data = vec![26, 0];
+    data.push(1);
+    data.extend_from_slice(authority.as_ref());
+    data.push(1);
+    data.extend_from_slice(authority.as_ref());
+    data.extend_from_slice(&transfer_fee_basis_points.to_le_bytes());
+    data.extend_from_slice(&maximum_fee.to_le_bytes());
+
+    Instruction {
+        program_id: spl_token_2022::id(),
+        accounts: vec![AccountMeta::new(*mint, false)],
+        data,
+    }
+}
+
+pub async fn create_transfer_fee_mint(
+    context: &mut ProgramTestContext,
+    mint: &Keypair,
+    manager: &Pubkey,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) -> transport::Result<()> {
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(TRANSFER_FEE_MINT_LEN),
+                TRANSFER_FEE_MINT_LEN as u64,
+                &spl_token_2022::id(),
+            ),
+            initialize_transfer_fee_config(
+                &mint.pubkey(),
+                manager,
+                transfer_fee_basis_points,
+                maximum_fee,
+            ),
+            token_2022_instruction(
+                spl_token::instruction::initialize_mint(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    manager,
+                    None,
+                    0,
+                )
+                .unwrap(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, mint],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn create_token_2022_account(
+    context: &mut ProgramTestContext,
+    account: &Keypair,
+    mint: &Pubkey,
+    manager: &Pubkey,
+    len: usize,
+) -> transport::Result<()> {
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(len),
+                len as u64,
+                &spl_token_2022::id(),
+            ),
+            token_2022_instruction(
+                spl_token::instruction::initialize_account(
+                    &spl_token::id(),
+                    &account.pubkey(),
+                    mint,
+                    manager,
+                )
+                .unwrap(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, account],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn mint_token_2022(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    account: &Pubkey,
+    mint_authority: &Keypair,
+    amount: u64,
+) -> transport::Result<()> {
+    let tx = Transaction::new_signed_with_payer(
+        &[token_2022_instruction(
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint,
+                account,
+                &mint_authority.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, mint_authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+/// Create liquidity for a Token-2022 mint with a transfer fee
+pub async fn create_transfer_fee_liquidity(
+    context: &mut ProgramTestContext,
+    market_info: &MarketInfo,
+    transfer_fee_basis_points: u16,
+) -> transport::Result<LiquidityInfo> {
+    let liquidity_tokens = market_info.get_data(context).await.liquidity_tokens;
+    let liquidity_info =
+        LiquidityInfo::new(&format!("liquidity{:?}", liquidity_tokens), market_info);
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    create_transfer_fee_mint(
+        context,
+        &liquidity_info.token_mint,
+        &market_info.owner.pubkey(),
+        transfer_fee_basis_points,
+        u64::MAX,
+    )
+    .await?;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            // Transfer a few lamports to cover fee for create account
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &market_info.owner.pubkey(),
+                999999999,
+            ),
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &liquidity_info.token_account.pubkey(),
+                rent.minimum_balance(TRANSFER_FEE_ACCOUNT_LEN),
+                TRANSFER_FEE_ACCOUNT_LEN as u64,
+                &spl_token_2022::id(),
+            ),
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &liquidity_info.pool_mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token_2022::id(),
+            ),
+            instruction::create_liquidity_token(
+                &id(),
+                &liquidity_info.liquidity_pubkey,
+                &liquidity_info.token_mint.pubkey(),
+                &liquidity_info.token_account.pubkey(),
+                &liquidity_info.pool_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &market_info.owner.pubkey(),
+                &spl_token_2022::id(),
+            )
+            .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[
+            &context.payer,
+            &liquidity_info.token_account,
+            &liquidity_info.pool_mint,
+            &market_info.owner,
+        ],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await?;
+
+    Ok(liquidity_info)
+}
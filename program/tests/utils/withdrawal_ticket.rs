@@ -0,0 +1,98 @@
+use super::{get_account, liquidity::LiquidityInfo, market::MarketInfo};
+use everlend_lending::{id, instruction, state::WithdrawalTicket};
+use solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    transport,
+};
+
+#[derive(Debug)]
+pub struct WithdrawalTicketInfo {
+    pub withdrawal_ticket: Keypair,
+}
+
+impl WithdrawalTicketInfo {
+    pub fn new() -> Self {
+        Self {
+            withdrawal_ticket: Keypair::new(),
+        }
+    }
+
+    pub async fn get_data(&self, context: &mut ProgramTestContext) -> WithdrawalTicket {
+        let withdrawal_ticket_account =
+            get_account(context, &self.withdrawal_ticket.pubkey()).await;
+        WithdrawalTicket::unpack_unchecked(&withdrawal_ticket_account.data).unwrap()
+    }
+
+    pub async fn request(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        source: &Pubkey,
+        amount: u64,
+        provider: &Keypair,
+    ) -> transport::Result<()> {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::create_account(
+                    &context.payer.pubkey(),
+                    &self.withdrawal_ticket.pubkey(),
+                    rent.minimum_balance(WithdrawalTicket::LEN),
+                    WithdrawalTicket::LEN as u64,
+                    &id(),
+                ),
+                instruction::liquidity_withdraw_request(
+                    &id(),
+                    amount,
+                    &self.withdrawal_ticket.pubkey(),
+                    &liquidity_info.liquidity_pubkey,
+                    source,
+                    &liquidity_info.token_account.pubkey(),
+                    &liquidity_info.pool_mint.pubkey(),
+                    &market_info.market.pubkey(),
+                    &provider.pubkey(),
+                    &spl_token::id(),
+                )
+                .unwrap(),
+            ],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &self.withdrawal_ticket, provider],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn claim(
+        &self,
+        context: &mut ProgramTestContext,
+        market_info: &MarketInfo,
+        liquidity_info: &LiquidityInfo,
+        destination: &Pubkey,
+        owner: &Keypair,
+    ) -> transport::Result<()> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::liquidity_withdraw_claim(
+                &id(),
+                &self.withdrawal_ticket.pubkey(),
+                &liquidity_info.liquidity_pubkey,
+                destination,
+                &liquidity_info.token_account.pubkey(),
+                &liquidity_info.token_mint.pubkey(),
+                &market_info.market.pubkey(),
+                &owner.pubkey(),
+                &spl_token::id(),
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, owner],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+}
@@ -0,0 +1,330 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use everlend_lending::{
+    error::LendingError,
+    state::{CollateralStatus, LiquidityStatus},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError, transport};
+use utils::*;
+
+const LIQUIDITY_AMOUNT: u64 = 10000;
+const COLLATERAL_AMOUNT: u64 = 2 * LIQUIDITY_AMOUNT;
+const BORROW_AMOUNT: u64 = LIQUIDITY_AMOUNT * 8 / 10;
+
+struct Env {
+    context: ProgramTestContext,
+    market_info: MarketInfo,
+    liquidity_info: LiquidityInfo,
+    collateral_info: CollateralInfo,
+    provider_actor: ProviderActor,
+    provider_liquidity: Keypair,
+    provider_pool: Keypair,
+    obligation_info: ObligationInfo,
+    borrower_liquidity: Keypair,
+}
+
+/// Liquidity with the most of it borrowed
+async fn setup() -> Env {
+    let mut context = program_test().start_with_context().await;
+
+    let market_info = MarketInfo::new();
+    market_info.init(&mut context).await.unwrap();
+
+    let liquidity_info = market_info
+        .create_liquidity_token(&mut context)
+        .await
+        .unwrap();
+
+    let collateral_info = market_info
+        .create_collateral_token(&mut context)
+        .await
+        .unwrap();
+
+    liquidity_info
+        .update(&mut context, LiquidityStatus::Active, &market_info)
+        .await
+        .unwrap();
+
+    collateral_info
+        .update(
+            &mut context,
+            CollateralStatus::Active,
+            collateral::RATIO_INITIAL,
+            collateral::RATIO_HEALTHY,
+            &market_info,
+        )
+        .await
+        .unwrap();
+
+    let pair_info = market_info
+        .create_pair(
+            &mut context,
+            &liquidity_info,
+            &collateral_info,
+            pair::CONFIG,
+        )
+        .await
+        .unwrap();
+
+    // Deposit liquidity from provider
+    let provider_actor = ProviderActor::new();
+    let (provider_liquidity, provider_pool) = provider_actor
+        .create_liquidity_accounts(&mut context, &liquidity_info)
+        .await
+        .unwrap();
+    provider_actor
+        .deposit(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &provider_liquidity,
+            &provider_pool,
+            LIQUIDITY_AMOUNT,
+        )
+        .await;
+
+    // Borrow against the collateral
+    let obligation_info = ObligationInfo::new(&market_info);
+    obligation_info
+        .create(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+        )
+        .await
+        .unwrap();
+
+    let borrower_collateral = Keypair::new();
+    let borrower_liquidity = Keypair::new();
+
+    create_token_account(
+        &mut context,
+        &borrower_collateral,
+        &collateral_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    create_token_account(
+        &mut context,
+        &borrower_liquidity,
+        &liquidity_info.token_mint.pubkey(),
+        &obligation_info.owner.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    mint_tokens(
+        &mut context,
+        &collateral_info.token_mint.pubkey(),
+        &borrower_collateral.pubkey(),
+        &market_info.owner,
+        COLLATERAL_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    obligation_info
+        .collateral_deposit(
+            &mut context,
+            &market_info,
+            &collateral_info,
+            COLLATERAL_AMOUNT,
+            &borrower_collateral.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    obligation_info
+        .liquidity_borrow(
+            &mut context,
+            &market_info,
+            &liquidity_info,
+            &collateral_info,
+            &pair_info,
+            BORROW_AMOUNT,
+            &borrower_liquidity.pubkey(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    Env {
+        context,
+        market_info,
+        liquidity_info,
+        collateral_info,
+        provider_actor,
+        provider_liquidity,
+        provider_pool,
+        obligation_info,
+        borrower_liquidity,
+    }
+}
+
+impl Env {
+    async fn request(&mut self, amount: u64) -> WithdrawalTicketInfo {
+        let withdrawal_ticket_info = WithdrawalTicketInfo::new();
+        withdrawal_ticket_info
+            .request(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.provider_pool.pubkey(),
+                amount,
+                &self.provider_actor.owner,
+            )
+            .await
+            .unwrap();
+
+        withdrawal_ticket_info
+    }
+
+    async fn claim(
+        &mut self,
+        withdrawal_ticket_info: &WithdrawalTicketInfo,
+    ) -> transport::Result<()> {
+        withdrawal_ticket_info
+            .claim(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.provider_liquidity.pubkey(),
+                &self.provider_actor.owner,
+            )
+            .await
+    }
+
+    async fn repay(&mut self, amount: u64) {
+        self.obligation_info
+            .liquidity_repay(
+                &mut self.context,
+                &self.market_info,
+                &self.liquidity_info,
+                &self.collateral_info,
+                amount,
+                &self.borrower_liquidity.pubkey(),
+            )
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn success() {
+    let mut env = setup().await;
+
+    let withdrawal_ticket_info = env.request(u64::MAX).await;
+    let withdrawal_ticket = withdrawal_ticket_info.get_data(&mut env.context).await;
+    assert_eq!(withdrawal_ticket.amount, LIQUIDITY_AMOUNT);
+    assert_eq!(withdrawal_ticket.pool_tokens_amount, LIQUIDITY_AMOUNT);
+    assert_eq!(withdrawal_ticket.queue_offset, 0);
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.provider_pool.pubkey()).await,
+        0
+    );
+
+    // Repayment fills the ticket
+    env.repay(BORROW_AMOUNT).await;
+    env.claim(&withdrawal_ticket_info).await.unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.provider_liquidity.pubkey()).await,
+        LIQUIDITY_AMOUNT
+    );
+    assert!(env
+        .context
+        .banks_client
+        .get_account(withdrawal_ticket_info.withdrawal_ticket.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+
+    let liquidity = env.liquidity_info.get_data(&mut env.context).await;
+    assert_eq!(liquidity.queue_amount_requested, LIQUIDITY_AMOUNT);
+    assert_eq!(liquidity.queue_amount_claimed, LIQUIDITY_AMOUNT);
+}
+
+#[tokio::test]
+async fn success_fifo() {
+    let mut env = setup().await;
+
+    let first_ticket_info = env.request(LIQUIDITY_AMOUNT / 2).await;
+    let second_ticket_info = env.request(LIQUIDITY_AMOUNT / 2).await;
+    assert_eq!(
+        second_ticket_info
+            .get_data(&mut env.context)
+            .await
+            .queue_offset,
+        LIQUIDITY_AMOUNT / 2
+    );
+
+    // Only the first ticket is filled by the partial repayment
+    env.repay(BORROW_AMOUNT / 2).await;
+
+    assert_eq!(
+        env.claim(&second_ticket_info).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::WithdrawalTicketNotFilled as u32)
+        )
+    );
+
+    env.claim(&first_ticket_info).await.unwrap();
+    assert_eq!(
+        get_token_balance(&mut env.context, &env.provider_liquidity.pubkey()).await,
+        LIQUIDITY_AMOUNT / 2
+    );
+}
+
+#[tokio::test]
+async fn fail_claim_not_filled() {
+    let mut env = setup().await;
+
+    let withdrawal_ticket_info = env.request(u64::MAX).await;
+
+    assert_eq!(
+        env.claim(&withdrawal_ticket_info)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::WithdrawalTicketNotFilled as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_withdraw_reserved_for_queue() {
+    let mut env = setup().await;
+
+    // The ticket reserves more than the liquidity left in the token account
+    env.request(LIQUIDITY_AMOUNT / 2).await;
+
+    assert_eq!(
+        env.liquidity_info
+            .withdraw(
+                &mut env.context,
+                &env.market_info,
+                &env.provider_pool.pubkey(),
+                &env.provider_liquidity.pubkey(),
+                1,
+                &env.provider_actor.owner,
+            )
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::UtilizationExceeded as u32)
+        )
+    );
+}